@@ -0,0 +1,45 @@
+//! Copies the entries of an archive written by the CLI whose names contain a
+//! filter into a new archive, streaming each entry from one to the other.
+//!
+//! ```bash
+//! cargo run --release --example archive_format -- mock_logs/all_logs.zst filtered.zst log_1
+//! ```
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use anyhow::{Context, Result};
+use gz_vs_zstd::archive_format::{ArchiveReader, ArchiveWriter};
+use gz_vs_zstd::format_bytes;
+
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let usage = "usage: archive_format INPUT OUTPUT [FILTER]";
+    let input = args.next().context(usage)?;
+    let output = args.next().context(usage)?;
+    let filter = args.next().unwrap_or_default();
+
+    let reader = ArchiveReader::new(File::open(&input).with_context(|| format!("failed to open {}", input))?)?;
+    let mut writer = ArchiveWriter::new(BufWriter::new(File::create(&output)?), 3)?;
+    let (mut kept, mut skipped, mut bytes) = (0, 0, 0);
+    for entry in reader {
+        let mut entry = entry?;
+        if !entry.name.contains(&filter) {
+            // Unread content is skipped, and its checksum still checked, by the next call.
+            skipped += 1;
+            continue;
+        }
+        if entry.is_symlink {
+            let mut target = String::new();
+            entry.read_to_string(&mut target)?;
+            writer.add_symlink(&entry.name, &target)?;
+        } else {
+            let (name, len) = (entry.name.clone(), entry.len);
+            writer.add_sized_entry(&name, len, &mut entry)?;
+            bytes += len;
+        }
+        kept += 1;
+    }
+    writer.finish()?.flush()?;
+    println!("Kept {} entries ({}), skipped {}; wrote {}", kept, format_bytes(bytes), skipped, output);
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Benchmarks codecs defined outside the crate next to the built-in ones, on the
+//! same generated corpus and with the same harness and reporting as the CLI.
+//!
+//! ```bash
+//! cargo run --release --example custom_codec -- 1000
+//! ```
+
+use std::io::{self, Read, Write};
+use anyhow::Result;
+use gz_vs_zstd::codec::{Codec, Encoder, Gzip, Zstd};
+use gz_vs_zstd::results::{CorpusInfo, Results, Stage};
+use gz_vs_zstd::{bench, chart, corpus, format_bytes, render};
+
+/// Stores the data as is, the floor every real codec should beat.
+struct Store;
+
+/// Any `Write` can be a codec's encoder once it says how to finish the stream.
+struct Passthrough<'a>(&'a mut dyn Write);
+
+impl Write for Passthrough<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Encoder for Passthrough<'_> {
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        self.flush()?;
+        Ok(())
+    }
+}
+
+impl Codec for Store {
+    fn name(&self) -> String {
+        "store".to_string()
+    }
+
+    fn extension(&self) -> &'static str {
+        "raw"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        Ok(Box::new(Passthrough(output)))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(input))
+    }
+}
+
+/// zstd with long-distance matching over a larger window, configured by hand
+/// on the library's own streams.
+struct ZstdLong {
+    level: i32,
+    window_log: u32,
+}
+
+impl Codec for ZstdLong {
+    fn name(&self) -> String {
+        format!("zstd-long-{}", self.level)
+    }
+
+    fn extension(&self) -> &'static str {
+        "zst"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        let mut encoder = zstd::Encoder::new(output, self.level)?;
+        encoder.long_distance_matching(true)?;
+        encoder.window_log(self.window_log)?;
+        Ok(Box::new(encoder))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        let mut decoder = zstd::Decoder::new(input)?;
+        decoder.window_log_max(self.window_log)?;
+        Ok(Box::new(decoder))
+    }
+}
+
+fn main() -> Result<()> {
+    let count = match std::env::args().nth(1) {
+        Some(count) => count.parse()?,
+        None => 1000,
+    };
+    let files = corpus::generate(count)?;
+    let json_size: u64 = files.iter().map(|file| file.len() as u64).sum();
+    println!("Corpus: {} generated files, {}", count, format_bytes(json_size));
+
+    let codecs: [Box<dyn Codec>; 4] = [
+        Box::new(Store),
+        Box::new(Gzip { level: 6 }),
+        Box::new(Zstd { level: 3 }),
+        Box::new(ZstdLong { level: 3, window_log: 24 }),
+    ];
+    let mut results = Results::new(CorpusInfo {
+        files: count,
+        bytes: json_size,
+        generation_secs: 0.0,
+        skipped: Vec::new(),
+        sampled_from: None,
+        stats: None,
+    });
+
+    println!("  {:<14} {:>12} {:>9} {:>14} {:>14}", "Codec", "Size", "Ratio", "Compress", "Decompress");
+    for codec in &codecs {
+        // Compresses each file on its own, then decompresses and verifies it.
+        let measurement = bench::measure(codec.as_ref(), &files)?;
+        println!(
+            "  {:<14} {:>12} {:>8.2}% {:>14} {:>14}",
+            codec.name(),
+            format_bytes(measurement.compressed_size),
+            measurement.compressed_size as f64 / json_size as f64 * 100.0,
+            render::mb_per_sec(bench::throughput(json_size, measurement.compress_time)),
+            render::mb_per_sec(bench::throughput(json_size, measurement.decompress_time))
+        );
+        let compressed = measurement.compressed_size;
+        results.stages.push(Stage::new("per_file_compress", &codec.name(), json_size, compressed, measurement.compress_time));
+        results.stages.push(Stage::new("per_file_decompress", &codec.name(), compressed, json_size, measurement.decompress_time));
+    }
+
+    // The same charts as `--chart`; `results.write` would give `--json` output.
+    chart::print(&results);
+    Ok(())
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Instant;
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use memmap2::Mmap;
+use crate::latency;
+use crate::results::Dedup;
+use gz_vs_zstd::archive_format::{
+    read_header, verify_checksum, write_flagged_entry, write_header, ArchiveReader, Checksummed, CHECKSUM_FLAG, REFERENCE_FLAG, SHARED_FLAG,
+    SYMLINK_FLAG,
+};
+use twox_hash::XxHash3_64;
+
+/// Decompressed bytes requested from zstd at a time by the memory-mapped reader.
+const MAPPED_CHUNK: usize = 1 << 20;
+
+/// A symbolic link stored as a link rather than as the file it points to.
+pub struct Symlink {
+    pub name: String,
+    /// Relative target, exactly as it will be recreated on extraction.
+    pub target: String,
+}
+
+/// How `write_archive` compresses.
+#[derive(Clone, Copy)]
+pub struct Options<'a> {
+    pub level: i32,
+    /// With `workers` above zero, zstd compresses on that many background threads.
+    pub workers: u32,
+    /// Store files whose content was already stored as references to the first copy.
+    pub dedup: bool,
+    /// Compress against this zstd dictionary; its ID goes into the frame header.
+    pub dict: Option<&'a [u8]>,
+}
+
+impl Options<'_> {
+    pub fn level(level: i32) -> Self {
+        Options { level, workers: 0, dedup: false, dict: None }
+    }
+}
+
+/// BLAKE3 of a file, hashed on all cores when it is large enough to split.
+fn content_hash(path: &Path) -> io::Result<blake3::Hash> {
+    Ok(blake3::Hasher::new().update_mmap_rayon(path)?.finalize())
+}
+
+/// Compresses `names` (relative to `dir`) and `links` into a single zstd stream at `archive_path`.
+///
+/// Each entry is laid out as a little-endian `u32` name length, the name bytes,
+/// a `u32` content length, the raw content and its `u64` xxh3 checksum, which
+/// `CHECKSUM_FLAG` in the name length announces. Symlink entries have
+/// `SYMLINK_FLAG` set in the name length and the link target as content.
+///
+/// With `options.dedup`, every file is hashed with BLAKE3 first. The first copy of repeated
+/// content is marked `SHARED_FLAG` and later copies become `REFERENCE_FLAG`
+/// entries holding the first copy's name, so the content is compressed once.
+pub fn write_archive(
+    dir: &Path,
+    names: &[String],
+    links: &[Symlink],
+    archive_path: &Path,
+    options: Options<'_>,
+    pb: &ProgressBar,
+) -> Result<Dedup> {
+    let writer = BufWriter::new(latency::create(archive_path)?);
+    let mut zstd_encoder = zstd::Encoder::with_dictionary(writer, options.level, options.dict.unwrap_or_default())?;
+    if options.workers > 0 {
+        zstd_encoder.multithread(options.workers)?;
+    }
+
+    let start = Instant::now();
+    let hashes = if options.dedup {
+        names.iter().map(|name| content_hash(&dir.join(name))).collect::<io::Result<Vec<_>>>()?
+    } else {
+        Vec::new()
+    };
+    let mut dedup = Dedup { hash_secs: start.elapsed().as_secs_f64(), ..Dedup::default() };
+    let mut copies: HashMap<&blake3::Hash, usize> = HashMap::new();
+    for hash in &hashes {
+        *copies.entry(hash).or_default() += 1;
+    }
+    let mut stored: HashMap<&blake3::Hash, &str> = HashMap::new();
+
+    for (i, name) in names.iter().enumerate() {
+        let mut flags = 0;
+        if let Some(hash) = hashes.get(i).filter(|hash| copies[hash] > 1) {
+            if let Some(original) = stored.get(hash) {
+                write_flagged_entry(&mut zstd_encoder, name, REFERENCE_FLAG, original.as_bytes())?;
+                dedup.references += 1;
+                dedup.saved_bytes += fs::metadata(dir.join(name))?.len();
+                pb.inc(1);
+                continue;
+            }
+            stored.insert(hash, name);
+            flags = SHARED_FLAG;
+        }
+
+        let input_file = latency::open(dir.join(name))?;
+
+        // Write the header, which rejects names and files too large for the format,
+        // then the file content and its checksum
+        let file_size = input_file.get_ref().metadata()?.len();
+        write_header(&mut zstd_encoder, name, flags | CHECKSUM_FLAG, file_size)?;
+        let mut checksummed = Checksummed { inner: input_file.take(file_size), hasher: XxHash3_64::new() };
+        let copied = std::io::copy(&mut checksummed, &mut zstd_encoder)?;
+        anyhow::ensure!(copied == file_size, "{} changed size while it was archived", name);
+        zstd_encoder.write_all(&checksummed.hasher.finish().to_le_bytes())?;
+
+        pb.inc(1);
+    }
+
+    for link in links {
+        write_flagged_entry(&mut zstd_encoder, &link.name, SYMLINK_FLAG, link.target.as_bytes())?;
+    }
+
+    zstd_encoder.finish()?.flush()?;
+    Ok(dedup)
+}
+
+/// Writes one regular-file entry in the layout described on `write_archive`.
+pub fn write_entry(writer: &mut impl Write, name: &str, content: &[u8]) -> io::Result<()> {
+    write_flagged_entry(writer, name, 0, content)
+}
+
+/// Header of one archive entry, as seen while streaming.
+pub struct Entry {
+    pub name: String,
+    pub is_symlink: bool,
+    pub len: u64,
+}
+
+/// Fails with a clear message unless `dict` is the dictionary the archive at
+/// `archive_path` was compressed with, going by the IDs in the frame header.
+fn check_dictionary(archive_path: &Path, dict: Option<&[u8]>) -> Result<()> {
+    // A frame header is at most 18 bytes.
+    let mut header = Vec::with_capacity(18);
+    File::open(archive_path)?.take(18).read_to_end(&mut header)?;
+    let needed = zstd::zstd_safe::get_dict_id_from_frame(&header);
+    let given = dict.and_then(zstd::zstd_safe::get_dict_id_from_dict);
+    match (needed, given) {
+        (Some(needed), None) => anyhow::bail!(
+            "{} was compressed with zstd dictionary {}; pass it with --dict",
+            archive_path.display(),
+            needed
+        ),
+        (Some(needed), Some(given)) if needed != given => anyhow::bail!(
+            "{} was compressed with zstd dictionary {}, but --dict has ID {}",
+            archive_path.display(),
+            needed,
+            given
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Streams the archive at `archive_path`, calling `f` with each entry's header and content.
+/// `dict` is the dictionary the archive was written with, if any.
+///
+/// Content is never buffered in full, except to check its checksum before `f`
+/// sees it when `verify` is set; whatever `f` leaves unread is skipped.
+pub fn for_each_entry(
+    archive_path: &Path,
+    dict: Option<&[u8]>,
+    verify: bool,
+    f: impl FnMut(&Entry, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    check_dictionary(archive_path, dict)?;
+    let reader = ArchiveReader::with_dictionary(latency::open(archive_path)?, dict.unwrap_or_default())?;
+    read_entries(reader, verify, f)
+}
+
+/// Like `for_each_entry`, over an archive reader set up by the caller.
+///
+/// Reference entries are resolved by the reader to the content of the shared
+/// entry they name, which it keeps in memory for the rest of the stream.
+pub fn read_entries<R: BufRead>(
+    reader: ArchiveReader<R>,
+    verify: bool,
+    mut f: impl FnMut(&Entry, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    let reader = if verify { reader } else { reader.skip_checksums() };
+    let mut data = Vec::new();
+    for entry in reader {
+        let mut entry = entry?;
+        let header = Entry { name: entry.name.clone(), is_symlink: entry.is_symlink, len: entry.len };
+        if verify {
+            // Reading the content to its end checks its checksum.
+            data.clear();
+            entry.read_to_end(&mut data)?;
+            f(&header, &mut data.as_slice())?;
+        } else {
+            f(&header, &mut entry)?;
+        }
+    }
+    Ok(())
+}
+
+/// One entry borrowed straight from the memory-mapped reader's decompression buffer.
+pub struct EntryRef<'a> {
+    pub name: &'a str,
+    pub is_symlink: bool,
+    pub content: &'a [u8],
+}
+
+/// An entry parsed out of the memory-mapped reader's buffer, before references are resolved.
+struct Parsed<'a> {
+    entry: EntryRef<'a>,
+    /// Flags of the entry's name length.
+    flags: u32,
+    checksum: Option<u64>,
+    /// Bytes the entry takes up in the buffer.
+    len: usize,
+}
+
+/// Parses the entry at the start of `data` without copying its content, or returns
+/// `None` when `data` ends before the entry does.
+fn parse_entry(data: &[u8]) -> Result<Option<Parsed<'_>>> {
+    let mut rest = data;
+    let header = match read_header(&mut rest) {
+        Ok(Some(header)) => header,
+        Ok(None) => return Ok(None),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let content_start = data.len() - rest.len();
+    let content_end = content_start + header.len as usize;
+    let checksum_len = if header.flags & CHECKSUM_FLAG != 0 { 8 } else { 0 };
+    if data.len() < content_end + checksum_len {
+        return Ok(None);
+    }
+    // The name sits right before the content length; `read_header` has checked it is UTF-8.
+    let name = std::str::from_utf8(&data[content_start - 4 - header.name.len()..content_start - 4])?;
+    let entry = EntryRef { name, is_symlink: header.flags & SYMLINK_FLAG != 0, content: &data[content_start..content_end] };
+    let checksum = (checksum_len > 0).then(|| u64::from_le_bytes(data[content_end..content_end + 8].try_into().expect("eight bytes")));
+    Ok(Some(Parsed { entry, flags: header.flags, checksum, len: content_end + checksum_len }))
+}
+
+/// Like `for_each_entry`, but reads the compressed archive through a memory map and
+/// hands `f` the name and content as slices of the decompression buffer, so
+/// nothing is copied after decompression. The buffer grows to the largest entry.
+/// Only shared entries are copied, so that later references can borrow them.
+/// With `verify`, each entry's checksum is checked before `f` sees it.
+pub fn for_each_entry_mapped(
+    archive_path: &Path,
+    dict: Option<&[u8]>,
+    verify: bool,
+    mut f: impl FnMut(EntryRef<'_>) -> Result<()>,
+) -> Result<()> {
+    check_dictionary(archive_path, dict)?;
+    let file = File::open(archive_path)?;
+    // SAFETY: the archive is only read. Truncating it from another process while it
+    // is mapped would fault, which is the usual caveat of reading through mmap.
+    let map = unsafe { Mmap::map(&file)? };
+    let mut decoder = zstd::stream::read::Decoder::with_dictionary(&map[..], dict.unwrap_or_default())?;
+    let mut buf = vec![0; MAPPED_CHUNK];
+    let (mut start, mut end) = (0, 0);
+    let mut shared: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut index = 0;
+
+    loop {
+        while let Some(Parsed { entry, flags, checksum, len }) = parse_entry(&buf[start..end])? {
+            index += 1;
+            if let (Some(stored), true) = (checksum, verify) {
+                verify_checksum(index, entry.name, entry.content, stored)?;
+            }
+            if flags & REFERENCE_FLAG != 0 {
+                let original = std::str::from_utf8(entry.content).context("archive reference is not UTF-8")?;
+                let content = shared.get(original).with_context(|| format!("{} refers to unknown entry {}", entry.name, original))?;
+                f(EntryRef { name: entry.name, is_symlink: false, content })?;
+            } else {
+                if flags & SHARED_FLAG != 0 {
+                    shared.insert(entry.name.to_string(), entry.content.to_vec());
+                }
+                f(entry)?;
+            }
+            start += len;
+        }
+
+        // Keep the partial entry, make room for more of it, then decompress more. The
+        // buffer only grows as data arrives, never to a length read from the archive.
+        buf.copy_within(start..end, 0);
+        end -= start;
+        start = 0;
+        if buf.len() - end < MAPPED_CHUNK {
+            buf.resize(end + MAPPED_CHUNK, 0);
+        }
+        match decoder.read(&mut buf[end..])? {
+            0 => {
+                anyhow::ensure!(end == 0, "truncated archive entry");
+                return Ok(());
+            }
+            n => end += n,
+        }
+    }
+}
@@ -0,0 +1,464 @@
+//! The entry layout of the CLI's multi-file zstd archives, with a streaming
+//! [`ArchiveWriter`] and [`ArchiveReader`] so other programs can produce and
+//! consume the format directly.
+//!
+//! An archive is one zstd stream of entries. Each entry is a little-endian
+//! `u32` name length, the name bytes, a `u32` content length, the content and,
+//! when `CHECKSUM_FLAG` is set in the name length, the content's little-endian
+//! `u64` xxh3 checksum. The top bits of the name length carry the flags below.
+//!
+//! The reader hands out each entry as it comes, reading its content straight
+//! from the decompressor, and verifies checksums as the content is read. Only
+//! entries that later references point to are held in memory.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::rc::Rc;
+use twox_hash::XxHash3_64;
+
+/// Set on an entry's name length to mark it as a symlink whose content is the link target.
+pub const SYMLINK_FLAG: u32 = 1 << 31;
+
+/// Set on an entry whose content is the name of an earlier entry with identical content.
+pub const REFERENCE_FLAG: u32 = 1 << 30;
+
+/// Set on an entry that later reference entries point to, so readers keep its content.
+pub const SHARED_FLAG: u32 = 1 << 29;
+
+/// Set on an entry whose content is followed by its little-endian xxh3 checksum.
+pub const CHECKSUM_FLAG: u32 = 1 << 28;
+
+/// Every flag bit of a name length; the rest is the length itself.
+pub const NAME_FLAGS: u32 = SYMLINK_FLAG | REFERENCE_FLAG | SHARED_FLAG | CHECKSUM_FLAG;
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Passes reads through while computing the xxh3 checksum of everything read.
+pub struct Checksummed<R> {
+    pub inner: R,
+    pub hasher: XxHash3_64,
+}
+
+impl<R> Checksummed<R> {
+    pub fn new(inner: R) -> Self {
+        Checksummed { inner, hasher: XxHash3_64::new() }
+    }
+}
+
+impl<R: Read> Read for Checksummed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.write(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Writes the header of an entry with `flags` and `len` bytes of content, which must follow.
+pub fn write_header(writer: &mut impl Write, name: &str, flags: u32, len: u64) -> io::Result<()> {
+    let name_len = u32::try_from(name.len()).ok().filter(|len| len & NAME_FLAGS == 0);
+    let name_len = name_len.ok_or_else(|| invalid(format!("entry name {} is too long", name)))?;
+    let len = u32::try_from(len).map_err(|_| invalid(format!("entry {} is larger than 4 GiB", name)))?;
+    writer.write_all(&(name_len | flags).to_le_bytes())?;
+    writer.write_all(name.as_bytes())?;
+    writer.write_all(&len.to_le_bytes())
+}
+
+/// An entry's name, flag bits and content length, as they precede its content.
+pub struct Header {
+    pub name: String,
+    /// The `NAME_FLAGS` bits of the name length.
+    pub flags: u32,
+    pub len: u64,
+}
+
+/// Reads a little-endian `u32`, or `None` when the stream ends before its first byte.
+fn read_u32(reader: &mut impl Read) -> io::Result<Option<u32>> {
+    let mut bytes = [0; 4];
+    let mut filled = 0;
+    while filled < bytes.len() {
+        match reader.read(&mut bytes[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated archive entry header")),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(Some(u32::from_le_bytes(bytes)))
+}
+
+/// Reads the next entry's header, or `None` at a clean end of stream between entries.
+/// A stream that ends anywhere inside a header fails with `UnexpectedEof`.
+pub fn read_header(reader: &mut impl Read) -> io::Result<Option<Header>> {
+    let Some(name_len) = read_u32(reader)? else {
+        return Ok(None);
+    };
+    let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "truncated archive entry header");
+    let mut name = vec![0; (name_len & !NAME_FLAGS) as usize];
+    reader.read_exact(&mut name).map_err(|e| if e.kind() == io::ErrorKind::UnexpectedEof { truncated() } else { e })?;
+    let name = String::from_utf8(name).map_err(|_| invalid("archive entry name is not UTF-8".to_string()))?;
+    let len = read_u32(reader)?.ok_or_else(truncated)?;
+    Ok(Some(Header { name, flags: name_len & NAME_FLAGS, len: len as u64 }))
+}
+
+fn checksum_mismatch(index: usize, name: &str, stored: u64, computed: u64) -> io::Error {
+    invalid(format!(
+        "checksum mismatch in archive entry #{} ({}): stored {:016x}, computed {:016x}; the archive is corrupted",
+        index, name, stored, computed
+    ))
+}
+
+/// Fails with the entry's position, counting from 1, and both checksums unless `content` hashes to `stored`.
+pub fn verify_checksum(index: usize, name: &str, content: &[u8], stored: u64) -> io::Result<()> {
+    let computed = XxHash3_64::oneshot(content);
+    if computed != stored {
+        return Err(checksum_mismatch(index, name, stored, computed));
+    }
+    Ok(())
+}
+
+/// Writes one complete checksummed entry with `flags` and `content`.
+pub fn write_flagged_entry(writer: &mut impl Write, name: &str, flags: u32, content: &[u8]) -> io::Result<()> {
+    write_header(writer, name, flags | CHECKSUM_FLAG, content.len() as u64)?;
+    writer.write_all(content)?;
+    writer.write_all(&XxHash3_64::oneshot(content).to_le_bytes())
+}
+
+/// Writes an archive entry by entry into a zstd stream.
+pub struct ArchiveWriter<W: Write> {
+    encoder: zstd::Encoder<'static, W>,
+    /// Holds the content of `add_entry`, whose length has to be known before it is written.
+    buf: Vec<u8>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    pub fn new(writer: W, level: i32) -> io::Result<Self> {
+        Ok(ArchiveWriter { encoder: zstd::Encoder::new(writer, level)?, buf: Vec::new() })
+    }
+
+    /// Compresses against a zstd dictionary, whose ID goes into the frame header.
+    pub fn with_dictionary(writer: W, level: i32, dict: &[u8]) -> io::Result<Self> {
+        Ok(ArchiveWriter { encoder: zstd::Encoder::with_dictionary(writer, level, dict)?, buf: Vec::new() })
+    }
+
+    /// Adds a file entry with everything `reader` yields.
+    ///
+    /// The content length precedes the content, so the content is buffered first;
+    /// use `add_sized_entry` to stream an entry whose length is known.
+    pub fn add_entry(&mut self, name: &str, mut reader: impl Read) -> io::Result<()> {
+        self.buf.clear();
+        reader.read_to_end(&mut self.buf)?;
+        write_flagged_entry(&mut self.encoder, name, 0, &self.buf)
+    }
+
+    /// Adds a file entry of exactly `len` bytes from `reader`, streamed without buffering.
+    pub fn add_sized_entry(&mut self, name: &str, len: u64, reader: impl Read) -> io::Result<()> {
+        write_header(&mut self.encoder, name, CHECKSUM_FLAG, len)?;
+        let mut checksummed = Checksummed::new(reader.take(len));
+        let copied = io::copy(&mut checksummed, &mut self.encoder)?;
+        if copied != len {
+            return Err(invalid(format!("{} ended after {} of {} bytes", name, copied, len)));
+        }
+        self.encoder.write_all(&checksummed.hasher.finish().to_le_bytes())
+    }
+
+    /// Adds a symbolic link to `target`, which is recreated as is on extraction.
+    pub fn add_symlink(&mut self, name: &str, target: &str) -> io::Result<()> {
+        write_flagged_entry(&mut self.encoder, name, SYMLINK_FLAG, target.as_bytes())
+    }
+
+    /// Ends the zstd stream and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.encoder.finish()
+    }
+}
+
+/// The decompressor and where in the stream it is, shared by the reader and its entries.
+struct State<R: BufRead> {
+    decoder: zstd::Decoder<'static, R>,
+    /// Position of the entry currently being read, counting from 1.
+    index: usize,
+    /// Content bytes of the current entry not read yet.
+    remaining: u64,
+    /// The current entry's checksum is still to be read and checked.
+    checksum: bool,
+    /// Compare checksums with the content, rather than only skipping over them.
+    verify: bool,
+    name: String,
+    hasher: XxHash3_64,
+    shared: HashMap<String, Rc<[u8]>>,
+}
+
+impl<R: BufRead> State<R> {
+    fn read_content(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let limit = buf.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        if limit == 0 {
+            return Ok(0);
+        }
+        let n = self.decoder.read(&mut buf[..limit])?;
+        if n == 0 {
+            return Err(invalid(format!("truncated content for {}", self.name)));
+        }
+        self.hasher.write(&buf[..n]);
+        self.remaining -= n as u64;
+        if self.remaining == 0 {
+            self.check()?;
+        }
+        Ok(n)
+    }
+
+    /// Reads the current entry's checksum once its content has been read, and compares.
+    fn check(&mut self) -> io::Result<()> {
+        if !std::mem::take(&mut self.checksum) {
+            return Ok(());
+        }
+        let mut bytes = [0; 8];
+        self.decoder.read_exact(&mut bytes).map_err(|_| invalid("truncated archive entry checksum".to_string()))?;
+        let (stored, computed) = (u64::from_le_bytes(bytes), self.hasher.finish());
+        if self.verify && stored != computed {
+            return Err(checksum_mismatch(self.index, &self.name, stored, computed));
+        }
+        Ok(())
+    }
+
+    /// Skips whatever the previous entry left unread, still checking its checksum.
+    fn skip_rest(&mut self) -> io::Result<()> {
+        let mut buf = [0; 8192];
+        while self.remaining > 0 {
+            self.read_content(&mut buf)?;
+        }
+        self.check()
+    }
+}
+
+/// Reads the entries of an archive in order.
+///
+/// Iterating yields each entry with a reader over its content. Reading an
+/// entry after the iterator has moved past it fails; whatever is left unread
+/// is skipped, and still checked, when the next entry is requested.
+pub struct ArchiveReader<R: BufRead> {
+    state: Rc<RefCell<State<R>>>,
+}
+
+impl<R: Read> ArchiveReader<BufReader<R>> {
+    pub fn new(reader: R) -> io::Result<Self> {
+        Self::from_decoder(zstd::Decoder::new(reader)?)
+    }
+
+    /// Reads an archive written with the zstd dictionary `dict`.
+    pub fn with_dictionary(reader: R, dict: &[u8]) -> io::Result<Self> {
+        Self::from_decoder(zstd::Decoder::with_dictionary(BufReader::new(reader), dict)?)
+    }
+}
+
+impl<R: BufRead> ArchiveReader<R> {
+    /// Reads from a zstd decoder set up by the caller, e.g. one limited to a single frame.
+    pub fn from_decoder(decoder: zstd::Decoder<'static, R>) -> io::Result<Self> {
+        let state = State {
+            decoder,
+            index: 0,
+            remaining: 0,
+            checksum: false,
+            verify: true,
+            name: String::new(),
+            hasher: XxHash3_64::new(),
+            shared: HashMap::new(),
+        };
+        Ok(ArchiveReader { state: Rc::new(RefCell::new(state)) })
+    }
+
+    /// Skips stored checksums instead of comparing them with the content.
+    pub fn skip_checksums(self) -> Self {
+        self.state.borrow_mut().verify = false;
+        self
+    }
+
+    fn next_entry(&mut self) -> io::Result<Option<ArchiveEntry<R>>> {
+        let mut state = self.state.borrow_mut();
+        state.skip_rest()?;
+        let Some(Header { name, flags, len }) = read_header(&mut state.decoder)? else {
+            return Ok(None);
+        };
+        state.index += 1;
+        state.remaining = len;
+        state.checksum = flags & CHECKSUM_FLAG != 0;
+        state.hasher = XxHash3_64::new();
+        state.name = name.clone();
+        let is_symlink = flags & SYMLINK_FLAG != 0;
+
+        if flags & (REFERENCE_FLAG | SHARED_FLAG) == 0 {
+            let content = Content::Stream { state: Rc::clone(&self.state), index: state.index };
+            return Ok(Some(ArchiveEntry { name, is_symlink, len, content }));
+        }
+        // The length comes from the archive, so the buffer only grows as content actually arrives.
+        let mut data = Vec::new();
+        let mut buf = [0; 8192];
+        while state.remaining > 0 {
+            let n = state.read_content(&mut buf)?;
+            data.extend_from_slice(&buf[..n]);
+        }
+        state.check()?;
+        let data: Rc<[u8]> = if flags & REFERENCE_FLAG != 0 {
+            let original = std::str::from_utf8(&data).map_err(|_| invalid("archive reference is not UTF-8".to_string()))?;
+            let shared = state.shared.get(original).ok_or_else(|| invalid(format!("{} refers to unknown entry {}", name, original)))?;
+            Rc::clone(shared)
+        } else {
+            let data: Rc<[u8]> = data.into();
+            state.shared.insert(name.clone(), Rc::clone(&data));
+            data
+        };
+        Ok(Some(ArchiveEntry { name, is_symlink, len: data.len() as u64, content: Content::Shared(Cursor::new(data)) }))
+    }
+}
+
+impl<R: BufRead> Iterator for ArchiveReader<R> {
+    type Item = io::Result<ArchiveEntry<R>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+enum Content<R: BufRead> {
+    /// Read from the decompressor while the reader is still at entry `index`.
+    Stream { state: Rc<RefCell<State<R>>>, index: usize },
+    /// A deduplicated entry's content, held in memory for the references to it.
+    Shared(Cursor<Rc<[u8]>>),
+}
+
+/// One entry of an archive; reading it yields the entry's content.
+pub struct ArchiveEntry<R: BufRead> {
+    pub name: String,
+    pub is_symlink: bool,
+    /// Content length in bytes.
+    pub len: u64,
+    content: Content<R>,
+}
+
+impl<R: BufRead> Read for ArchiveEntry<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.content {
+            Content::Stream { state, index } => {
+                let mut state = state.borrow_mut();
+                if state.index != *index {
+                    return Err(io::Error::other(format!("{} was read after the archive reader moved past it", self.name)));
+                }
+                state.read_content(buf)
+            }
+            Content::Shared(cursor) => cursor.read(buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compresses entries written straight into the raw, uncompressed entry stream.
+    fn compress(raw: &[u8]) -> Vec<u8> {
+        zstd::encode_all(raw, 3).unwrap()
+    }
+
+    fn read_all(archive: &[u8]) -> io::Result<Vec<(String, bool, Vec<u8>)>> {
+        let mut entries = Vec::new();
+        for entry in ArchiveReader::new(archive)? {
+            let mut entry = entry?;
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content)?;
+            entries.push((entry.name.clone(), entry.is_symlink, content));
+        }
+        Ok(entries)
+    }
+
+    #[test]
+    fn round_trips_plain_symlink_shared_and_reference_entries() {
+        let mut writer = ArchiveWriter::new(Vec::new(), 3).unwrap();
+        writer.add_entry("a.json", &b"{\"level\":\"INFO\"}"[..]).unwrap();
+        writer.add_sized_entry("logs/b.log", 5, &b"hello"[..]).unwrap();
+        writer.add_symlink("latest", "a.json").unwrap();
+        write_flagged_entry(&mut writer.encoder, "first", SHARED_FLAG, b"same content").unwrap();
+        write_flagged_entry(&mut writer.encoder, "second", REFERENCE_FLAG, b"first").unwrap();
+        let archive = writer.finish().unwrap();
+
+        let entries = read_all(&archive).unwrap();
+        let expected: Vec<(String, bool, Vec<u8>)> = vec![
+            ("a.json".into(), false, b"{\"level\":\"INFO\"}".to_vec()),
+            ("logs/b.log".into(), false, b"hello".to_vec()),
+            ("latest".into(), true, b"a.json".to_vec()),
+            ("first".into(), false, b"same content".to_vec()),
+            ("second".into(), false, b"same content".to_vec()),
+        ];
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn skips_unread_content_and_still_reads_the_next_entry() {
+        let mut writer = ArchiveWriter::new(Vec::new(), 3).unwrap();
+        writer.add_entry("a", &[7; 20_000][..]).unwrap();
+        writer.add_entry("b", &b"tail"[..]).unwrap();
+        let archive = writer.finish().unwrap();
+
+        let mut reader = ArchiveReader::new(&archive[..]).unwrap();
+        assert_eq!(reader.next().unwrap().unwrap().name, "a");
+        let mut b = reader.next().unwrap().unwrap();
+        let mut content = String::new();
+        b.read_to_string(&mut content).unwrap();
+        assert_eq!((b.name.as_str(), content.as_str()), ("b", "tail"));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn fails_on_a_header_cut_short() {
+        let mut raw = Vec::new();
+        write_flagged_entry(&mut raw, "a", 0, b"x").unwrap();
+        for cut in [&[5, 0][..], &[5, 0, 0, 0, b'a', b'b'][..], &[1, 0, 0, 0, b'a', 1, 0][..]] {
+            let mut stream = raw.clone();
+            stream.extend_from_slice(cut);
+            let archive = compress(&stream);
+            let mut reader = ArchiveReader::new(&archive[..]).unwrap();
+            assert_eq!(reader.next().unwrap().unwrap().name, "a");
+            let err = reader.next().unwrap().err().expect("a partial header must not read as the end");
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "after {:?}", cut);
+        }
+    }
+
+    #[test]
+    fn reports_a_flipped_content_byte() {
+        let mut raw = Vec::new();
+        write_flagged_entry(&mut raw, "a.json", 0, b"{\"level\":\"INFO\"}").unwrap();
+        raw[4 + "a.json".len() + 4] ^= 1;
+        let err = read_all(&compress(&raw)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("checksum mismatch in archive entry #1 (a.json)"), "{}", err);
+    }
+
+    #[test]
+    fn skip_checksums_reads_a_flipped_byte_unverified() {
+        let mut raw = Vec::new();
+        write_flagged_entry(&mut raw, "a", 0, b"abc").unwrap();
+        raw[4 + 1 + 4] = b'x';
+        let archive = compress(&raw);
+        let mut entry = ArchiveReader::new(&archive[..]).unwrap().skip_checksums().next().unwrap().unwrap();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).unwrap();
+        assert_eq!(content, b"xbc");
+    }
+
+    #[test]
+    fn rejects_a_reference_to_an_unknown_entry() {
+        let mut raw = Vec::new();
+        write_flagged_entry(&mut raw, "copy", REFERENCE_FLAG, b"missing").unwrap();
+        let err = read_all(&compress(&raw)).unwrap_err();
+        assert!(err.to_string().contains("copy refers to unknown entry missing"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_entries_too_large_for_the_header() {
+        let err = write_header(&mut Vec::new(), "big", 0, u64::from(u32::MAX) + 1).unwrap_err();
+        assert!(err.to_string().contains("larger than 4 GiB"), "{}", err);
+    }
+}
@@ -0,0 +1,101 @@
+//! Results files, reports and other JSON artifacts, stored compressed when
+//! their path ends in `.gz` or `.zst` and read back transparently either way.
+//!
+//! Reading goes by the leading magic bytes rather than the name, so a renamed
+//! or extensionless file still opens.
+
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use crate::format_bytes;
+
+/// zstd level for artifacts: they are small, so the slowest levels cost next to nothing.
+const ZSTD_LEVEL: i32 = 19;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The compression `path`'s extension asks for.
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// The compression `data` is in, going by its magic bytes.
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/// Sizes of an artifact as serialized and as stored.
+#[derive(Clone, Copy, Debug)]
+pub struct Written {
+    pub compression: Compression,
+    pub bytes: u64,
+    pub stored_bytes: u64,
+}
+
+impl fmt::Display for Written {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.compression {
+            Compression::None => write!(f, "{}", format_bytes(self.bytes)),
+            Compression::Gzip => write!(f, "{}, {} as gzip", format_bytes(self.bytes), format_bytes(self.stored_bytes)),
+            Compression::Zstd => write!(f, "{}, {} as zstd", format_bytes(self.bytes), format_bytes(self.stored_bytes)),
+        }
+    }
+}
+
+/// Writes `data` to `path`, compressed as the extension of `path` asks for.
+pub fn write(path: &Path, data: &[u8]) -> Result<Written> {
+    write_with(path, data, Compression::for_path(path))
+}
+
+/// Writes `data` to `path` with the given compression, whatever its extension.
+pub fn write_with(path: &Path, data: &[u8], compression: Compression) -> Result<Written> {
+    let stored = match compression {
+        Compression::None => data.to_vec(),
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::best());
+            encoder.write_all(data)?;
+            encoder.finish()?
+        }
+        Compression::Zstd => zstd::bulk::compress(data, ZSTD_LEVEL)?,
+    };
+    fs::write(path, &stored).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(Written { compression, bytes: data.len() as u64, stored_bytes: stored.len() as u64 })
+}
+
+/// Decompresses `data` if it is gzip or zstd, and returns it as it is otherwise.
+pub fn decode(data: Vec<u8>) -> Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    match Compression::detect(&data) {
+        Compression::None => return Ok(data),
+        Compression::Gzip => MultiGzDecoder::new(data.as_slice()).read_to_end(&mut decoded)?,
+        Compression::Zstd => zstd::Decoder::new(data.as_slice())?.read_to_end(&mut decoded)?,
+    };
+    Ok(decoded)
+}
+
+/// Reads `path`, decompressing it if it is gzip or zstd.
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    let data = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    decode(data).with_context(|| format!("failed to decompress {}", path.display()))
+}
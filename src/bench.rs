@@ -0,0 +1,69 @@
+//! In-memory codec measurements over the loaded corpus.
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use anyhow::{ensure, Result};
+use crate::codec::{Codec, MemoryEstimate};
+use crate::render;
+
+pub struct Measurement {
+    pub compressed_size: u64,
+    pub compress_time: Duration,
+    pub decompress_time: Duration,
+    pub memory: Option<MemoryEstimate>,
+}
+
+/// Reads every corpus file (relative to `dir`) into memory so codec timings exclude filesystem I/O.
+pub fn load_corpus(dir: &Path, names: &[String]) -> Result<Vec<Vec<u8>>> {
+    names
+        .iter()
+        .map(|name| Ok(fs::read(dir.join(name))?))
+        .collect()
+}
+
+/// Compresses every file individually with `codec`, then decompresses and verifies each one.
+pub fn measure(codec: &dyn Codec, files: &[Vec<u8>]) -> Result<Measurement> {
+    measure_tracked(codec, files, &AtomicUsize::new(0))
+}
+
+/// Like `measure`, counting every file compressed and then every file decompressed in `progress`.
+pub fn measure_tracked(codec: &dyn Codec, files: &[Vec<u8>], progress: &AtomicUsize) -> Result<Measurement> {
+    let start = Instant::now();
+    let mut compressed = Vec::with_capacity(files.len());
+    for file in files {
+        compressed.push(codec.compress_bytes(file)?);
+        progress.fetch_add(1, Ordering::Relaxed);
+    }
+    let compress_time = start.elapsed();
+
+    let start = Instant::now();
+    for (frame, original) in compressed.iter().zip(files) {
+        let mut input = frame.as_slice();
+        let mut decoder = codec.decoder(&mut input)?;
+        let mut output = Vec::with_capacity(original.len());
+        decoder.read_to_end(&mut output)?;
+        ensure!(output == *original, "{} round trip mismatch", codec.name());
+        progress.fetch_add(1, Ordering::Relaxed);
+    }
+    let decompress_time = start.elapsed();
+
+    Ok(Measurement {
+        compressed_size: compressed.iter().map(|frame| frame.len() as u64).sum(),
+        compress_time,
+        decompress_time,
+        memory: codec.memory_estimate_for(files.iter().map(|file| file.len() as u64).max().unwrap_or(0)),
+    })
+}
+
+/// Throughput in MB/s (MiB/s with binary `render::Units`) of uncompressed data.
+pub fn throughput(bytes: u64, time: Duration) -> f64 {
+    bytes as f64 / render::units().base().powi(2) / time.as_secs_f64()
+}
+
+/// Throughput per thread, so codecs running on more cores are compared on equal hardware.
+pub fn throughput_per_core(bytes: u64, time: Duration, threads: u32) -> f64 {
+    throughput(bytes, time) / threads.max(1) as f64
+}
@@ -0,0 +1,270 @@
+//! `--bundle`: packs everything needed to defend a run later into one `.tar.zst`,
+//! and `replay`, which runs the recorded configuration again and compares.
+//!
+//! The bundle holds `manifest.json` (arguments, corpus seed and schema, and the
+//! environment) and the run's `results.json`. The input corpus itself is not
+//! included, so replaying an `--input` run needs the same files in place.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::SystemTime;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::corpus::FIXED_KEYS;
+use crate::format_bytes;
+use crate::priority::{self, Priority};
+use crate::render::{self, Align, Table};
+use crate::results::Results;
+
+/// Bumped whenever the layout of a bundle changes.
+const FORMAT: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const RESULTS: &str = "results.json";
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub struct Environment {
+    pub version: String,
+    pub features: Vec<String>,
+    pub zstd: String,
+    pub os: String,
+    pub arch: String,
+    pub cpus: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_bytes: Option<u64>,
+    /// SIMD and bit-manipulation extensions the codecs and checksums can dispatch to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_features: Vec<String>,
+    /// CPU niceness and I/O class the run had, from `--nice` and `--ionice` or inherited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+}
+
+/// First `key: value` line of a `/proc` file whose key is `key`.
+fn proc_field(path: &str, key: &str) -> Option<String> {
+    let text = std::fs::read_to_string(path).ok()?;
+    text.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim().to_string())
+    })
+}
+
+/// The CPU features relevant to compression and hashing that this machine supports.
+pub fn cpu_features() -> Vec<String> {
+    #[allow(unused_mut)]
+    let mut features: Vec<&str> = Vec::new();
+    #[cfg(target_arch = "x86_64")]
+    {
+        macro_rules! detect {
+            ($($feature:tt),*) => {
+                $(if std::arch::is_x86_feature_detected!($feature) {
+                    features.push($feature);
+                })*
+            };
+        }
+        detect!("sse4.2", "pclmulqdq", "avx", "avx2", "avx512f", "bmi1", "bmi2", "lzcnt", "popcnt", "sha");
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        macro_rules! detect {
+            ($($feature:tt),*) => {
+                $(if std::arch::is_aarch64_feature_detected!($feature) {
+                    features.push($feature);
+                })*
+            };
+        }
+        detect!("neon", "crc", "aes", "sha2", "sve");
+    }
+    features.into_iter().map(String::from).collect()
+}
+
+impl Environment {
+    pub fn current() -> Self {
+        let features = [
+            ("brotli", cfg!(feature = "brotli")),
+            ("lz4", cfg!(feature = "lz4")),
+            ("xz", cfg!(feature = "xz")),
+            ("io-uring", cfg!(feature = "io-uring")),
+            ("simd-json", cfg!(feature = "simd-json")),
+            ("s3", cfg!(feature = "s3")),
+            ("parquet", cfg!(feature = "parquet")),
+            ("no-asm", cfg!(feature = "no-asm")),
+        ];
+        Environment {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            features: features.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| name.to_string()).collect(),
+            zstd: zstd::zstd_safe::version_string().to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpus: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            cpu_model: proc_field("/proc/cpuinfo", "model name"),
+            memory_bytes: proc_field("/proc/meminfo", "MemTotal")
+                .and_then(|kb| kb.trim_end_matches("kB").trim().parse::<u64>().ok())
+                .map(|kb| kb * 1024),
+            cpu_features: cpu_features(),
+            priority: priority::current(),
+        }
+    }
+
+    /// One line per field that differs from `other`, for `replay` and `--reference`.
+    pub fn differences(&self, other: &Environment) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut compare = |field: &str, recorded: String, current: String| {
+            if recorded != current {
+                lines.push(format!("{}: recorded {}, now {}", field, recorded, current));
+            }
+        };
+        let optional = |value: &Option<String>| value.clone().unwrap_or_else(|| "unknown".to_string());
+        compare("version", self.version.clone(), other.version.clone());
+        compare("features", self.features.join(","), other.features.join(","));
+        compare("zstd", self.zstd.clone(), other.zstd.clone());
+        compare("platform", format!("{}/{}", self.os, self.arch), format!("{}/{}", other.os, other.arch));
+        compare("cpus", self.cpus.to_string(), other.cpus.to_string());
+        compare("cpu", optional(&self.cpu_model), optional(&other.cpu_model));
+        compare("memory", format!("{:?}", self.memory_bytes), format!("{:?}", other.memory_bytes));
+        compare("cpu features", self.cpu_features.join(","), other.cpu_features.join(","));
+        let priority = |value: &Option<Priority>| value.as_ref().map_or("unknown".to_string(), Priority::to_string);
+        compare("priority", priority(&self.priority), priority(&other.priority));
+        lines
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub created_at: String,
+    /// Command-line arguments after the program name, with `--seed` pinned and `--bundle` removed.
+    pub args: Vec<String>,
+    /// Seed the generated corpus was drawn from.
+    pub seed: u64,
+    /// Keys of every generated record.
+    pub schema: Vec<String>,
+    pub environment: Environment,
+    /// BLAKE3 of `results.json`, checked when the bundle is read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results_blake3: Option<String>,
+}
+
+/// `args` without any `--bundle` or `--seed`, then `--seed seed`, so replaying
+/// them regenerates the same corpus without writing another bundle.
+pub fn effective_args(args: impl IntoIterator<Item = String>, seed: u64) -> Vec<String> {
+    let mut effective = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bundle" | "--seed" => {
+                args.next();
+            }
+            _ if arg.starts_with("--bundle=") || arg.starts_with("--seed=") => {}
+            _ => effective.push(arg),
+        }
+    }
+    effective.extend(["--seed".to_string(), seed.to_string()]);
+    effective
+}
+
+fn append(builder: &mut tar::Builder<impl Write>, name: &str, data: &[u8], mtime: u64) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    builder.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+/// Writes the bundle for a run with `args` and `seed` that produced `results`.
+pub fn write(path: &Path, args: Vec<String>, seed: u64, results: &Results) -> Result<()> {
+    let results = serde_json::to_vec_pretty(results)?;
+    let manifest = Manifest {
+        format: FORMAT,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        args,
+        seed,
+        schema: FIXED_KEYS.iter().map(|key| key.to_string()).collect(),
+        environment: Environment::current(),
+        results_blake3: Some(blake3::hash(&results).to_hex().to_string()),
+    };
+    let file = File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut builder = tar::Builder::new(zstd::Encoder::new(BufWriter::new(file), 19)?);
+    let mtime = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+    append(&mut builder, MANIFEST, &serde_json::to_vec_pretty(&manifest)?, mtime)?;
+    append(&mut builder, RESULTS, &results, mtime)?;
+    builder.into_inner()?.finish()?.flush()?;
+    Ok(())
+}
+
+/// Reads a bundle written by `write`.
+pub fn read(path: &Path) -> Result<(Manifest, Results)> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut archive = tar::Archive::new(zstd::Decoder::new(BufReader::new(file))?);
+    let (mut manifest, mut results) = (None, None::<Vec<u8>>);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        match name.as_str() {
+            MANIFEST => manifest = Some(serde_json::from_slice::<Manifest>(&data).context("invalid bundle manifest")?),
+            RESULTS => results = Some(data),
+            _ => {}
+        }
+    }
+    let manifest = manifest.with_context(|| format!("{} has no {}", path.display(), MANIFEST))?;
+    anyhow::ensure!(manifest.format <= FORMAT, "{} uses bundle format {}, newer than this build", path.display(), manifest.format);
+    let results = results.with_context(|| format!("{} has no {}", path.display(), RESULTS))?;
+    if let Some(expected) = &manifest.results_blake3 {
+        let actual = blake3::hash(&results).to_hex();
+        anyhow::ensure!(
+            actual.as_str() == expected,
+            "{} in {} does not match the manifest (BLAKE3 {}, recorded {}); the bundle is corrupted",
+            RESULTS,
+            path.display(),
+            actual,
+            expected
+        );
+    }
+    let results = Results::from_slice(&results).context("invalid bundle results")?;
+    Ok((manifest, results))
+}
+
+/// Prints what a replay of `manifest` runs and how this machine differs from the recorded one.
+pub fn print_replay(path: &Path, manifest: &Manifest) {
+    println!("🔁 Replaying {} from {}", path.display(), manifest.created_at);
+    println!("  Arguments: {}", manifest.args.join(" "));
+    let differences = manifest.environment.differences(&Environment::current());
+    if differences.is_empty() {
+        println!("  Environment matches the recorded one");
+    } else {
+        println!("  Environment differs, so timings may too:");
+        for line in differences {
+            println!("    {}", line);
+        }
+    }
+}
+
+/// Prints every stage of the replay next to the recorded one.
+pub fn print_comparison(recorded: &Results, replayed: &Results) {
+    let mut table = Table::new(&[
+        ("Stage", Align::Left),
+        ("Codec", Align::Left),
+        ("Output", Align::Right),
+        ("Δ output", Align::Right),
+        ("Time", Align::Right),
+        ("Δ time", Align::Right),
+    ]);
+    for stage in &replayed.stages {
+        let old = recorded.stages.iter().find(|old| old.name == stage.name && old.codec == stage.codec);
+        table.row(vec![
+            stage.name.clone(),
+            stage.codec.clone(),
+            format_bytes(stage.bytes_out),
+            old.map_or("new".to_string(), |old| render::change(stage.bytes_out as f64, old.bytes_out as f64)),
+            render::seconds(stage.secs),
+            old.map_or("new".to_string(), |old| render::change(stage.secs, old.secs)),
+        ]);
+    }
+    print!("{}", table.terminal());
+    crate::reproducible::print_comparison(recorded, replayed);
+}
@@ -0,0 +1,68 @@
+//! Cached `--sweep` and `--matrix` measurements, so iterating on one new codec
+//! configuration does not re-run every configuration measured before.
+//!
+//! Cells are keyed by a BLAKE3 digest of the corpus content and the codec's
+//! name, which spells out its level and every other setting. A later run over
+//! byte-identical input reuses the measurement instead of repeating it;
+//! `--no-cache` measures everything afresh. Timings are only comparable on the
+//! machine that took them, so the cache is a local file rather than part of the
+//! results.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::bench::Measurement;
+use crate::isolate::Report;
+
+/// The cache file used unless `--cache-file` names another.
+pub const DEFAULT_PATH: &str = ".gz-vs-zstd-cache.json";
+
+#[derive(Default, Serialize, Deserialize)]
+struct Cells {
+    /// Measurements by codec name, by corpus digest.
+    corpora: BTreeMap<String, BTreeMap<String, Report>>,
+}
+
+pub struct Cache {
+    path: PathBuf,
+    corpus: String,
+    cells: Cells,
+    /// Measurements reused from the file during this run.
+    pub hits: usize,
+}
+
+impl Cache {
+    /// Opens the cache at `path` for the corpus with digest `corpus`, starting empty if there is none yet.
+    pub fn open(path: &Path, corpus: String) -> Result<Self> {
+        let cells = match fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data).with_context(|| format!("invalid measurement cache {}; delete it or pass --no-cache", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Cells::default(),
+            Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+        };
+        Ok(Cache { path: path.to_path_buf(), corpus, cells, hits: 0 })
+    }
+
+    /// The measurement of `codec` on this corpus from an earlier run.
+    pub fn get(&mut self, codec: &str) -> Option<Measurement> {
+        let measurement = self.cells.corpora.get(&self.corpus)?.get(codec)?.measurement();
+        self.hits += 1;
+        Some(measurement)
+    }
+
+    /// Records a fresh measurement and saves right away, so an interrupted run keeps what it measured.
+    pub fn insert(&mut self, codec: &str, measurement: &Measurement) -> Result<()> {
+        self.cells.corpora.entry(self.corpus.clone()).or_default().insert(codec.to_string(), Report::new(measurement));
+        self.save()
+    }
+
+    /// Writes the cache next to its final path first, so a crash never leaves it half written.
+    fn save(&self) -> Result<()> {
+        let temp = self.path.with_extension("tmp");
+        serde_json::to_writer(BufWriter::new(File::create(&temp)?), &self.cells)?;
+        fs::rename(&temp, &self.path).with_context(|| format!("failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+}
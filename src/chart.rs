@@ -0,0 +1,60 @@
+//! `--chart`: unicode bar charts of the run's results, for a quick visual
+//! comparison in the terminal without the HTML report.
+
+use crate::format_bytes;
+use crate::render;
+use crate::results::Results;
+
+const BAR_WIDTH: usize = 40;
+/// Partial blocks in eighths, from one eighth up to seven.
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// A bar of `value / max` times `BAR_WIDTH` cells, with eighth-cell resolution.
+fn bar(value: f64, max: f64) -> String {
+    let eighths = if max > 0.0 { (value / max * (BAR_WIDTH * 8) as f64).round() as usize } else { 0 };
+    let mut bar = "█".repeat(eighths / 8);
+    if eighths % 8 > 0 {
+        bar.push(PARTIAL_BLOCKS[eighths % 8 - 1]);
+    }
+    // Never draw nothing for a non-zero value.
+    if bar.is_empty() && value > 0.0 {
+        bar.push(PARTIAL_BLOCKS[0]);
+    }
+    bar
+}
+
+fn draw(title: &str, rows: &[(String, f64, String)]) {
+    if rows.is_empty() {
+        return;
+    }
+    let max = rows.iter().map(|(_, value, _)| *value).fold(0.0, f64::max);
+    let label_width = rows.iter().map(|(label, _, _)| label.chars().count()).max().unwrap_or(0);
+    println!();
+    println!("{}:", title);
+    for (label, value, text) in rows {
+        println!("  {:<label_width$} {:<bar_width$} {}", label, bar(*value, max), text, bar_width = BAR_WIDTH);
+    }
+}
+
+/// Prints one chart of stage durations and one of compressed size per codec.
+pub fn print(results: &Results) {
+    let durations: Vec<_> = results
+        .stages
+        .iter()
+        .map(|stage| (format!("{} ({})", stage.name, stage.codec), stage.secs, render::seconds(stage.secs)))
+        .collect();
+    draw("Stage durations", &durations);
+
+    // The compressing stages first, then any matrix cells for codecs they did not cover.
+    let mut sizes: Vec<(String, f64, String)> = Vec::new();
+    let compressed = results.stages.iter().filter(|stage| stage.name.ends_with("_compress"));
+    for (codec, bytes) in compressed
+        .map(|stage| (&stage.codec, stage.bytes_out))
+        .chain(results.matrix.iter().map(|cell| (&cell.codec, cell.compressed_bytes)))
+    {
+        if !sizes.iter().any(|(label, _, _)| label == codec) {
+            sizes.push((codec.clone(), bytes as f64, format_bytes(bytes)));
+        }
+    }
+    draw("Compressed size per codec", &sizes);
+}
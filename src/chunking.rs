@@ -0,0 +1,160 @@
+//! Compresses the corpus as one stream cut into independent chunks, the way a
+//! memory-constrained streaming agent ships logs, and compares the result with
+//! compressing the same stream whole.
+//!
+//! The files are read from disk back to back, so neither mode ever holds more
+//! than one chunk of input. Each chunk is a complete frame of its codec with no
+//! history from the previous one; the size it gives up against the whole stream
+//! is the ratio penalty of bounding memory at that chunk size.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use anyhow::{ensure, Result};
+use crate::bench::throughput;
+use crate::codec::Family;
+use crate::render::{Align, Table};
+use crate::{format_bytes, progress_bar, render};
+
+/// The corpus files read one after another as a single stream.
+struct Concat<'a> {
+    paths: std::slice::Iter<'a, PathBuf>,
+    current: Option<File>,
+}
+
+impl<'a> Concat<'a> {
+    fn new(paths: &'a [PathBuf]) -> Self {
+        Concat { paths: paths.iter(), current: None }
+    }
+}
+
+impl Read for Concat<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(file) = &mut self.current {
+                let n = file.read(buf)?;
+                if n > 0 || buf.is_empty() {
+                    return Ok(n);
+                }
+            }
+            match self.paths.next() {
+                Some(path) => self.current = Some(File::open(path)?),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Counts what is written and throws it away.
+#[derive(Default)]
+struct Counter(u64);
+
+impl Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct ChunkResult {
+    pub codec: String,
+    pub whole_size: u64,
+    pub whole_time: Duration,
+    pub chunked_size: u64,
+    pub chunked_time: Duration,
+    pub chunks: usize,
+    /// Estimated encoder working memory, if the codec reports it.
+    pub encoder_memory: Option<u64>,
+}
+
+/// Compresses the files `names` under `dir` whole and in `chunk_size` chunks with every
+/// enabled codec family at its default level, checking that every chunk round-trips.
+pub fn run(dir: &Path, names: &[String], chunk_size: usize) -> Result<Vec<ChunkResult>> {
+    ensure!(chunk_size > 0, "--chunk-size must be at least one byte");
+    let paths: Vec<PathBuf> = names.iter().map(|name| dir.join(name)).collect();
+    let pb = progress_bar(Family::ALL.len());
+    let mut results = Vec::with_capacity(Family::ALL.len());
+    let mut chunk = Vec::with_capacity(chunk_size);
+    let mut decoded = Vec::with_capacity(chunk_size);
+    for &family in Family::ALL {
+        let codec = family.build(family.default_level());
+        pb.set_message(codec.name());
+
+        let start = Instant::now();
+        let mut counter = Counter::default();
+        let mut encoder = codec.encoder(&mut counter)?;
+        io::copy(&mut Concat::new(&paths), &mut encoder)?;
+        encoder.finish()?;
+        let whole_time = start.elapsed();
+
+        let mut stream = Concat::new(&paths);
+        let (mut chunked_size, mut chunked_time, mut chunks) = (0, Duration::ZERO, 0);
+        loop {
+            // Reading counts towards the time in both modes, only the round-trip check does not.
+            let start = Instant::now();
+            chunk.clear();
+            (&mut stream).take(chunk_size as u64).read_to_end(&mut chunk)?;
+            if chunk.is_empty() {
+                break;
+            }
+            let frame = codec.compress_bytes(&chunk)?;
+            chunked_time += start.elapsed();
+            chunked_size += frame.len() as u64;
+            chunks += 1;
+
+            decoded.clear();
+            codec.decoder(&mut frame.as_slice())?.read_to_end(&mut decoded)?;
+            ensure!(decoded == chunk, "{} chunk {} round trip mismatch", codec.name(), chunks);
+        }
+
+        results.push(ChunkResult {
+            codec: codec.name(),
+            whole_size: counter.0,
+            whole_time,
+            chunked_size,
+            chunked_time,
+            chunks,
+            encoder_memory: codec.memory_estimate().map(|memory| memory.compress),
+        });
+        pb.inc(1);
+    }
+    pb.finish_with_message("Chunked compression complete!");
+    Ok(results)
+}
+
+pub fn print(results: &[ChunkResult], chunk_size: usize, json_size: u64) {
+    let mut table = Table::new(&[
+        ("Codec", Align::Left),
+        ("Whole", Align::Right),
+        ("Chunked", Align::Right),
+        ("Chunks", Align::Right),
+        ("Penalty", Align::Right),
+        ("Whole speed", Align::Right),
+        ("Chunked speed", Align::Right),
+        ("Peak memory", Align::Right),
+    ]);
+    for result in results {
+        // The input chunk and its compressed frame are both held while the encoder runs.
+        let buffers = 2 * chunk_size as u64;
+        let memory = match result.encoder_memory {
+            Some(encoder) => format_bytes(buffers + encoder),
+            None => format!("≥ {}", format_bytes(buffers)),
+        };
+        table.row(vec![
+            result.codec.to_string(),
+            format_bytes(result.whole_size),
+            format_bytes(result.chunked_size),
+            result.chunks.to_string(),
+            render::percent(result.chunked_size as f64 / result.whole_size.max(1) as f64 - 1.0),
+            render::mb_per_sec(throughput(json_size, result.whole_time)),
+            render::mb_per_sec(throughput(json_size, result.chunked_time)),
+            memory,
+        ]);
+    }
+    print!("{}", table.terminal());
+}
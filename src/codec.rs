@@ -0,0 +1,656 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use anyhow::{bail, Result};
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+use flate2::Compression;
+
+/// A streaming compression codec at a fixed configuration.
+///
+/// Codecs are `Send` so that `--stage-timeout` can measure each on a thread of its own.
+pub trait Codec: Send {
+    /// Short display name, e.g. `gzip-6`.
+    fn name(&self) -> String;
+
+    /// Extension appended to compressed files (without the leading dot).
+    fn extension(&self) -> &'static str;
+
+    /// Wraps `output` in a compressing writer.
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>>;
+
+    /// Wraps `input` in a decompressing reader.
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>>;
+
+    /// Estimated working memory for compression and decompression, if the codec can report it.
+    fn memory_estimate(&self) -> Option<MemoryEstimate> {
+        None
+    }
+
+    /// Like `memory_estimate`, for `compress_bytes` inputs of at most `input_len` bytes.
+    fn memory_estimate_for(&self, input_len: u64) -> Option<MemoryEstimate> {
+        let _ = input_len;
+        self.memory_estimate()
+    }
+
+    /// Compresses a complete in-memory buffer. Codecs whose parameters depend on
+    /// the input size should override this instead of streaming.
+    fn compress_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        let mut encoder = self.encoder(&mut output)?;
+        encoder.write_all(data)?;
+        encoder.finish()?;
+        Ok(output)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryEstimate {
+    pub compress: u64,
+    pub decompress: u64,
+}
+
+/// A compressing writer that must be explicitly finished to flush its trailer.
+pub trait Encoder: Write {
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+impl<W: Write> Encoder for GzEncoder<W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        GzEncoder::finish(*self)?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Encoder for ZlibEncoder<W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        ZlibEncoder::finish(*self)?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Encoder for DeflateEncoder<W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        DeflateEncoder::finish(*self)?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Encoder for zstd::Encoder<'_, W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        zstd::Encoder::finish(*self)?;
+        Ok(())
+    }
+}
+
+/// zlib's documented working memory for the default window (15 bits) and memLevel (8):
+/// `(1 << (windowBits + 2)) + (1 << (memLevel + 9))` to deflate, `1 << windowBits` plus
+/// about 7 KB of state to inflate.
+const DEFLATE_MEMORY: MemoryEstimate = MemoryEstimate {
+    compress: (1 << 17) + (1 << 17),
+    decompress: (1 << 15) + 7 * 1024,
+};
+
+pub struct Gzip {
+    pub level: u32,
+}
+
+impl Codec for Gzip {
+    fn name(&self) -> String {
+        format!("gzip-{}", self.level)
+    }
+
+    fn extension(&self) -> &'static str {
+        "gz"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        Ok(Box::new(GzEncoder::new(output, Compression::new(self.level))))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(GzDecoder::new(input)))
+    }
+
+    fn memory_estimate(&self) -> Option<MemoryEstimate> {
+        Some(DEFLATE_MEMORY)
+    }
+}
+
+/// DEFLATE with the 2-byte zlib header and Adler-32 trailer (RFC 1950).
+pub struct Zlib {
+    pub level: u32,
+}
+
+impl Codec for Zlib {
+    fn name(&self) -> String {
+        format!("zlib-{}", self.level)
+    }
+
+    fn extension(&self) -> &'static str {
+        "zlib"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        Ok(Box::new(ZlibEncoder::new(output, Compression::new(self.level))))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(ZlibDecoder::new(input)))
+    }
+
+    fn memory_estimate(&self) -> Option<MemoryEstimate> {
+        Some(DEFLATE_MEMORY)
+    }
+}
+
+/// Raw DEFLATE without any framing or checksum (RFC 1951).
+pub struct Deflate {
+    pub level: u32,
+}
+
+impl Codec for Deflate {
+    fn name(&self) -> String {
+        format!("deflate-{}", self.level)
+    }
+
+    fn extension(&self) -> &'static str {
+        "deflate"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        Ok(Box::new(DeflateEncoder::new(output, Compression::new(self.level))))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(DeflateDecoder::new(input)))
+    }
+
+    fn memory_estimate(&self) -> Option<MemoryEstimate> {
+        Some(DEFLATE_MEMORY)
+    }
+}
+
+#[cfg(feature = "brotli")]
+impl<W: Write> Encoder for brotli::CompressorWriter<W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        // `into_inner` emits the final meta-block before handing the writer back.
+        self.into_inner();
+        Ok(())
+    }
+}
+
+/// Brotli at a given quality (0–11) and window size (`lgwin`, 10–24).
+#[cfg(feature = "brotli")]
+pub struct Brotli {
+    pub quality: u32,
+    pub lgwin: u32,
+}
+
+#[cfg(feature = "brotli")]
+impl Brotli {
+    pub const DEFAULT_LGWIN: u32 = 22;
+    const BUFFER_SIZE: usize = 4096;
+}
+
+#[cfg(feature = "brotli")]
+impl Codec for Brotli {
+    fn name(&self) -> String {
+        if self.lgwin == Self::DEFAULT_LGWIN {
+            format!("brotli-{}", self.quality)
+        } else {
+            format!("brotli-{}-w{}", self.quality, self.lgwin)
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        "br"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        Ok(Box::new(brotli::CompressorWriter::new(output, Self::BUFFER_SIZE, self.quality, self.lgwin)))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(brotli::Decompressor::new(input, Self::BUFFER_SIZE)))
+    }
+
+    fn compress_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let params = brotli::enc::BrotliEncoderParams {
+            quality: self.quality as i32,
+            lgwin: self.lgwin as i32,
+            size_hint: data.len(),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        brotli::BrotliCompress(&mut &data[..], &mut output, &params)?;
+        Ok(output)
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl<W: Write> Encoder for lz4::Encoder<W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        let (_, result) = lz4::Encoder::finish(*self);
+        Ok(result?)
+    }
+}
+
+#[cfg(feature = "lz4")]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Lz4Mode {
+    /// The LZ4 frame format with its own header, block checksums and end mark.
+    Frame,
+    /// A single raw LZ4 block, prefixed only with its little-endian `u32` size.
+    Block,
+}
+
+#[cfg(feature = "lz4")]
+impl FromStr for Lz4Mode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "frame" => Ok(Lz4Mode::Frame),
+            "block" => Ok(Lz4Mode::Block),
+            other => bail!("unknown lz4 mode `{}` (expected frame or block)", other),
+        }
+    }
+}
+
+/// LZ4 fast mode in either framing, with an acceleration factor (1 = default, higher = faster).
+#[cfg(feature = "lz4")]
+pub struct Lz4 {
+    pub mode: Lz4Mode,
+    pub acceleration: u32,
+}
+
+/// Buffers the whole input, since a raw block has to be compressed in one call.
+#[cfg(feature = "lz4")]
+struct Lz4BlockEncoder<W: Write> {
+    output: W,
+    buffer: Vec<u8>,
+    acceleration: u32,
+}
+
+#[cfg(feature = "lz4")]
+impl<W: Write> Write for Lz4BlockEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl<W: Write> Encoder for Lz4BlockEncoder<W> {
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        let mode = lz4::block::CompressionMode::FAST(self.acceleration as i32);
+        let block = lz4::block::compress(&self.buffer, Some(mode), true)?;
+        self.output.write_all(&block)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl Codec for Lz4 {
+    fn name(&self) -> String {
+        let mode = match self.mode {
+            Lz4Mode::Frame => "frame",
+            Lz4Mode::Block => "block",
+        };
+        format!("lz4-{}-a{}", mode, self.acceleration)
+    }
+
+    fn extension(&self) -> &'static str {
+        match self.mode {
+            Lz4Mode::Frame => "lz4",
+            Lz4Mode::Block => "lz4block",
+        }
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        match self.mode {
+            // LZ4F treats negative compression levels as acceleration factors; the
+            // crate only exposes the level as `u32`, so pass the two's complement.
+            Lz4Mode::Frame => Ok(Box::new(lz4::EncoderBuilder::new().level((-(self.acceleration as i32)) as u32).build(output)?)),
+            Lz4Mode::Block => Ok(Box::new(Lz4BlockEncoder { output, buffer: Vec::new(), acceleration: self.acceleration })),
+        }
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        match self.mode {
+            Lz4Mode::Frame => Ok(Box::new(lz4::Decoder::new(input)?)),
+            Lz4Mode::Block => {
+                let mut block = Vec::new();
+                input.read_to_end(&mut block)?;
+                Ok(Box::new(std::io::Cursor::new(lz4::block::decompress(&block, None)?)))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "xz")]
+impl<W: Write> Encoder for xz2::write::XzEncoder<W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        xz2::write::XzEncoder::finish(*self)?;
+        Ok(())
+    }
+}
+
+/// xz (LZMA2) at preset 0–9, optionally with the `--extreme` flag.
+#[cfg(feature = "xz")]
+pub struct Xz {
+    pub preset: u32,
+    pub extreme: bool,
+}
+
+#[cfg(feature = "xz")]
+impl Xz {
+    /// Dictionary size of each preset, as documented in xz(1).
+    const PRESET_DICT_SIZES: [u32; 10] = [
+        256 << 10, 1 << 20, 2 << 20, 4 << 20, 4 << 20, 8 << 20, 8 << 20, 16 << 20, 32 << 20, 64 << 20,
+    ];
+
+    fn preset_flags(&self) -> u32 {
+        if self.extreme { self.preset | lzma_sys::LZMA_PRESET_EXTREME } else { self.preset }
+    }
+
+    /// The smallest power of two holding `input_len` bytes, between 4 KiB and the preset's dictionary.
+    fn dict_size(&self, input_len: u64) -> u32 {
+        let preset_dict = Self::PRESET_DICT_SIZES[self.preset as usize];
+        input_len
+            .max(4096)
+            .checked_next_power_of_two()
+            .map_or(preset_dict, |size| size.min(preset_dict as u64) as u32)
+    }
+}
+
+#[cfg(feature = "xz")]
+impl Codec for Xz {
+    fn name(&self) -> String {
+        format!("xz-{}{}", self.preset, if self.extreme { "e" } else { "" })
+    }
+
+    fn extension(&self) -> &'static str {
+        "xz"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        Ok(Box::new(xz2::write::XzEncoder::new(output, self.preset_flags())))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(xz2::read::XzDecoder::new(input)))
+    }
+
+    fn memory_estimate(&self) -> Option<MemoryEstimate> {
+        self.memory_estimate_for(u64::MAX)
+    }
+
+    fn memory_estimate_for(&self, input_len: u64) -> Option<MemoryEstimate> {
+        // SAFETY: `options` is plain data that lzma_lzma_preset fills in, and the
+        // filter chain is terminated by LZMA_VLI_UNKNOWN as liblzma requires.
+        unsafe {
+            let mut options: lzma_sys::lzma_options_lzma = std::mem::zeroed();
+            if lzma_sys::lzma_lzma_preset(&mut options, self.preset_flags()) != 0 {
+                return None;
+            }
+            options.dict_size = self.dict_size(input_len);
+            let filters = [
+                lzma_sys::lzma_filter { id: lzma_sys::LZMA_FILTER_LZMA2, options: &mut options as *mut _ as *mut std::ffi::c_void },
+                lzma_sys::lzma_filter { id: lzma_sys::LZMA_VLI_UNKNOWN, options: std::ptr::null_mut() },
+            ];
+            Some(MemoryEstimate {
+                compress: lzma_sys::lzma_raw_encoder_memusage(filters.as_ptr()),
+                decompress: lzma_sys::lzma_raw_decoder_memusage(filters.as_ptr()),
+            })
+        }
+    }
+
+    fn compress_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
+        // A dictionary larger than the input is never used, but liblzma still
+        // allocates it up front; shrink it so per-file runs don't spend their
+        // time in the allocator. The output is identical apart from the header.
+        let dict_size = self.dict_size(data.len() as u64);
+        let mut options = xz2::stream::LzmaOptions::new_preset(self.preset_flags())?;
+        options.dict_size(dict_size);
+        let mut filters = xz2::stream::Filters::new();
+        filters.lzma2(&options);
+        let stream = xz2::stream::Stream::new_stream_encoder(&filters, xz2::stream::Check::Crc64)?;
+
+        let mut encoder = xz2::write::XzEncoder::new_stream(Vec::new(), stream);
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+}
+
+pub struct Zstd {
+    pub level: i32,
+}
+
+impl Codec for Zstd {
+    fn name(&self) -> String {
+        format!("zstd-{}", self.level)
+    }
+
+    fn extension(&self) -> &'static str {
+        "zst"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        Ok(Box::new(zstd::Encoder::new(output, self.level)?))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(zstd::Decoder::new(input)?))
+    }
+
+    fn memory_estimate(&self) -> Option<MemoryEstimate> {
+        use zstd::zstd_safe::zstd_sys;
+        // SAFETY: the estimators are pure functions of the compression parameters.
+        unsafe {
+            let window_log = zstd_sys::ZSTD_getCParams(self.level, 0, 0).windowLog;
+            Some(MemoryEstimate {
+                compress: zstd_sys::ZSTD_estimateCStreamSize(self.level) as u64,
+                decompress: zstd_sys::ZSTD_estimateDStreamSize(1 << window_log) as u64,
+            })
+        }
+    }
+
+    fn compress_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
+        // The bulk API knows the source size up front, so small files get small
+        // match tables instead of a full window allocation per file.
+        Ok(zstd::bulk::compress(data, self.level)?)
+    }
+}
+
+/// zstd with explicit frame header options: an xxh64 checksum of the content
+/// after each frame (4 bytes, verified on decode) and the content size in the
+/// header (up to 8 bytes). Plain `Zstd` writes the content size and no checksum.
+pub struct ZstdFramed {
+    pub level: i32,
+    pub checksum: bool,
+    pub content_size: bool,
+}
+
+impl Codec for ZstdFramed {
+    fn name(&self) -> String {
+        format!(
+            "zstd-{}{}{}",
+            self.level,
+            if self.checksum { "+checksum" } else { "" },
+            if self.content_size { "+size" } else { "" }
+        )
+    }
+
+    fn extension(&self) -> &'static str {
+        "zst"
+    }
+
+    fn encoder<'a>(&self, output: &'a mut dyn Write) -> Result<Box<dyn Encoder + 'a>> {
+        let mut encoder = zstd::Encoder::new(output, self.level)?;
+        encoder.include_checksum(self.checksum)?;
+        encoder.include_contentsize(self.content_size)?;
+        Ok(Box::new(encoder))
+    }
+
+    fn decoder<'a>(&self, input: &'a mut dyn Read) -> Result<Box<dyn Read + 'a>> {
+        Ok(Box::new(zstd::Decoder::new(input)?))
+    }
+
+    fn compress_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
+        use zstd::zstd_safe::CParameter;
+        let mut compressor = zstd::bulk::Compressor::new(self.level)?;
+        compressor.set_parameter(CParameter::ChecksumFlag(self.checksum))?;
+        compressor.set_parameter(CParameter::ContentSizeFlag(self.content_size))?;
+        Ok(compressor.compress(data)?)
+    }
+}
+
+/// The codec families the benchmark knows how to build.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Family {
+    Gzip,
+    Zlib,
+    Deflate,
+    Zstd,
+    #[cfg(feature = "brotli")]
+    Brotli,
+    #[cfg(feature = "lz4")]
+    Lz4,
+    #[cfg(feature = "xz")]
+    Xz,
+}
+
+impl Family {
+    pub const ALL: &'static [Family] = &[
+        Family::Gzip,
+        Family::Zlib,
+        Family::Deflate,
+        Family::Zstd,
+        #[cfg(feature = "brotli")]
+        Family::Brotli,
+        #[cfg(feature = "lz4")]
+        Family::Lz4,
+        #[cfg(feature = "xz")]
+        Family::Xz,
+    ];
+
+    /// The three framings of the same DEFLATE stream, from most to least overhead.
+    pub const DEFLATE_FRAMINGS: [Family; 3] = [Family::Gzip, Family::Zlib, Family::Deflate];
+
+    pub fn levels(self) -> RangeInclusive<i32> {
+        match self {
+            Family::Gzip | Family::Zlib | Family::Deflate => 1..=9,
+            Family::Zstd => 1..=19,
+            #[cfg(feature = "brotli")]
+            Family::Brotli => 0..=11,
+            // For lz4 the "level" is the acceleration factor.
+            #[cfg(feature = "lz4")]
+            Family::Lz4 => 1..=64,
+            #[cfg(feature = "xz")]
+            Family::Xz => 0..=9,
+        }
+    }
+
+    pub fn default_level(self) -> i32 {
+        match self {
+            Family::Gzip | Family::Zlib | Family::Deflate => 6,
+            Family::Zstd => 3,
+            #[cfg(feature = "brotli")]
+            Family::Brotli => 11,
+            #[cfg(feature = "lz4")]
+            Family::Lz4 => 1,
+            #[cfg(feature = "xz")]
+            Family::Xz => 6,
+        }
+    }
+
+    pub fn build(self, level: i32) -> Box<dyn Codec> {
+        match self {
+            Family::Gzip => Box::new(Gzip { level: level as u32 }),
+            Family::Zlib => Box::new(Zlib { level: level as u32 }),
+            Family::Deflate => Box::new(Deflate { level: level as u32 }),
+            Family::Zstd => Box::new(Zstd { level }),
+            #[cfg(feature = "brotli")]
+            Family::Brotli => Box::new(Brotli { quality: level as u32, lgwin: Brotli::DEFAULT_LGWIN }),
+            #[cfg(feature = "lz4")]
+            Family::Lz4 => Box::new(Lz4 { mode: Lz4Mode::Frame, acceleration: level as u32 }),
+            #[cfg(feature = "xz")]
+            Family::Xz => Box::new(Xz { preset: level as u32, extreme: false }),
+        }
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Family::Gzip => "gzip",
+            Family::Zlib => "zlib",
+            Family::Deflate => "deflate",
+            Family::Zstd => "zstd",
+            #[cfg(feature = "brotli")]
+            Family::Brotli => "brotli",
+            #[cfg(feature = "lz4")]
+            Family::Lz4 => "lz4",
+            #[cfg(feature = "xz")]
+            Family::Xz => "xz",
+        })
+    }
+}
+
+impl FromStr for Family {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "gzip" | "gz" => Ok(Family::Gzip),
+            "zlib" => Ok(Family::Zlib),
+            "deflate" | "raw-deflate" => Ok(Family::Deflate),
+            "zstd" | "zst" => Ok(Family::Zstd),
+            #[cfg(feature = "brotli")]
+            "brotli" | "br" => Ok(Family::Brotli),
+            #[cfg(feature = "lz4")]
+            "lz4" => Ok(Family::Lz4),
+            #[cfg(feature = "xz")]
+            "xz" | "lzma" => Ok(Family::Xz),
+            other => bail!("unknown codec `{}`", other),
+        }
+    }
+}
+
+/// A codec family plus level, written as `family[:level]` on the command line.
+#[derive(Clone, Copy, Debug)]
+pub struct CodecSpec {
+    pub family: Family,
+    pub level: i32,
+}
+
+impl CodecSpec {
+    pub fn build(&self) -> Box<dyn Codec> {
+        self.family.build(self.level)
+    }
+}
+
+impl FromStr for CodecSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (family, level) = match s.split_once(':') {
+            Some((family, level)) => (family.parse::<Family>()?, level.parse::<i32>()?),
+            None => {
+                let family = s.parse::<Family>()?;
+                (family, family.default_level())
+            }
+        };
+        if !family.levels().contains(&level) {
+            bail!("{} level must be in {:?}, got {}", family, family.levels(), level);
+        }
+        Ok(CodecSpec { family, level })
+    }
+}
@@ -0,0 +1,257 @@
+//! `--parquet`: the same log records stored as Parquet instead of compressed JSON.
+//!
+//! Records are parsed once up front. Both sides are then timed from parsed
+//! records to bytes (write) and back to parsed rows (read): NDJSON through a
+//! solid gzip or zstd stream, versus Parquet with its own page compression.
+//! Columns are typed INT64 when every value of a key is an integer and UTF-8
+//! otherwise, with nested values kept as JSON text.
+
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::{bail, ensure, Context, Result};
+use bytes::Bytes;
+use clap::ValueEnum;
+use parquet::basic::{ConvertedType, GzipLevel, Repetition, Type as PhysicalType, ZstdLevel};
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+use serde_json::{Map, Value};
+use crate::bench::throughput;
+use crate::codec::{Codec, Gzip, Zstd};
+use crate::render::{Align, Table};
+use crate::results_parquet::write_column;
+use crate::{format_bytes, progress_bar, render};
+
+const ROW_GROUP_ROWS: usize = 64 * 1024;
+
+/// Page compression inside the Parquet file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ParquetCompression {
+    Uncompressed,
+    /// gzip level 6, as used for the per-file gzip stage
+    Gzip,
+    /// zstd level 3, as used for the archive
+    Zstd,
+    /// brotli at the parquet crate's default level
+    #[cfg(feature = "brotli")]
+    Brotli,
+}
+
+impl ParquetCompression {
+    fn codec(self) -> Result<parquet::basic::Compression> {
+        use parquet::basic::Compression;
+        Ok(match self {
+            ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::try_new(6)?),
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::try_new(3)?),
+            #[cfg(feature = "brotli")]
+            ParquetCompression::Brotli => Compression::BROTLI(Default::default()),
+        })
+    }
+
+    fn label(self) -> String {
+        format!("parquet+{}", self.to_possible_value().expect("no skipped variants").get_name())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Int64,
+    Text,
+}
+
+struct Field {
+    name: String,
+    kind: Kind,
+}
+
+pub struct ColumnarResult {
+    pub format: String,
+    pub size: u64,
+    pub write: Duration,
+    pub read: Duration,
+}
+
+pub struct Columnar {
+    pub records: usize,
+    pub columns: usize,
+    pub results: Vec<ColumnarResult>,
+}
+
+/// The records in one corpus file: a single object, an array of objects, or NDJSON.
+fn records(file: &[u8]) -> Result<Vec<Map<String, Value>>> {
+    let values = match serde_json::from_slice::<Value>(file) {
+        Ok(Value::Array(values)) => values,
+        Ok(value) => vec![value],
+        Err(_) => file
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.iter().all(u8::is_ascii_whitespace))
+            .map(serde_json::from_slice::<Value>)
+            .collect::<serde_json::Result<_>>()?,
+    };
+    values
+        .into_iter()
+        .map(|value| match value {
+            Value::Object(record) => Ok(record),
+            other => bail!("expected JSON objects, found {}", other),
+        })
+        .collect()
+}
+
+/// One field per key, in first-seen order.
+fn infer_schema(records: &[Map<String, Value>]) -> Vec<Field> {
+    let mut fields: Vec<Field> = Vec::new();
+    for record in records {
+        for (key, value) in record {
+            let kind = if value.is_i64() || value.is_null() { Kind::Int64 } else { Kind::Text };
+            match fields.iter_mut().find(|field| field.name == *key) {
+                Some(field) if kind == Kind::Text => field.kind = Kind::Text,
+                Some(_) => {}
+                None => fields.push(Field { name: key.clone(), kind }),
+            }
+        }
+    }
+    fields
+}
+
+fn parquet_schema(fields: &[Field]) -> Result<Type> {
+    let columns = fields
+        .iter()
+        .map(|field| {
+            let column = match field.kind {
+                Kind::Int64 => Type::primitive_type_builder(&field.name, PhysicalType::INT64),
+                Kind::Text => Type::primitive_type_builder(&field.name, PhysicalType::BYTE_ARRAY)
+                    .with_converted_type(ConvertedType::UTF8),
+            };
+            Ok(Arc::new(column.with_repetition(Repetition::OPTIONAL).build()?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Type::group_type_builder("logs").with_fields(columns).build()?)
+}
+
+fn text(value: &Value) -> Option<ByteArray> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(ByteArray::from(s.as_bytes().to_vec())),
+        other => Some(ByteArray::from(other.to_string().into_bytes())),
+    }
+}
+
+fn write_parquet(records: &[Map<String, Value>], fields: &[Field], schema: &Arc<Type>, compression: ParquetCompression) -> Result<Vec<u8>> {
+    let properties = WriterProperties::builder().set_compression(compression.codec()?).build();
+    let mut writer = SerializedFileWriter::new(Vec::new(), schema.clone(), Arc::new(properties))?;
+    for chunk in records.chunks(ROW_GROUP_ROWS) {
+        let mut row_group = writer.next_row_group()?;
+        for field in fields {
+            let mut column = row_group.next_column()?.context("schema has fewer columns than fields")?;
+            let values = chunk.iter().map(|record| record.get(&field.name));
+            match field.kind {
+                Kind::Int64 => write_column::<Int64Type>(&mut column, values.map(|value| value.and_then(Value::as_i64)).collect())?,
+                Kind::Text => write_column::<ByteArrayType>(&mut column, values.map(|value| value.and_then(text)).collect())?,
+            }
+            column.close()?;
+        }
+        row_group.close()?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+fn read_parquet(file: &[u8]) -> Result<usize> {
+    let reader = SerializedFileReader::new(Bytes::copy_from_slice(file))?;
+    let mut rows = 0;
+    for row in reader.get_row_iter(None)? {
+        row?;
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+fn write_json(records: &[Map<String, Value>], codec: &dyn Codec) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut encoder = codec.encoder(&mut output)?;
+    for record in records {
+        serde_json::to_writer(&mut encoder, record)?;
+        encoder.write_all(b"\n")?;
+    }
+    encoder.finish()?;
+    Ok(output)
+}
+
+fn read_json(frame: &[u8], codec: &dyn Codec) -> Result<usize> {
+    let mut input = frame;
+    let mut data = Vec::new();
+    codec.decoder(&mut input)?.read_to_end(&mut data)?;
+    let mut rows = 0;
+    for line in data.split(|&b| b == b'\n').filter(|line| !line.is_empty()) {
+        serde_json::from_slice::<Map<String, Value>>(line)?;
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+/// Times compressed NDJSON against Parquet with each of `compressions`.
+pub fn run(compressions: &[ParquetCompression], names: &[String], files: &[Vec<u8>]) -> Result<Columnar> {
+    let mut all = Vec::new();
+    for (name, file) in names.iter().zip(files) {
+        all.extend(records(file).with_context(|| format!("could not read records from {}", name))?);
+    }
+    ensure!(!all.is_empty(), "no JSON records to convert");
+    let fields = infer_schema(&all);
+    let schema = Arc::new(parquet_schema(&fields)?);
+
+    let json_codecs: [Box<dyn Codec>; 2] = [Box::new(Gzip { level: 6 }), Box::new(Zstd { level: 3 })];
+    let pb = progress_bar(json_codecs.len() + compressions.len());
+    let mut results = Vec::with_capacity(json_codecs.len() + compressions.len());
+
+    for codec in &json_codecs {
+        let start = Instant::now();
+        let frame = write_json(&all, codec.as_ref())?;
+        let write = start.elapsed();
+        let start = Instant::now();
+        let rows = read_json(&frame, codec.as_ref())?;
+        let read = start.elapsed();
+        ensure!(rows == all.len(), "{} NDJSON read back {} of {} records", codec.name(), rows, all.len());
+        results.push(ColumnarResult { format: format!("ndjson+{}", codec.name()), size: frame.len() as u64, write, read });
+        pb.inc(1);
+    }
+
+    for &compression in compressions {
+        let start = Instant::now();
+        let file = write_parquet(&all, &fields, &schema, compression)?;
+        let write = start.elapsed();
+        let start = Instant::now();
+        let rows = read_parquet(&file)?;
+        let read = start.elapsed();
+        ensure!(rows == all.len(), "{} read back {} of {} records", compression.label(), rows, all.len());
+        results.push(ColumnarResult { format: compression.label(), size: file.len() as u64, write, read });
+        pb.inc(1);
+    }
+    pb.finish_with_message("Parquet comparison complete!");
+
+    Ok(Columnar { records: all.len(), columns: fields.len(), results })
+}
+
+pub fn print(columnar: &Columnar, json_size: u64) {
+    println!("  {} records, {} columns", columnar.records, columnar.columns);
+    let cell = |time: Duration| format!("{} ({})", render::duration(time), render::mb_per_sec(throughput(json_size, time)));
+    let mut table = Table::new(&[
+        ("Format", Align::Left),
+        ("Size", Align::Right),
+        ("Ratio", Align::Right),
+        ("Write", Align::Right),
+        ("Read", Align::Right),
+    ]);
+    for result in &columnar.results {
+        table.row(vec![
+            result.format.to_string(),
+            format_bytes(result.size),
+            render::percent(result.size as f64 / json_size as f64),
+            cell(result.write),
+            cell(result.read),
+        ]);
+    }
+    print!("{}", table.terminal());
+}
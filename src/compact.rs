@@ -0,0 +1,248 @@
+//! `compact`: watches a log directory and compresses every file that log
+//! rotation leaves behind, the job this benchmark is prototyping.
+//!
+//! Rotated files are recognised by name (`app.log.1`, `app.log-20250709` by
+//! default) and only picked up once their size and modification time have not
+//! changed for `--settle`, so a file still being written or renamed is left
+//! alone. Each one is compressed into a temporary file next to itself, decoded
+//! again and compared with the original, synced and only then renamed into
+//! place, so an interrupted run never leaves a truncated output that a later
+//! run would take for finished; only then is the original deleted, if asked.
+//! Totals accumulate in a state file across restarts.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::codec::{Codec, CodecSpec};
+use crate::recompress::streams_equal;
+use crate::{durability, format_bytes, render};
+
+/// logrotate's numbered (`app.log.1`) and `dateext` (`app.log-20250709`) suffixes.
+pub const DEFAULT_PATTERN: &str = r"(\.\d+|-\d{8}(\d{2})?)$";
+
+pub struct Options {
+    pub codec: CodecSpec,
+    /// Names matching this are rotated files.
+    pub pattern: Regex,
+    /// How long a file must stay unchanged before it is compressed.
+    pub settle: Duration,
+    pub poll: Duration,
+    pub delete_originals: bool,
+    pub state: PathBuf,
+    /// Process what is ready now and exit instead of watching.
+    pub once: bool,
+}
+
+/// Totals over every run that used the same state file.
+#[derive(Serialize, Deserialize, Default)]
+struct Lifetime {
+    started_at: Option<String>,
+    updated_at: Option<String>,
+    files: u64,
+    input_bytes: u64,
+    output_bytes: u64,
+    compress_secs: f64,
+    verify_secs: f64,
+    deleted_originals: u64,
+    failures: u64,
+}
+
+impl Lifetime {
+    fn load(path: &Path) -> Result<Self> {
+        match fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data).with_context(|| format!("invalid compaction state {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Lifetime::default()),
+            Err(err) => Err(err).with_context(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    /// Writes and syncs the state next to its final path first, so a crash never leaves it half written.
+    fn save(&mut self, path: &Path) -> Result<()> {
+        self.updated_at = Some(chrono::Utc::now().to_rfc3339());
+        let temp = path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&temp)?);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        durability::sync_file(&writer.into_inner().map_err(|err| err.into_error())?)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    fn print(&self) {
+        println!(
+            "  Lifetime: {} files, {} → {} ({}), {} compressing, {} verifying, {} originals deleted, {} failures",
+            self.files,
+            format_bytes(self.input_bytes),
+            format_bytes(self.output_bytes),
+            render::percent(self.output_bytes as f64 / self.input_bytes.max(1) as f64),
+            render::seconds(self.compress_secs),
+            render::seconds(self.verify_secs),
+            self.deleted_originals,
+            self.failures
+        );
+    }
+}
+
+/// One compressed and verified file.
+struct Compacted {
+    input_bytes: u64,
+    output_bytes: u64,
+    compress_time: Duration,
+    verify_time: Duration,
+}
+
+/// The temporary sibling `output` is written to before it is verified and renamed into place.
+fn partial_path(output: &Path) -> PathBuf {
+    let mut partial = output.as_os_str().to_owned();
+    partial.push(".partial");
+    PathBuf::from(partial)
+}
+
+fn compact(codec: &dyn Codec, path: &Path, output: &Path) -> Result<Compacted> {
+    let partial = partial_path(output);
+    let start = Instant::now();
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    let mut encoder = codec.encoder(&mut writer)?;
+    let input_bytes = std::io::copy(&mut BufReader::new(File::open(path)?), &mut encoder)?;
+    encoder.finish()?;
+    let file = writer.into_inner().map_err(|err| err.into_error())?;
+    let compress_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut compressed = BufReader::new(File::open(&partial)?);
+    if !streams_equal(codec.decoder(&mut compressed)?, BufReader::new(File::open(path)?))? {
+        bail!("{} output does not match the original", codec.name());
+    }
+    let verify_time = start.elapsed();
+
+    // The original may be deleted next, so the copy and its name have to be on disk first.
+    let start = Instant::now();
+    durability::sync_file(&file)?;
+    fs::rename(&partial, output)?;
+    durability::sync_dir(output.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new(".")))?;
+    let compress_time = compress_time + start.elapsed();
+    Ok(Compacted { input_bytes, output_bytes: fs::metadata(output)?.len(), compress_time, verify_time })
+}
+
+/// Rotated files directly under `dir`, with their current size and modification time.
+fn rotated(dir: &Path, pattern: &Regex) -> Result<Vec<(PathBuf, (u64, SystemTime))>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let entry = entry?;
+        let Ok(metadata) = entry.metadata() else { continue };
+        if metadata.is_file() && pattern.is_match(&entry.file_name().to_string_lossy()) {
+            files.push((entry.path(), (metadata.len(), metadata.modified()?)));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Compresses rotated files under `dir` as they appear, until interrupted or, with `once`, after one pass.
+pub fn run(dir: &Path, options: &Options) -> Result<()> {
+    let codec = options.codec.build();
+    let mut lifetime = Lifetime::load(&options.state)?;
+    lifetime.started_at.get_or_insert_with(|| chrono::Utc::now().to_rfc3339());
+    println!(
+        "👀 Compacting rotated files under {} with {} (matching {}, settled for {}){}",
+        dir.display(),
+        codec.name(),
+        options.pattern,
+        render::duration(options.settle),
+        if options.delete_originals { ", deleting verified originals" } else { "" }
+    );
+    lifetime.print();
+
+    // When each file was first seen with its current size and modification time.
+    let mut seen: HashMap<PathBuf, ((u64, SystemTime), Instant)> = HashMap::new();
+    // Files that failed or whose output already exists are not retried until they change.
+    let mut skipped: HashMap<PathBuf, (u64, SystemTime)> = HashMap::new();
+    loop {
+        let files = rotated(dir, &options.pattern)?;
+        let present: HashSet<&PathBuf> = files.iter().map(|(path, _)| path).collect();
+        seen.retain(|path, _| present.contains(path));
+        skipped.retain(|path, _| present.contains(path));
+
+        let mut ready = Vec::new();
+        for (path, stamp) in &files {
+            if skipped.get(path) == Some(stamp) {
+                continue;
+            }
+            let first_seen = match seen.get(path) {
+                Some((seen_stamp, since)) if seen_stamp == stamp => *since,
+                _ => {
+                    seen.insert(path.clone(), (*stamp, Instant::now()));
+                    Instant::now()
+                }
+            };
+            // With --once there is no later pass, so only the modification time can show a file settled.
+            let settled = if options.once {
+                stamp.1.elapsed().unwrap_or_default() >= options.settle
+            } else {
+                first_seen.elapsed() >= options.settle
+            };
+            if settled {
+                ready.push((path.clone(), *stamp));
+            }
+        }
+
+        for (path, stamp) in ready {
+            let mut output = path.clone().into_os_string();
+            output.push(format!(".{}", codec.extension()));
+            let output = PathBuf::from(output);
+            // Usually compressed by an earlier run that kept the original.
+            if output.exists() {
+                skipped.insert(path, stamp);
+                continue;
+            }
+            match compact(codec.as_ref(), &path, &output) {
+                Ok(compacted) => {
+                    let deleted = options.delete_originals;
+                    if deleted {
+                        fs::remove_file(&path)?;
+                    }
+                    println!(
+                        "🗜️  {}: {} → {} ({}) in {}, verified in {}{}",
+                        path.display(),
+                        format_bytes(compacted.input_bytes),
+                        format_bytes(compacted.output_bytes),
+                        render::percent(compacted.output_bytes as f64 / compacted.input_bytes.max(1) as f64),
+                        render::duration(compacted.compress_time),
+                        render::duration(compacted.verify_time),
+                        if deleted { ", original deleted" } else { "" }
+                    );
+                    lifetime.files += 1;
+                    lifetime.input_bytes += compacted.input_bytes;
+                    lifetime.output_bytes += compacted.output_bytes;
+                    lifetime.compress_secs += compacted.compress_time.as_secs_f64();
+                    lifetime.verify_secs += compacted.verify_time.as_secs_f64();
+                    lifetime.deleted_originals += deleted as u64;
+                    if !deleted {
+                        // The original stays, so it must not be compressed again on the next pass.
+                        skipped.insert(path.clone(), stamp);
+                    }
+                }
+                Err(err) => {
+                    // Never leave an unverified copy next to the original.
+                    let _ = fs::remove_file(partial_path(&output));
+                    println!("⚠️  {}: {:#}", path.display(), err);
+                    lifetime.failures += 1;
+                    skipped.insert(path.clone(), stamp);
+                }
+            }
+            seen.remove(&path);
+            lifetime.save(&options.state)?;
+        }
+
+        if options.once {
+            lifetime.save(&options.state)?;
+            lifetime.print();
+            return Ok(());
+        }
+        std::thread::sleep(options.poll);
+    }
+}
@@ -0,0 +1,323 @@
+//! `corpora`: runs the same codecs over several corpora listed in a config file
+//! and shows how their rankings shift from one kind of data to another.
+//! `compare-corpora` does the same for exactly two, an old and a new version of
+//! the same logs, and reports how the change moved their compressed size.
+//!
+//! The config is JSON:
+//!
+//! ```json
+//! {
+//!   "codecs": ["gzip:6", "zstd:3", "zstd:19"],
+//!   "corpora": [
+//!     { "name": "synthetic", "generate": 2000, "seed": 1 },
+//!     { "name": "nginx", "path": "/var/log/nginx" },
+//!     { "name": "events", "path": "dump.ndjson", "lines_per_file": 1000 }
+//!   ]
+//! }
+//! ```
+//!
+//! A directory contributes every file under it; a single file is one document,
+//! or is cut into documents of `lines_per_file` lines, like an NDJSON dump
+//! written out by a log shipper.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Deserialize;
+use crate::bench::{self, Measurement};
+use crate::codec::{CodecSpec, Family};
+use crate::corpus::generate_json_with;
+use crate::render::{Align, Table};
+use crate::{format_bytes, plan, progress_bar, render};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// `family[:level]` specs; every enabled family at its default level when empty.
+    #[serde(default)]
+    codecs: Vec<String>,
+    corpora: Vec<CorpusConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CorpusConfig {
+    name: String,
+    /// Number of synthetic documents to generate.
+    generate: Option<usize>,
+    /// Seed for `generate`; random when not given.
+    seed: Option<u64>,
+    /// Directory or file to read instead.
+    path: Option<PathBuf>,
+    /// Cut a single file at `path` into documents of this many lines.
+    lines_per_file: Option<usize>,
+}
+
+impl CorpusConfig {
+    fn load(&self, base: &Path) -> Result<Vec<Vec<u8>>> {
+        match (self.generate, &self.path) {
+            (Some(count), None) => {
+                let mut rng = StdRng::seed_from_u64(self.seed.unwrap_or_else(rand::random));
+                Ok((0..count).map(|_| serde_json::to_vec_pretty(&generate_json_with(&mut rng))).collect::<serde_json::Result<_>>()?)
+            }
+            (None, Some(path)) => {
+                // Relative paths are relative to the config file, so configs can travel with their data.
+                let path = base.join(path);
+                if path.is_dir() {
+                    anyhow::ensure!(self.lines_per_file.is_none(), "corpus `{}`: lines_per_file only applies to a single file", self.name);
+                    return plan::file_sizes(&path)?.iter().map(|(file, _)| Ok(fs::read(file)?)).collect();
+                }
+                let data = fs::read(&path).with_context(|| format!("corpus `{}`: failed to read {}", self.name, path.display()))?;
+                match self.lines_per_file {
+                    None => Ok(vec![data]),
+                    Some(0) => bail!("corpus `{}`: lines_per_file must be at least 1", self.name),
+                    Some(lines) => Ok(split_lines(&data, lines)),
+                }
+            }
+            _ => bail!("corpus `{}` needs exactly one of `generate` or `path`", self.name),
+        }
+    }
+}
+
+/// Cuts `data` into documents of `lines` lines each, keeping the newlines.
+fn split_lines(data: &[u8], lines: usize) -> Vec<Vec<u8>> {
+    let mut documents = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let end = rest
+            .iter()
+            .enumerate()
+            .filter(|(_, &byte)| byte == b'\n')
+            .nth(lines - 1)
+            .map_or(rest.len(), |(i, _)| i + 1);
+        documents.push(rest[..end].to_vec());
+        rest = &rest[end..];
+    }
+    documents
+}
+
+struct CorpusResult {
+    name: String,
+    files: usize,
+    bytes: u64,
+    /// One measurement per codec, in config order.
+    measurements: Vec<Measurement>,
+}
+
+impl CorpusResult {
+    /// 1-based rank of every codec by compressed size, smallest first.
+    fn size_ranks(&self) -> Vec<usize> {
+        ranks(&self.measurements, |m| m.compressed_size as f64)
+    }
+
+    /// 1-based rank of every codec by compression time, fastest first.
+    fn speed_ranks(&self) -> Vec<usize> {
+        ranks(&self.measurements, |m| m.compress_time.as_secs_f64())
+    }
+}
+
+fn ranks(measurements: &[Measurement], key: impl Fn(&Measurement) -> f64) -> Vec<usize> {
+    measurements.iter().map(|m| 1 + measurements.iter().filter(|other| key(other) < key(m)).count()).collect()
+}
+
+/// Files compressed untimed before each codec's measurements in `compare`.
+const WARM_UP_FILES: usize = 100;
+
+fn default_codecs() -> Vec<CodecSpec> {
+    Family::ALL.iter().map(|&family| CodecSpec { family, level: family.default_level() }).collect()
+}
+
+fn read_config(path: &Path) -> Result<(Config, Vec<CodecSpec>)> {
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let config: Config = serde_json::from_str(&text).with_context(|| format!("invalid corpora config {}", path.display()))?;
+    anyhow::ensure!(!config.corpora.is_empty(), "{} lists no corpora", path.display());
+    let codecs = if config.codecs.is_empty() {
+        default_codecs()
+    } else {
+        config.codecs.iter().map(|spec| spec.parse::<CodecSpec>().with_context(|| format!("invalid codec `{}`", spec))).collect::<Result<_>>()?
+    };
+    Ok((config, codecs))
+}
+
+/// Measures every codec of the config at `config_path` on every corpus it lists.
+pub fn run(config_path: &Path) -> Result<()> {
+    let (config, specs) = read_config(config_path)?;
+    let base = config_path.parent().unwrap_or(Path::new(""));
+    let codecs: Vec<_> = specs.iter().map(CodecSpec::build).collect();
+    let names: Vec<String> = codecs.iter().map(|codec| codec.name()).collect();
+
+    let mut results = Vec::with_capacity(config.corpora.len());
+    for corpus in &config.corpora {
+        let files = corpus.load(base)?;
+        anyhow::ensure!(!files.is_empty(), "corpus `{}` is empty", corpus.name);
+        let bytes: u64 = files.iter().map(|file| file.len() as u64).sum();
+        println!("\n📚 {}: {} files, {}", corpus.name, files.len(), format_bytes(bytes));
+
+        let pb = progress_bar(codecs.len());
+        let mut measurements = Vec::with_capacity(codecs.len());
+        for codec in &codecs {
+            pb.set_message(codec.name());
+            measurements.push(bench::measure(codec.as_ref(), &files)?);
+            pb.inc(1);
+        }
+        pb.finish_with_message(format!("{} complete!", corpus.name));
+
+        let result = CorpusResult { name: corpus.name.clone(), files: files.len(), bytes, measurements };
+        print_corpus(&result, &names);
+        results.push(result);
+    }
+
+    print_comparison(&results, &names);
+    Ok(())
+}
+
+fn print_corpus(result: &CorpusResult, names: &[String]) {
+    let mut table = Table::new(&[
+        ("Codec", Align::Left),
+        ("Size", Align::Right),
+        ("Ratio", Align::Right),
+        ("Compress", Align::Right),
+        ("Decompress", Align::Right),
+        ("Rank", Align::Right),
+    ]);
+    for ((name, m), rank) in names.iter().zip(&result.measurements).zip(result.size_ranks()) {
+        table.row(vec![
+            name.clone(),
+            format_bytes(m.compressed_size),
+            render::percent(m.compressed_size as f64 / result.bytes as f64),
+            render::mb_per_sec(bench::throughput(result.bytes, m.compress_time)),
+            render::mb_per_sec(bench::throughput(result.bytes, m.decompress_time)),
+            format!("#{}", rank),
+        ]);
+    }
+    print!("{}", table.terminal());
+}
+
+/// One column per corpus with every codec's ratio and rank, then the winner of each.
+fn print_comparison(results: &[CorpusResult], names: &[String]) {
+    println!("\n🧮 Cross-corpus comparison (ratio and rank by size, then rank by compression speed):");
+    let mut columns = vec![("Codec", Align::Left)];
+    columns.extend(results.iter().map(|result| (result.name.as_str(), Align::Right)));
+    let mut table = Table::new(&columns);
+    let size_ranks: Vec<_> = results.iter().map(CorpusResult::size_ranks).collect();
+    let speed_ranks: Vec<_> = results.iter().map(CorpusResult::speed_ranks).collect();
+    for (i, name) in names.iter().enumerate() {
+        let mut row = vec![name.clone()];
+        for (result, (sizes, speeds)) in results.iter().zip(size_ranks.iter().zip(&speed_ranks)) {
+            let ratio = result.measurements[i].compressed_size as f64 / result.bytes as f64;
+            row.push(format!("{} #{} / #{}", render::percent(ratio), sizes[i], speeds[i]));
+        }
+        table.row(row);
+    }
+    print!("{}", table.terminal());
+
+    println!();
+    let winner = |ranks: &[usize]| names[ranks.iter().position(|&rank| rank == 1).unwrap_or(0)].clone();
+    for (result, (sizes, speeds)) in results.iter().zip(size_ranks.iter().zip(&speed_ranks)) {
+        println!(
+            "  {:<22} smallest: {:<16} fastest: {:<16} ({} files)",
+            result.name,
+            winner(sizes),
+            winner(speeds),
+            result.files
+        );
+    }
+    let smallest: Vec<_> = size_ranks.iter().map(|ranks| winner(ranks)).collect();
+    if smallest.iter().all(|name| *name == smallest[0]) {
+        println!("  {} is the smallest on every corpus.", smallest[0]);
+    } else {
+        println!("  The smallest codec depends on the corpus.");
+    }
+}
+
+/// Files of one side of `compare`, a directory or a single file.
+fn load_side(label: &str, path: &Path) -> Result<(Vec<Vec<u8>>, u64)> {
+    let corpus = CorpusConfig { name: label.to_string(), generate: None, seed: None, path: Some(path.to_path_buf()), lines_per_file: None };
+    let files = corpus.load(Path::new(""))?;
+    anyhow::ensure!(!files.is_empty(), "{} corpus {} is empty", label, path.display());
+    let bytes: u64 = files.iter().map(|file| file.len() as u64).sum();
+    println!("📚 {}: {} ({} files, {})", label, path.display(), files.len(), format_bytes(bytes));
+    Ok((files, bytes))
+}
+
+/// Runs `specs` (every enabled family at its default level when empty) over `old` and `new`
+/// and prints how the compressed size and speed of each moved.
+pub fn compare(old: &Path, new: &Path, specs: &[CodecSpec]) -> Result<()> {
+    let specs = if specs.is_empty() { default_codecs() } else { specs.to_vec() };
+    let codecs: Vec<_> = specs.iter().map(CodecSpec::build).collect();
+    let (old_files, old_bytes) = load_side("old", old)?;
+    let (new_files, new_bytes) = load_side("new", new)?;
+
+    // Each codec runs on both sides back to back after a warm-up, so neither side pays for cold caches.
+    let pb = progress_bar(codecs.len());
+    let (mut before, mut after) = (Vec::with_capacity(codecs.len()), Vec::with_capacity(codecs.len()));
+    for codec in &codecs {
+        pb.set_message(codec.name());
+        bench::measure(codec.as_ref(), &old_files[..old_files.len().min(WARM_UP_FILES)])?;
+        before.push(bench::measure(codec.as_ref(), &old_files)?);
+        after.push(bench::measure(codec.as_ref(), &new_files)?);
+        pb.inc(1);
+    }
+    pb.finish_with_message("Both corpora complete!");
+    let before = CorpusResult { name: "old".to_string(), files: old_files.len(), bytes: old_bytes, measurements: before };
+    let after = CorpusResult { name: "new".to_string(), files: new_files.len(), bytes: new_bytes, measurements: after };
+
+    println!("\n🆚 Old vs new corpus (per-file, in memory):");
+    println!(
+        "  Raw: {} -> {} ({}), {} -> {} files",
+        format_bytes(before.bytes),
+        format_bytes(after.bytes),
+        render::change(after.bytes as f64, before.bytes as f64),
+        before.files,
+        after.files
+    );
+    let mut table = Table::new(&[
+        ("Codec", Align::Left),
+        ("Old size", Align::Right),
+        ("New size", Align::Right),
+        ("Change", Align::Right),
+        ("Ratio", Align::Right),
+        ("Δ compress", Align::Right),
+        ("Δ decomp.", Align::Right),
+    ]);
+    let raw_delta = after.bytes as f64 - before.bytes as f64;
+    for ((codec, old), new) in codecs.iter().zip(&before.measurements).zip(&after.measurements) {
+        table.row(vec![
+            codec.name(),
+            format_bytes(old.compressed_size),
+            format_bytes(new.compressed_size),
+            render::change(new.compressed_size as f64, old.compressed_size as f64),
+            format!(
+                "{} -> {}",
+                render::percent(old.compressed_size as f64 / before.bytes as f64),
+                render::percent(new.compressed_size as f64 / after.bytes as f64)
+            ),
+            render::change(bench::throughput(after.bytes, new.compress_time), bench::throughput(before.bytes, old.compress_time)),
+            render::change(bench::throughput(after.bytes, new.decompress_time), bench::throughput(before.bytes, old.decompress_time)),
+        ]);
+    }
+    print!("{}", table.terminal());
+
+    // What the raw bytes that changed were worth once compressed, against the old corpus as a whole.
+    println!();
+    if raw_delta == 0.0 {
+        println!("  The raw size did not change, so every difference above is in how the content compresses.");
+        return Ok(());
+    }
+    for (codec, (old, new)) in codecs.iter().zip(before.measurements.iter().zip(&after.measurements)) {
+        let marginal = (new.compressed_size as f64 - old.compressed_size as f64) / raw_delta;
+        let overall = old.compressed_size as f64 / before.bytes as f64;
+        println!(
+            "  {:<16} the {} {} compressed at {}, {} than the old corpus as a whole ({})",
+            codec.name(),
+            format_bytes(raw_delta.abs() as u64),
+            if raw_delta < 0.0 { "removed" } else { "added" },
+            render::percent(marginal),
+            if marginal < overall { "better" } else { "worse" },
+            render::percent(overall)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,259 @@
+//! The generated corpus: JSON log records with a fixed set of keys and random
+//! values, each drawn by the generator `FIELDS` registers for it. `CorpusBuilder`
+//! adds fields of your own.
+
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Keys of every generated record, in order.
+pub const FIXED_KEYS: [&str; 15] = {
+    let mut keys = [""; 15];
+    let mut i = 0;
+    while i < keys.len() {
+        keys[i] = FIELDS[i].0;
+        i += 1;
+    }
+    keys
+};
+
+/// The record shape produced by `generate_json`, and the schema `--pipeline` validates against.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub message: String,
+    pub source_ip: String,
+    pub user_id: String,
+    pub request_id: String,
+    pub http_method: String,
+    pub http_path: String,
+    pub http_status: u16,
+    pub user_agent: String,
+    pub response_time_ms: u32,
+    pub app_version: String,
+    pub service_name: String,
+    pub region: String,
+    pub payload: String,
+}
+
+impl LogEntry {
+    /// Checks what the field types alone cannot: formats and the sets the generator draws from.
+    pub fn validate(&self) -> Result<(), String> {
+        if chrono::DateTime::parse_from_rfc3339(&self.timestamp).is_err() {
+            return Err(format!("timestamp `{}` is not RFC 3339", self.timestamp));
+        }
+        if !["INFO", "WARN", "ERROR", "DEBUG"].contains(&self.level.as_str()) {
+            return Err(format!("unknown level `{}`", self.level));
+        }
+        if self.source_ip.parse::<std::net::Ipv4Addr>().is_err() {
+            return Err(format!("source_ip `{}` is not an IPv4 address", self.source_ip));
+        }
+        if !["GET", "POST", "PUT", "DELETE"].contains(&self.http_method.as_str()) {
+            return Err(format!("unknown http_method `{}`", self.http_method));
+        }
+        if !self.http_path.starts_with('/') {
+            return Err(format!("http_path `{}` is not absolute", self.http_path));
+        }
+        if !(100..600).contains(&self.http_status) {
+            return Err(format!("http_status {} is out of range", self.http_status));
+        }
+        Ok(())
+    }
+}
+
+fn random_string(rng: &mut dyn RngCore, length: usize) -> String {
+    rng.sample_iter(&rand::distributions::Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+fn one_of<T: Copy>(rng: &mut dyn RngCore, choices: &[T]) -> T {
+    choices[rng.gen_range(0..choices.len())]
+}
+
+fn timestamp(rng: &mut dyn RngCore) -> Value {
+    let hour = rng.gen_range(0..24);
+    let minute = rng.gen_range(0..60);
+    let second = rng.gen_range(0..60);
+    let millisecond = rng.gen_range(0..1000);
+    Value::String(format!("2025-07-09T{:02}:{:02}:{:02}.{:03}Z", hour, minute, second, millisecond))
+}
+
+fn level(rng: &mut dyn RngCore) -> Value {
+    Value::String(one_of(rng, &["INFO", "WARN", "ERROR", "DEBUG"]).to_string())
+}
+
+fn message(rng: &mut dyn RngCore) -> Value {
+    let length = rng.gen_range(50..151);
+    Value::String(random_string(rng, length))
+}
+
+fn source_ip(rng: &mut dyn RngCore) -> Value {
+    Value::String(format!(
+        "{}.{}.{}.{}",
+        rng.gen_range(1..255),
+        rng.gen_range(1..255),
+        rng.gen_range(1..255),
+        rng.gen_range(1..255)
+    ))
+}
+
+fn user_id(rng: &mut dyn RngCore) -> Value {
+    Value::String(format!("user-{}", rng.gen_range(1000..10000)))
+}
+
+fn request_id(rng: &mut dyn RngCore) -> Value {
+    Value::String(random_string(rng, 32))
+}
+
+fn http_method(rng: &mut dyn RngCore) -> Value {
+    Value::String(one_of(rng, &["GET", "POST", "PUT", "DELETE"]).to_string())
+}
+
+fn http_path(rng: &mut dyn RngCore) -> Value {
+    let segments = rng.gen_range(1..4);
+    let path = (0..segments)
+        .map(|_| {
+            let length = rng.gen_range(5..11);
+            random_string(rng, length)
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    Value::String(format!("/{}", path))
+}
+
+fn http_status(rng: &mut dyn RngCore) -> Value {
+    Value::Number(one_of(rng, &[200, 201, 400, 404, 500]).into())
+}
+
+fn user_agent(_: &mut dyn RngCore) -> Value {
+    Value::String("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36".to_string())
+}
+
+fn response_time_ms(rng: &mut dyn RngCore) -> Value {
+    Value::Number(rng.gen_range(10..501).into())
+}
+
+fn app_version(rng: &mut dyn RngCore) -> Value {
+    Value::String(format!("{}.{}.{}", rng.gen_range(1..6), rng.gen_range(0..10), rng.gen_range(0..10)))
+}
+
+fn service_name(rng: &mut dyn RngCore) -> Value {
+    Value::String(one_of(rng, &["auth-service", "product-service", "order-service"]).to_string())
+}
+
+fn region(rng: &mut dyn RngCore) -> Value {
+    Value::String(one_of(rng, &["us-east-1", "us-west-2", "eu-central-1"]).to_string())
+}
+
+fn payload(rng: &mut dyn RngCore) -> Value {
+    Value::String(random_string(rng, 2500))
+}
+
+/// Draws one field's value.
+pub type FieldFn = fn(&mut dyn RngCore) -> Value;
+
+/// The built-in fields of every generated record, in order, with their generators.
+pub const FIELDS: [(&str, FieldFn); 15] = [
+    ("timestamp", timestamp),
+    ("level", level),
+    ("message", message),
+    ("source_ip", source_ip),
+    ("user_id", user_id),
+    ("request_id", request_id),
+    ("http_method", http_method),
+    ("http_path", http_path),
+    ("http_status", http_status),
+    ("user_agent", user_agent),
+    ("response_time_ms", response_time_ms),
+    ("app_version", app_version),
+    ("service_name", service_name),
+    ("region", region),
+    ("payload", payload),
+];
+
+/// One random log record with the fixed set of keys.
+pub fn generate_json() -> Value {
+    generate_json_with(&mut thread_rng())
+}
+
+/// Like `generate_json`, drawing from `rng` so a seeded generator repeats the same records.
+pub fn generate_json_with(rng: &mut impl Rng) -> Value {
+    let mut data = Map::new();
+    for (key, field) in FIELDS {
+        data.insert(key.to_string(), field(rng));
+    }
+    Value::Object(data)
+}
+
+/// `count` generated documents, pretty-printed exactly as the CLI writes them to disk.
+pub fn generate(count: usize) -> serde_json::Result<Vec<Vec<u8>>> {
+    (0..count).map(|_| serde_json::to_vec_pretty(&generate_json())).collect()
+}
+
+/// A custom field's generator, called once per record.
+pub type Generator = Box<dyn Fn(&mut dyn RngCore) -> Value + Send + Sync>;
+
+/// Records of the built-in fields plus any added with `with_field`, for benchmarking on a log shape of your own.
+///
+/// Fields keep their order: replacing one keeps its place, new ones are appended.
+pub struct CorpusBuilder {
+    fields: Vec<(String, Generator)>,
+}
+
+impl Default for CorpusBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CorpusBuilder {
+    /// Starts from the built-in fields of `FIELDS`.
+    pub fn new() -> Self {
+        let fields = FIELDS.iter().map(|&(key, field)| (key.to_string(), Box::new(field) as Generator)).collect();
+        CorpusBuilder { fields }
+    }
+
+    /// Starts without any fields.
+    pub fn empty() -> Self {
+        CorpusBuilder { fields: Vec::new() }
+    }
+
+    /// Generates `name` with `generator`, replacing a field of that name.
+    pub fn with_field(mut self, name: &str, generator: impl Fn(&mut dyn RngCore) -> Value + Send + Sync + 'static) -> Self {
+        let generator: Generator = Box::new(generator);
+        match self.fields.iter_mut().find(|(key, _)| key == name) {
+            Some((_, field)) => *field = generator,
+            None => self.fields.push((name.to_string(), generator)),
+        }
+        self
+    }
+
+    pub fn without_field(mut self, name: &str) -> Self {
+        self.fields.retain(|(key, _)| key != name);
+        self
+    }
+
+    /// Keys of every record, in order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|(key, _)| key.as_str())
+    }
+
+    /// One record, drawing from `rng` so a seeded generator repeats the same records.
+    pub fn record(&self, rng: &mut impl Rng) -> Value {
+        let mut data = Map::new();
+        for (key, field) in &self.fields {
+            data.insert(key.clone(), field(rng));
+        }
+        Value::Object(data)
+    }
+
+    /// `count` documents drawn from `seed`, pretty-printed exactly as the CLI writes them to disk.
+    pub fn generate(&self, count: usize, seed: u64) -> serde_json::Result<Vec<Vec<u8>>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..count).map(|_| serde_json::to_vec_pretty(&self.record(&mut rng))).collect()
+    }
+}
@@ -0,0 +1,307 @@
+//! Per-file zstd compression with dictionaries trained on a sample of the corpus.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use crate::bench::throughput;
+use crate::corpus::CorpusBuilder;
+use crate::render::{Align, Table};
+use crate::{format_bytes, plan, progress_bar, render, NUM_FILES};
+
+/// Default dictionary size, matching the `zstd --train` default.
+pub const DEFAULT_DICT_SIZE: usize = 112 * 1024;
+
+/// Every n-th file is used as a training sample.
+const SAMPLE_STRIDE: usize = 10;
+
+/// Partition of the files `partition_by` cannot parse.
+const NOT_JSON: &str = "(not JSON)";
+
+pub struct DictResult {
+    pub requested_size: usize,
+    pub dict_size: usize,
+    pub train_time: Duration,
+    pub compressed_size: u64,
+    pub compress_time: Duration,
+    pub decompress_time: Duration,
+}
+
+fn from_samples(samples: &[&[u8]], max_size: usize) -> Result<Vec<u8>> {
+    zstd::dict::from_samples(samples, max_size)
+        .with_context(|| format!("failed to train a {} dictionary", format_bytes(max_size as u64)))
+}
+
+/// Trains a dictionary of at most `max_size` bytes from every `SAMPLE_STRIDE`-th file.
+pub fn train(files: &[Vec<u8>], max_size: usize) -> Result<Vec<u8>> {
+    let samples: Vec<&[u8]> = files.iter().step_by(SAMPLE_STRIDE).map(Vec::as_slice).collect();
+    from_samples(&samples, max_size)
+}
+
+/// ID zstd stores in a dictionary and in the header of every frame compressed with it.
+pub fn id(dict: &[u8]) -> Option<u32> {
+    zstd::zstd_safe::get_dict_id_from_dict(dict).map(|id| id.get())
+}
+
+/// Reads a dictionary written by `dict train` or `zstd --train`.
+pub fn load(path: &Path) -> Result<Vec<u8>> {
+    let dict = fs::read(path).with_context(|| format!("failed to read dictionary {}", path.display()))?;
+    anyhow::ensure!(id(&dict).is_some(), "{} is not a zstd dictionary", path.display());
+    Ok(dict)
+}
+
+/// `dict train`: trains a dictionary on the same samples as `--dict`, from the
+/// files under `input` or the corpus a run with `--seed seed` generates, and
+/// writes it to `output`.
+pub fn train_to_file(input: Option<&Path>, seed: u64, max_size: usize, output: &Path) -> Result<()> {
+    let start = Instant::now();
+    let (files, samples) = match input {
+        Some(dir) => {
+            let files = plan::file_sizes(dir)?;
+            let samples = files.iter().step_by(SAMPLE_STRIDE).map(|(path, _)| Ok(fs::read(path)?)).collect::<Result<Vec<_>>>()?;
+            (files.len(), samples)
+        }
+        None => {
+            println!("Generating the corpus of seed {}", seed);
+            let files = CorpusBuilder::new().generate(NUM_FILES, seed)?;
+            (NUM_FILES, files.into_iter().step_by(SAMPLE_STRIDE).collect())
+        }
+    };
+    anyhow::ensure!(!samples.is_empty(), "no files to train on");
+    let dict = from_samples(&samples.iter().map(Vec::as_slice).collect::<Vec<_>>(), max_size)?;
+    fs::write(output, &dict).with_context(|| format!("failed to write {}", output.display()))?;
+
+    println!(
+        "Trained a {} dictionary with ID {} on {} of {} files in {}",
+        format_bytes(dict.len() as u64),
+        id(&dict).unwrap_or_default(),
+        samples.len(),
+        files,
+        render::duration(start.elapsed())
+    );
+    println!("Wrote {0}; use it with `--dict {0}`, or `zstd -D {0}`", output.display());
+    Ok(())
+}
+
+/// Measures per-file compression with a dictionary loaded from disk instead of trained.
+pub fn reuse(files: &[Vec<u8>], dict: &[u8], level: i32) -> Result<DictResult> {
+    let (compressed_size, compress_time, decompress_time) = measure(files, dict, level)?;
+    Ok(DictResult {
+        requested_size: dict.len(),
+        dict_size: dict.len(),
+        train_time: Duration::ZERO,
+        compressed_size,
+        compress_time,
+        decompress_time,
+    })
+}
+
+/// Compresses and decompresses every file individually against `dict`.
+pub fn measure(files: &[Vec<u8>], dict: &[u8], level: i32) -> Result<(u64, Duration, Duration)> {
+    let mut compressor = zstd::bulk::Compressor::with_dictionary(level, dict)?;
+    let mut decompressor = zstd::bulk::Decompressor::with_dictionary(dict)?;
+
+    let start = Instant::now();
+    let compressed = files
+        .iter()
+        .map(|file| compressor.compress(file))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let compress_time = start.elapsed();
+
+    let start = Instant::now();
+    for (frame, original) in compressed.iter().zip(files) {
+        let decompressed = decompressor.decompress(frame, original.len())?;
+        anyhow::ensure!(decompressed == *original, "dictionary round trip mismatch");
+    }
+    let decompress_time = start.elapsed();
+
+    let compressed_size = compressed.iter().map(|frame| frame.len() as u64).sum();
+    Ok((compressed_size, compress_time, decompress_time))
+}
+
+/// Trains one dictionary per requested size and measures per-file compression with each.
+pub fn sweep(files: &[Vec<u8>], sizes: &[usize], level: i32) -> Result<Vec<DictResult>> {
+    let pb = progress_bar(sizes.len());
+    let mut results = Vec::with_capacity(sizes.len());
+
+    for &requested_size in sizes {
+        let start = Instant::now();
+        let dict = train(files, requested_size)?;
+        let train_time = start.elapsed();
+
+        let (compressed_size, compress_time, decompress_time) = measure(files, &dict, level)?;
+        results.push(DictResult {
+            requested_size,
+            dict_size: dict.len(),
+            train_time,
+            compressed_size,
+            compress_time,
+            decompress_time,
+        });
+        pb.inc(1);
+    }
+    pb.finish_with_message("Dictionary sweep complete!");
+
+    Ok(results)
+}
+
+pub struct PartitionResult {
+    pub requested_size: usize,
+    /// Compressed data plus every partition's dictionary.
+    pub total_size: u64,
+    pub train_time: Duration,
+    /// Partitions too small to train on, which shared one dictionary instead.
+    pub merged: Vec<String>,
+}
+
+pub struct Partitioned {
+    pub field: String,
+    pub partitions: Vec<(String, usize)>,
+    pub results: Vec<PartitionResult>,
+}
+
+/// Groups file indices by the value of `field` in each JSON document. Files that
+/// are not JSON, such as plain-text logs in an `--input` directory, share a
+/// partition of their own.
+fn partition_by(files: &[Vec<u8>], field: &str) -> BTreeMap<String, Vec<usize>> {
+    let mut partitions: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, file) in files.iter().enumerate() {
+        let key = match serde_json::from_slice::<serde_json::Value>(file) {
+            Ok(value) => match value.get(field) {
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => "(missing)".to_string(),
+            },
+            Err(_) => NOT_JSON.to_string(),
+        };
+        partitions.entry(key).or_default().push(i);
+    }
+    if let Some(indices) = partitions.get(NOT_JSON) {
+        println!("⚠️  {} of {} files are not JSON, so they form the {} partition", indices.len(), files.len(), NOT_JSON);
+    }
+    partitions
+}
+
+/// Trains one dictionary per distinct value of `field` for each requested size.
+///
+/// A partition with too few samples to train a dictionary of that size joins a
+/// shared one trained on all such partitions together; if even that fails,
+/// their files are compressed without a dictionary.
+pub fn sweep_partitioned(files: &[Vec<u8>], field: &str, sizes: &[usize], level: i32) -> Result<Partitioned> {
+    let partitions = partition_by(files, field);
+    let pb = progress_bar(sizes.len() * partitions.len());
+    let mut results = Vec::with_capacity(sizes.len());
+
+    for &requested_size in sizes {
+        let mut total_size = 0;
+        let mut train_time = Duration::ZERO;
+        let (mut merged, mut leftovers) = (Vec::new(), Vec::new());
+        for (key, indices) in &partitions {
+            let members: Vec<Vec<u8>> = indices.iter().map(|&i| files[i].clone()).collect();
+            let start = Instant::now();
+            let trained = train(&members, requested_size);
+            train_time += start.elapsed();
+            pb.inc(1);
+            let Ok(dict) = trained else {
+                merged.push(key.clone());
+                leftovers.extend(members);
+                continue;
+            };
+            let (compressed_size, _, _) = measure(&members, &dict, level)?;
+            total_size += compressed_size + dict.len() as u64;
+        }
+        if !leftovers.is_empty() {
+            let start = Instant::now();
+            let dict = train(&leftovers, requested_size).unwrap_or_default();
+            train_time += start.elapsed();
+            pb.println(format!(
+                "⚠️  {} dictionary: {} of {} partitions by {} are too small to train on, so they share {}",
+                format_bytes(requested_size as u64),
+                merged.len(),
+                partitions.len(),
+                field,
+                if dict.is_empty() { "no dictionary".to_string() } else { format!("one {} dictionary", format_bytes(dict.len() as u64)) }
+            ));
+            let (compressed_size, _, _) = measure(&leftovers, &dict, level)?;
+            total_size += compressed_size + dict.len() as u64;
+        }
+        results.push(PartitionResult { requested_size, total_size, train_time, merged });
+    }
+    pb.finish_with_message("Partitioned dictionary sweep complete!");
+
+    Ok(Partitioned {
+        field: field.to_string(),
+        partitions: partitions.into_iter().map(|(key, indices)| (key, indices.len())).collect(),
+        results,
+    })
+}
+
+pub fn print_results(results: &[DictResult], baseline: u64, json_size: u64) {
+    let mut table = Table::new(&[
+        ("Dict", Align::Left),
+        ("Actual", Align::Right),
+        ("Size", Align::Right),
+        ("Ratio", Align::Right),
+        ("Train", Align::Right),
+        ("Compress", Align::Right),
+        ("Decompress", Align::Right),
+    ]);
+    for result in results {
+        // The dictionary has to be shipped alongside the data, so it counts towards the total.
+        let total = result.compressed_size + result.dict_size as u64;
+        table.row(vec![
+            format_bytes(result.requested_size as u64),
+            format_bytes(result.dict_size as u64),
+            format_bytes(total),
+            render::percent(total as f64 / json_size as f64),
+            render::duration(result.train_time),
+            render::mb_per_sec(throughput(json_size, result.compress_time)),
+            render::mb_per_sec(throughput(json_size, result.decompress_time)),
+        ]);
+    }
+    print!("{}", table.terminal());
+    if let Some(best) = results.iter().min_by_key(|r| r.compressed_size + r.dict_size as u64) {
+        let total = best.compressed_size + best.dict_size as u64;
+        println!(
+            "  Best: {} dictionary, {} smaller than per-file zstd without a dictionary",
+            format_bytes(best.requested_size as u64),
+            render::percent(1.0 - total as f64 / baseline as f64)
+        );
+    }
+}
+
+pub fn print_partitioned(partitioned: &Partitioned, global: &[DictResult]) {
+    let summary = partitioned
+        .partitions
+        .iter()
+        .map(|(key, count)| format!("{} ({})", key, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!("  Partitions by {}: {}", partitioned.field, summary);
+    let mut table = Table::new(&[
+        ("Dict", Align::Left),
+        ("Global", Align::Right),
+        ("Partitioned", Align::Right),
+        ("Delta", Align::Right),
+        ("Train", Align::Right),
+        ("Shared dictionary", Align::Left),
+    ]);
+    for (result, global) in partitioned.results.iter().zip(global) {
+        let global_total = global.compressed_size + global.dict_size as u64;
+        let shared = if result.merged.is_empty() {
+            String::new()
+        } else {
+            format!("{} of {} partitions", result.merged.len(), partitioned.partitions.len())
+        };
+        table.row(vec![
+            format_bytes(result.requested_size as u64),
+            format_bytes(global_total),
+            format_bytes(result.total_size),
+            render::change(result.total_size as f64, global_total as f64),
+            render::duration(result.train_time),
+            shared,
+        ]);
+    }
+    print!("{}", table.terminal());
+}
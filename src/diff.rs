@@ -0,0 +1,193 @@
+//! `diff`: per-stage and per-codec deltas between two `--json` results files.
+
+use std::io::IsTerminal;
+use std::path::Path;
+use anyhow::Result;
+use crate::format_bytes;
+use crate::render::{self, Align, Table};
+use crate::results::Results;
+
+/// Changes smaller than these fractions are not highlighted.
+#[derive(Clone, Copy)]
+pub struct Thresholds {
+    pub size: f64,
+    pub time: f64,
+}
+
+/// How one measurement moved; smaller is better for both sizes and times.
+#[derive(Clone, Copy, PartialEq)]
+enum Change {
+    Same,
+    Better,
+    Worse,
+}
+
+fn change(old: f64, new: f64, threshold: f64) -> Change {
+    if old <= 0.0 || ((new - old) / old).abs() < threshold {
+        Change::Same
+    } else if new < old {
+        Change::Better
+    } else {
+        Change::Worse
+    }
+}
+
+impl Change {
+    /// The change that decides a row's highlight: any regression beats any improvement.
+    fn overall(changes: &[Change]) -> Change {
+        if changes.contains(&Change::Worse) {
+            Change::Worse
+        } else if changes.contains(&Change::Better) {
+            Change::Better
+        } else {
+            Change::Same
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Change::Same => "",
+            Change::Better => "better",
+            Change::Worse => "worse",
+        }
+    }
+}
+
+struct Printer {
+    color: bool,
+}
+
+impl Printer {
+    /// Prints `table`, each row in green or red when its entry in `flags` made it better or worse.
+    fn table(&self, table: &Table, flags: &[Change]) {
+        let out = table.terminal();
+        let mut lines = out.lines();
+        if let Some(header) = lines.next() {
+            println!("{}", header);
+        }
+        for (line, flag) in lines.zip(flags) {
+            match flag {
+                Change::Better if self.color => println!("\x1b[32m{}\x1b[0m", line),
+                Change::Worse if self.color => println!("\x1b[31m{}\x1b[0m", line),
+                _ => println!("{}", line),
+            }
+        }
+    }
+}
+
+fn size_delta(old: u64, new: u64) -> String {
+    let bytes = new as i64 - old as i64;
+    let sign = if bytes < 0 { "-" } else { "+" };
+    format!("{}{} ({})", sign, format_bytes(bytes.unsigned_abs()), render::change(new as f64, old as f64))
+}
+
+fn time_delta(old: f64, new: f64) -> String {
+    let sign = if new < old { "-" } else { "+" };
+    format!("{}{} ({})", sign, render::seconds((new - old).abs()), render::change(new, old))
+}
+
+/// Prints how every stage and matrix cell of `new_path` differs from `old_path`.
+pub fn run(old_path: &Path, new_path: &Path, thresholds: Thresholds) -> Result<()> {
+    let old = Results::read(old_path)?;
+    let new = Results::read(new_path)?;
+    let printer = Printer { color: std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none() };
+
+    println!("Old: {} ({}, {} files, {})", old_path.display(), old.generated_at, old.corpus.files, format_bytes(old.corpus.bytes));
+    println!("New: {} ({}, {} files, {})", new_path.display(), new.generated_at, new.corpus.files, format_bytes(new.corpus.bytes));
+    if old.corpus.files != new.corpus.files || old.corpus.bytes != new.corpus.bytes {
+        println!("The corpora differ, so sizes are not directly comparable.");
+    }
+    println!(
+        "Highlighting size changes of at least {} and time changes of at least {}.",
+        render::percent(thresholds.size),
+        render::percent(thresholds.time)
+    );
+
+    println!();
+    let mut stages = Table::new(&[
+        ("Stage", Align::Left),
+        ("Codec", Align::Left),
+        ("Size", Align::Right),
+        ("Δ size", Align::Right),
+        ("Time", Align::Right),
+        ("Δ time", Align::Right),
+        ("", Align::Left),
+    ]);
+    let mut flags = Vec::new();
+    for stage in &new.stages {
+        // Like the summary, sizes are the compressed side whichever direction the stage ran in.
+        let size = stage.bytes_in.min(stage.bytes_out);
+        let Some(before) = old.stages.iter().find(|old| old.name == stage.name && old.codec == stage.codec) else {
+            stages.row(vec![
+                stage.name.clone(),
+                stage.codec.clone(),
+                format_bytes(size),
+                "only in new".to_string(),
+                render::seconds(stage.secs),
+            ]);
+            flags.push(Change::Same);
+            continue;
+        };
+        let before_size = before.bytes_in.min(before.bytes_out);
+        let flag = Change::overall(&[
+            change(before_size as f64, size as f64, thresholds.size),
+            change(before.secs, stage.secs, thresholds.time),
+        ]);
+        stages.row(vec![
+            stage.name.clone(),
+            stage.codec.clone(),
+            format_bytes(size),
+            size_delta(before_size, size),
+            render::seconds(stage.secs),
+            time_delta(before.secs, stage.secs),
+            flag.label().to_string(),
+        ]);
+        flags.push(flag);
+    }
+    for stage in old.stages.iter().filter(|old| !new.stages.iter().any(|new| new.name == old.name && new.codec == old.codec)) {
+        stages.row(vec![stage.name.clone(), stage.codec.clone(), String::new(), "only in old".to_string()]);
+        flags.push(Change::Same);
+    }
+    printer.table(&stages, &flags);
+    crate::reproducible::print_comparison(&old, &new);
+
+    if !old.matrix.is_empty() || !new.matrix.is_empty() {
+        println!();
+        let mut matrix = Table::new(&[
+            ("Codec", Align::Left),
+            ("Size", Align::Right),
+            ("Δ size", Align::Right),
+            ("Δ compress", Align::Right),
+            ("Δ decompress", Align::Right),
+            ("", Align::Left),
+        ]);
+        let mut flags = Vec::new();
+        for cell in &new.matrix {
+            let Some(before) = old.matrix.iter().find(|old| old.codec == cell.codec) else {
+                matrix.row(vec![cell.codec.clone(), format_bytes(cell.compressed_bytes), "only in new".to_string()]);
+                flags.push(Change::Same);
+                continue;
+            };
+            let flag = Change::overall(&[
+                change(before.compressed_bytes as f64, cell.compressed_bytes as f64, thresholds.size),
+                change(before.compress_secs, cell.compress_secs, thresholds.time),
+                change(before.decompress_secs, cell.decompress_secs, thresholds.time),
+            ]);
+            matrix.row(vec![
+                cell.codec.clone(),
+                format_bytes(cell.compressed_bytes),
+                size_delta(before.compressed_bytes, cell.compressed_bytes),
+                time_delta(before.compress_secs, cell.compress_secs),
+                time_delta(before.decompress_secs, cell.decompress_secs),
+                flag.label().to_string(),
+            ]);
+            flags.push(flag);
+        }
+        for cell in old.matrix.iter().filter(|old| !new.matrix.iter().any(|new| new.codec == old.codec)) {
+            matrix.row(vec![cell.codec.clone(), String::new(), "only in old".to_string()]);
+            flags.push(Change::Same);
+        }
+        printer.table(&matrix, &flags);
+    }
+    Ok(())
+}
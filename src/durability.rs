@@ -0,0 +1,49 @@
+//! fsync policies for compressed outputs, so durable-write costs show up in the timings.
+
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use clap::ValueEnum;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Durability {
+    /// Never fsync; data may still be in the page cache when timing stops
+    #[default]
+    None,
+    /// fsync every output file as soon as it is written
+    PerFile,
+    /// fsync all outputs once, after the whole stage has been written
+    Batched,
+}
+
+impl fmt::Display for Durability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        f.write_str(value.get_name())
+    }
+}
+
+/// fsyncs an open file and returns the time spent.
+pub fn sync_file(file: &File) -> Result<Duration> {
+    let start = Instant::now();
+    file.sync_all()?;
+    Ok(start.elapsed())
+}
+
+/// Reopens and fsyncs every path.
+pub fn sync_paths(paths: &[PathBuf]) -> Result<Duration> {
+    let start = Instant::now();
+    for path in paths {
+        File::open(path)?.sync_all()?;
+    }
+    Ok(start.elapsed())
+}
+
+/// fsyncs a directory so newly created entries survive a crash.
+pub fn sync_dir(dir: &Path) -> Result<Duration> {
+    let start = Instant::now();
+    File::open(dir)?.sync_all()?;
+    Ok(start.elapsed())
+}
@@ -0,0 +1,240 @@
+//! `extract`: writes the entries of a solid archive, or of the partitions
+//! overlapping a time range, into a directory or as a tar stream.
+//!
+//! Entry names and link targets come from the archive, so they are checked
+//! before anything is written: a name must be a relative path of plain
+//! components, nothing is written through a symlink extracted earlier, and a
+//! link must point inside the output directory.
+
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use crate::{archive, input, partition};
+
+/// Rejects entry names that could land outside the output directory.
+fn check_name(name: &str) -> Result<()> {
+    let path = Path::new(name);
+    if name.is_empty() || !path.components().all(|component| matches!(component, Component::Normal(_))) {
+        bail!("refusing to extract {:?}: not a relative path inside the output directory", name);
+    }
+    Ok(())
+}
+
+/// Rejects link targets that are absolute or climb out of the output directory from the link's directory.
+fn check_link_target(name: &str, target: &str) -> Result<()> {
+    let link_dir = Path::new(name).parent().unwrap_or(Path::new(""));
+    if Path::new(target).is_absolute() || input::resolve_inside(link_dir, Path::new(target)).is_none() {
+        bail!("refusing to extract link {:?}: its target {:?} is outside the output directory", name, target);
+    }
+    Ok(())
+}
+
+/// Creates the parent directories of entry `name` under `dir` and returns its path,
+/// refusing to go through, or replace, a symlink that is already there.
+fn prepare(dir: &Path, name: &str) -> Result<PathBuf> {
+    let mut path = dir.to_path_buf();
+    let mut components = Path::new(name).components().peekable();
+    while let Some(component) = components.next() {
+        path.push(component);
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                bail!("refusing to extract {:?}: {} is a symlink", name, path.display());
+            }
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound && components.peek().is_some() => fs::create_dir(&path)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(path)
+}
+
+/// Where extracted entries go.
+enum Sink {
+    Dir(PathBuf),
+    Tar { builder: tar::Builder<Box<dyn Write>>, mtime: u64 },
+}
+
+impl Sink {
+    fn new(output: &Path, to_tar: Option<&Path>) -> Result<Self> {
+        let Some(path) = to_tar else {
+            fs::create_dir_all(output)?;
+            return Ok(Sink::Dir(output.to_path_buf()));
+        };
+        let writer: Box<dyn Write> = if path == Path::new("-") {
+            Box::new(BufWriter::new(io::stdout().lock()))
+        } else {
+            Box::new(BufWriter::new(fs::File::create(path).with_context(|| format!("failed to create {}", path.display()))?))
+        };
+        let mtime = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        Ok(Sink::Tar { builder: tar::Builder::new(writer), mtime })
+    }
+
+    fn header(mtime: u64, size: u64, mode: u32) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(size);
+        header.set_mode(mode);
+        header.set_mtime(mtime);
+        header
+    }
+
+    fn file(&mut self, name: &str, len: u64, content: &mut dyn Read) -> Result<()> {
+        check_name(name)?;
+        match self {
+            Sink::Dir(dir) => {
+                io::copy(content, &mut fs::File::create(prepare(dir, name)?)?)?;
+            }
+            Sink::Tar { builder, mtime } => builder.append_data(&mut Self::header(*mtime, len, 0o644), name, content)?,
+        }
+        Ok(())
+    }
+
+    fn symlink(&mut self, name: &str, target: &str) -> Result<()> {
+        check_name(name)?;
+        check_link_target(name, target)?;
+        match self {
+            #[cfg(unix)]
+            Sink::Dir(dir) => {
+                std::os::unix::fs::symlink(target, prepare(dir, name)?)?;
+            }
+            #[cfg(not(unix))]
+            Sink::Dir(_) => {}
+            Sink::Tar { builder, mtime } => {
+                let mut header = Self::header(*mtime, 0, 0o777);
+                header.set_entry_type(tar::EntryType::Symlink);
+                builder.append_link(&mut header, name, target)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        if let Sink::Tar { builder, .. } = self {
+            builder.into_inner()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Extracts `source`, an archive or a directory of `--partition-by` archives, keeping
+/// only entries whose timestamp lies in `range` if one is given.
+///
+/// With `to_tar` the entries are streamed as a tar archive, to stdout for `-`, and
+/// status lines go to stderr so they never mix with the stream. With `verify`, an
+/// entry whose checksum does not match stops the extraction before it is written.
+/// `dict` is the dictionary the archives were written with, if any.
+pub fn run(
+    source: &Path,
+    dict: Option<&[u8]>,
+    range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    output: &Path,
+    to_tar: Option<&Path>,
+    verify: bool,
+) -> Result<()> {
+    if let Some((from, to)) = range {
+        anyhow::ensure!(from < to, "--from must be before --to");
+    }
+    let to_stdout = to_tar == Some(Path::new("-"));
+    let status = |line: String| if to_stdout { eprintln!("{}", line) } else { println!("{}", line) };
+    let archives = if source.is_dir() {
+        let Some((from, to)) = range else {
+            bail!("{} is a partition directory, so --from and --to are required", source.display());
+        };
+        let (relevant, total) = partition::relevant(source, from, to)?;
+        status(format!("Opened {} of {} partitions", relevant.len(), total));
+        relevant
+    } else {
+        vec![source.to_path_buf()]
+    };
+
+    let mut sink = Sink::new(output, to_tar)?;
+    let (mut scanned, mut extracted) = (0, 0);
+    let result = archives.iter().try_for_each(|path| {
+        archive::for_each_entry(path, dict, verify, |entry, content| {
+            scanned += 1;
+            if entry.is_symlink {
+                // Symlinks carry no timestamp, so only whole-archive extraction keeps them.
+                if range.is_none() {
+                    let mut target = String::new();
+                    content.read_to_string(&mut target)?;
+                    sink.symlink(&entry.name, &target)?;
+                    extracted += 1;
+                }
+                return Ok(());
+            }
+            match range {
+                None => sink.file(&entry.name, entry.len, content)?,
+                Some((from, to)) => {
+                    let mut data = Vec::with_capacity(entry.len as usize);
+                    content.read_to_end(&mut data)?;
+                    if !partition::timestamp_of(&data).is_some_and(|timestamp| from <= timestamp && timestamp < to) {
+                        return Ok(());
+                    }
+                    sink.file(&entry.name, entry.len, &mut data.as_slice())?;
+                }
+            }
+            extracted += 1;
+            Ok(())
+        })
+    });
+    let result = result.and_then(|()| sink.finish());
+    // Like `list`, stop quietly when the reader goes away, e.g. `| head`.
+    match result {
+        Err(err) if err.downcast_ref::<io::Error>().is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) => return Ok(()),
+        other => other?,
+    }
+
+    let destination = match to_tar {
+        Some(_) if to_stdout => "a tar stream on stdout".to_string(),
+        Some(path) => format!("tar archive {}", path.display()),
+        None => output.display().to_string(),
+    };
+    status(format!("Scanned {} entries, extracted {} into {}", scanned, extracted, destination));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_name_accepts_only_plain_relative_paths() {
+        check_name("logs/2024/app.json").unwrap();
+        for name in ["", "../escape.json", "logs/../../escape.json", "/etc/passwd", "./app.json"] {
+            assert!(check_name(name).is_err(), "{:?} was accepted", name);
+        }
+    }
+
+    #[test]
+    fn check_link_target_keeps_links_inside_the_output() {
+        check_link_target("logs/latest", "app.json").unwrap();
+        check_link_target("logs/latest", "../other/app.json").unwrap();
+        for (name, target) in [("latest", "../app.json"), ("logs/latest", "../../app.json"), ("latest", "/etc/passwd")] {
+            assert!(check_link_target(name, target).is_err(), "{} -> {} was accepted", name, target);
+        }
+    }
+
+    #[test]
+    fn prepare_creates_parents_of_a_nested_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = prepare(dir.path(), "a/b/app.json").unwrap();
+        assert_eq!(path, dir.path().join("a/b/app.json"));
+        assert!(dir.path().join("a/b").is_dir());
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prepare_refuses_to_write_through_a_symlinked_parent() {
+        let outside = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("logs")).unwrap();
+        let err = prepare(dir.path(), "logs/app.json").unwrap_err();
+        assert!(err.to_string().contains("is a symlink"), "{}", err);
+        assert!(prepare(dir.path(), "logs").is_err());
+        assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+    }
+}
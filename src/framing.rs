@@ -0,0 +1,147 @@
+//! Compares gzip, zlib and raw DEFLATE framings of the same compressed stream to
+//! expose the fixed per-file cost of headers and checksums, and the same for the
+//! optional checksum and content size of zstd frames.
+//!
+//! For zstd, every combination is also tried against corruption: a bit in the
+//! middle of a sample of frames is flipped and each is decoded again, counting
+//! how often the decoder fails rather than returning wrong content.
+
+use std::io::Read;
+use anyhow::Result;
+use crate::bench::{measure, throughput, Measurement};
+use crate::codec::{Codec, Family, ZstdFramed};
+use crate::render::{Align, Table};
+use crate::{format_bytes, progress_bar, render};
+
+/// Frames corrupted per zstd frame combination, spread evenly over the corpus.
+const CORRUPTION_TRIALS: usize = 100;
+
+pub fn run(files: &[Vec<u8>], level: i32) -> Result<Vec<(Family, Measurement)>> {
+    let pb = progress_bar(Family::DEFLATE_FRAMINGS.len());
+    let mut results = Vec::with_capacity(Family::DEFLATE_FRAMINGS.len());
+    for family in Family::DEFLATE_FRAMINGS {
+        results.push((family, measure(family.build(level).as_ref(), files)?));
+        pb.inc(1);
+    }
+    pb.finish_with_message("Framing comparison complete!");
+    Ok(results)
+}
+
+pub fn print(results: &[(Family, Measurement)], file_count: usize, json_size: u64) {
+    // Raw DEFLATE carries no framing at all, so it is the zero-overhead baseline.
+    let Some((_, raw)) = results.iter().find(|(family, _)| *family == Family::Deflate) else {
+        return;
+    };
+    let mut table = Table::new(&[
+        ("Framing", Align::Left),
+        ("Size", Align::Right),
+        ("Ratio", Align::Right),
+        ("Overhead", Align::Right),
+        ("Per file", Align::Right),
+        ("Compress", Align::Right),
+        ("Decompress", Align::Right),
+    ]);
+    for (family, measurement) in results {
+        let overhead = measurement.compressed_size - raw.compressed_size;
+        table.row(vec![
+            family.to_string(),
+            format_bytes(measurement.compressed_size),
+            render::percent(measurement.compressed_size as f64 / json_size as f64),
+            format_bytes(overhead),
+            format!("{:.1} B", overhead as f64 / file_count as f64),
+            render::mb_per_sec(throughput(json_size, measurement.compress_time)),
+            render::mb_per_sec(throughput(json_size, measurement.decompress_time)),
+        ]);
+    }
+    print!("{}", table.terminal());
+}
+
+/// How one zstd frame combination fared against flipped bits.
+#[derive(Default)]
+pub struct Corruption {
+    pub trials: usize,
+    /// The decoder returned an error.
+    pub detected: usize,
+    /// The decoder returned content that differs from the original.
+    pub silent: usize,
+}
+
+pub struct ZstdFrameResult {
+    pub codec: ZstdFramed,
+    pub measurement: Measurement,
+    pub corruption: Corruption,
+}
+
+/// Flips one bit in the middle of frames of evenly spaced files and decodes them again.
+fn corrupt(codec: &ZstdFramed, files: &[Vec<u8>]) -> Result<Corruption> {
+    let mut corruption = Corruption::default();
+    for original in files.iter().step_by(files.len().div_ceil(CORRUPTION_TRIALS).max(1)) {
+        let mut frame = codec.compress_bytes(original)?;
+        let middle = frame.len() / 2;
+        frame[middle] ^= 0x10;
+        let mut output = Vec::with_capacity(original.len());
+        let mut input = frame.as_slice();
+        let decoded = codec.decoder(&mut input).and_then(|mut decoder| Ok(decoder.read_to_end(&mut output)?));
+        corruption.trials += 1;
+        match decoded {
+            Err(_) => corruption.detected += 1,
+            Ok(_) if output != *original => corruption.silent += 1,
+            Ok(_) => {}
+        }
+    }
+    Ok(corruption)
+}
+
+/// Measures zstd at `level` with every combination of frame checksum and content size.
+pub fn run_zstd(files: &[Vec<u8>], level: i32) -> Result<Vec<ZstdFrameResult>> {
+    let combinations = [(false, false), (false, true), (true, false), (true, true)];
+    let pb = progress_bar(combinations.len());
+    let mut results = Vec::with_capacity(combinations.len());
+    for (checksum, content_size) in combinations {
+        let codec = ZstdFramed { level, checksum, content_size };
+        pb.set_message(codec.name());
+        // `measure` decodes every frame and compares it to the original, which checks the checksums too.
+        let measurement = measure(&codec, files)?;
+        let corruption = corrupt(&codec, files)?;
+        results.push(ZstdFrameResult { codec, measurement, corruption });
+        pb.inc(1);
+    }
+    pb.finish_with_message("zstd frame option comparison complete!");
+    Ok(results)
+}
+
+pub fn print_zstd(results: &[ZstdFrameResult], file_count: usize, json_size: u64) {
+    // A frame with neither option is the zero-overhead baseline.
+    let Some(bare) = results.iter().find(|result| !result.codec.checksum && !result.codec.content_size) else {
+        return;
+    };
+    let rate = |time, base| {
+        let (rate, base) = (throughput(json_size, time), throughput(json_size, base));
+        format!("{} ({})", render::mb_per_sec(rate), render::change(rate, base))
+    };
+    let mut table = Table::new(&[
+        ("Frame options", Align::Left),
+        ("Size", Align::Right),
+        ("Overhead", Align::Right),
+        ("Per file", Align::Right),
+        ("Compress", Align::Right),
+        ("Decompress", Align::Right),
+        ("Caught", Align::Right),
+        ("Silent", Align::Right),
+    ]);
+    for result in results {
+        let m = &result.measurement;
+        let overhead = m.compressed_size - bare.measurement.compressed_size;
+        table.row(vec![
+            result.codec.name(),
+            format_bytes(m.compressed_size),
+            format_bytes(overhead),
+            format!("{:.1} B", overhead as f64 / file_count as f64),
+            rate(m.compress_time, bare.measurement.compress_time),
+            rate(m.decompress_time, bare.measurement.decompress_time),
+            format!("{}/{}", result.corruption.detected, result.corruption.trials),
+            result.corruption.silent.to_string(),
+        ]);
+    }
+    print!("{}", table.terminal());
+}
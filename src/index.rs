@@ -0,0 +1,251 @@
+//! Sidecar field index for queryable archives.
+//!
+//! The indexed archive uses the solid archive's entry layout but starts a new
+//! zstd frame every `FRAME_ENTRIES` entries. The sidecar records, per frame, its
+//! byte range, the timestamp range and a bitmap of the values seen for each of
+//! `INDEXED_FIELDS`, so a query or search can decode only the frames that may
+//! match its field value and `TimeRange`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use crate::archive;
+use crate::workspace::output_dir;
+use gz_vs_zstd::archive_format::ArchiveReader;
+
+/// Fields whose values are recorded per frame.
+pub const INDEXED_FIELDS: [&str; 2] = ["level", "service_name"];
+
+/// Entries per zstd frame: small enough to skip usefully, large enough to keep most of the solid ratio.
+const FRAME_ENTRIES: usize = 256;
+
+/// Fields with more distinct values than fit in a `u64` bitmap are left unindexed.
+const MAX_VALUES: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+pub struct Index {
+    /// Distinct values per indexed field; bit `i` of a frame's bitmap stands for `values[field][i]`.
+    pub values: BTreeMap<String, Vec<String>>,
+    pub frames: Vec<FrameIndex>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct FrameIndex {
+    pub offset: u64,
+    pub len: u64,
+    pub entries: usize,
+    pub min_timestamp: Option<String>,
+    pub max_timestamp: Option<String>,
+    /// Value bitmap per field. A field missing here could not be indexed for
+    /// this frame (e.g. an entry was not JSON), so the frame is always scanned.
+    pub fields: BTreeMap<String, u64>,
+}
+
+/// Where the index of the archive at `archive_path` is kept.
+pub fn sidecar(archive_path: &Path) -> PathBuf {
+    let mut path = archive_path.as_os_str().to_owned();
+    path.push(".idx");
+    PathBuf::from(path)
+}
+
+impl Index {
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = File::open(path).with_context(|| format!("failed to open index {}", path.display()))?;
+        serde_json::from_reader(std::io::BufReader::new(file)).with_context(|| format!("failed to parse index {}", path.display()))
+    }
+
+    /// Whether frame `frame` may contain `field` with `value`; unindexed fields always may.
+    pub fn may_match(&self, frame: &FrameIndex, field: &str, value: &str) -> bool {
+        let (Some(values), Some(bitmap)) = (self.values.get(field), frame.fields.get(field)) else {
+            return true;
+        };
+        values.iter().position(|v| v == value).is_some_and(|bit| bitmap & (1 << bit) != 0)
+    }
+}
+
+/// `--since` and `--until`: an inclusive range of `timestamp` values. RFC 3339
+/// timestamps in one time zone sort as strings, so they are compared as strings.
+#[derive(Clone, Debug, Default)]
+pub struct TimeRange {
+    pub since: Option<String>,
+    pub until: Option<String>,
+}
+
+impl TimeRange {
+    pub fn is_set(&self) -> bool {
+        self.since.is_some() || self.until.is_some()
+    }
+
+    fn contains(&self, timestamp: &str) -> bool {
+        self.since.as_deref().is_none_or(|since| timestamp >= since) && self.until.as_deref().is_none_or(|until| timestamp <= until)
+    }
+
+    /// Whether `frame` may hold an entry in range, judged by its timestamp range alone.
+    pub fn overlaps(&self, frame: &FrameIndex) -> bool {
+        if !self.is_set() {
+            return true;
+        }
+        let (Some(min), Some(max)) = (&frame.min_timestamp, &frame.max_timestamp) else {
+            return false;
+        };
+        self.since.as_deref().is_none_or(|since| max.as_str() >= since)
+            && self.until.as_deref().is_none_or(|until| min.as_str() <= until)
+    }
+
+    /// Whether an entry has a `timestamp`, at any depth, in range. Entries that
+    /// are not JSON have none, as in the index.
+    pub fn admits(&self, content: &[u8]) -> bool {
+        if !self.is_set() {
+            return true;
+        }
+        documents(content).is_some_and(|documents| documents.iter().any(|document| self.any_in(document)))
+    }
+
+    fn any_in(&self, value: &serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::Object(map) => map.iter().any(|(key, child)| {
+                (key == "timestamp" && child.as_str().is_some_and(|ts| self.contains(ts))) || self.any_in(child)
+            }),
+            serde_json::Value::Array(items) => items.iter().any(|item| self.any_in(item)),
+            _ => false,
+        }
+    }
+}
+
+/// The JSON documents of an entry, one or a stream such as NDJSON; `None` if any fails to parse.
+fn documents(content: &[u8]) -> Option<Vec<serde_json::Value>> {
+    serde_json::Deserializer::from_slice(content).into_iter().collect::<serde_json::Result<_>>().ok()
+}
+
+/// Values collected for one frame before they are turned into bitmaps.
+#[derive(Default)]
+struct FrameValues {
+    /// `None` once an entry could not be parsed.
+    fields: Option<BTreeMap<String, BTreeSet<String>>>,
+    min_timestamp: Option<String>,
+    max_timestamp: Option<String>,
+}
+
+/// Records every occurrence of an indexed field, at any depth, since the
+/// query predicate matches lines regardless of nesting.
+fn collect(value: &serde_json::Value, values: &mut FrameValues) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map {
+                if let Some(fields) = values.fields.as_mut().filter(|_| INDEXED_FIELDS.contains(&key.as_str())) {
+                    let text = match child {
+                        serde_json::Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    fields.entry(key.clone()).or_default().insert(text);
+                }
+                if key == "timestamp" {
+                    if let Some(ts) = child.as_str() {
+                        if values.min_timestamp.as_deref().is_none_or(|min| ts < min) {
+                            values.min_timestamp = Some(ts.to_string());
+                        }
+                        if values.max_timestamp.as_deref().is_none_or(|max| ts > max) {
+                            values.max_timestamp = Some(ts.to_string());
+                        }
+                    }
+                }
+                collect(child, values);
+            }
+        }
+        serde_json::Value::Array(items) => items.iter().for_each(|item| collect(item, values)),
+        _ => {}
+    }
+}
+
+/// Counts bytes written so frame offsets can be recorded.
+struct CountingWriter<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes `names` as a multi-frame archive at `archive_path` and its index at `index_path`.
+pub fn write_indexed(names: &[String], archive_path: &Path, index_path: &Path, level: i32, pb: &ProgressBar) -> Result<Index> {
+    let mut writer = CountingWriter { inner: BufWriter::new(File::create(archive_path)?), written: 0 };
+    let mut frames = Vec::new();
+    let mut frame_values = Vec::new();
+
+    for chunk in names.chunks(FRAME_ENTRIES) {
+        let offset = writer.written;
+        let mut values = FrameValues { fields: Some(BTreeMap::new()), ..Default::default() };
+        let mut encoder = zstd::Encoder::new(&mut writer, level)?;
+        for name in chunk {
+            let content = fs::read(output_dir().join(name))?;
+            match documents(&content) {
+                Some(documents) => documents.iter().for_each(|document| collect(document, &mut values)),
+                None => values.fields = None,
+            }
+            archive::write_entry(&mut encoder, name, &content)?;
+            pb.inc(1);
+        }
+        encoder.finish()?;
+        frames.push(FrameIndex {
+            offset,
+            len: writer.written - offset,
+            entries: chunk.len(),
+            min_timestamp: values.min_timestamp.take(),
+            max_timestamp: values.max_timestamp.take(),
+            fields: BTreeMap::new(),
+        });
+        frame_values.push(values.fields);
+    }
+    writer.flush()?;
+
+    // Build the per-field vocabularies, then express each frame's values as bitmaps.
+    let mut vocabularies: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for fields in frame_values.iter().flatten() {
+        for (field, set) in fields {
+            vocabularies.entry(field.clone()).or_default().extend(set.iter().cloned());
+        }
+    }
+    let values: BTreeMap<String, Vec<String>> = vocabularies
+        .into_iter()
+        .filter(|(_, set)| set.len() <= MAX_VALUES)
+        .map(|(field, set)| (field, set.into_iter().collect()))
+        .collect();
+    for (frame, fields) in frames.iter_mut().zip(&frame_values) {
+        let Some(fields) = fields else { continue };
+        for (field, vocabulary) in &values {
+            let bitmap = fields.get(field).map_or(0, |set| {
+                vocabulary.iter().enumerate().filter(|(_, v)| set.contains(*v)).fold(0, |bits, (i, _)| bits | 1 << i)
+            });
+            frame.fields.insert(field.clone(), bitmap);
+        }
+    }
+
+    let index = Index { values, frames };
+    serde_json::to_writer(BufWriter::new(File::create(index_path)?), &index)?;
+    Ok(index)
+}
+
+/// Decodes frame `frame` of the archive at `archive_path` and streams its entries into `f`.
+pub fn read_frame(
+    archive_path: &Path,
+    frame: &FrameIndex,
+    f: impl FnMut(&archive::Entry, &mut dyn Read) -> Result<()>,
+) -> Result<()> {
+    let mut file = File::open(archive_path)?;
+    file.seek(SeekFrom::Start(frame.offset))?;
+    let decoder = zstd::Decoder::new(file.take(frame.len))?.single_frame();
+    archive::read_entries(ArchiveReader::from_decoder(decoder)?, true, f)
+}
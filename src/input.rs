@@ -0,0 +1,272 @@
+//! Benchmarking an existing log directory instead of the generated corpus.
+//!
+//! The directory is snapshotted into the workspace first so every stage sees the
+//! same files. Files that are rotated away or unreadable while the snapshot is
+//! taken are skipped and reported instead of aborting the run.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use crate::archive::Symlink;
+use crate::workspace::output_dir;
+use crate::progress_bar;
+
+/// Where `--input` reads the corpus from.
+#[derive(Clone, Debug)]
+pub enum Source {
+    Dir(PathBuf),
+    /// `s3://bucket/prefix` on an S3-compatible endpoint.
+    S3 { bucket: String, prefix: String },
+    /// `journal:` for `journalctl -o export`, or `journal:FILE` for a saved export stream.
+    Journal(Option<PathBuf>),
+}
+
+impl FromStr for Source {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(file) = s.strip_prefix("journal:") {
+            return Ok(Source::Journal((!file.is_empty()).then(|| PathBuf::from(file))));
+        }
+        let Some(location) = s.strip_prefix("s3://") else {
+            return Ok(Source::Dir(PathBuf::from(s)));
+        };
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        if bucket.is_empty() {
+            bail!("expected s3://bucket/prefix, got {:?}", s);
+        }
+        Ok(Source::S3 { bucket: bucket.to_string(), prefix: prefix.to_string() })
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Dir(dir) => write!(f, "{}", dir.display()),
+            Source::S3 { bucket, prefix } => write!(f, "s3://{}/{}", bucket, prefix),
+            Source::Journal(None) => write!(f, "journalctl -o export"),
+            Source::Journal(Some(file)) => write!(f, "journal export {}", file.display()),
+        }
+    }
+}
+
+impl Source {
+    /// Copies the corpus into the workspace; for object storage this is the download.
+    pub fn snapshot(&self, symlinks: SymlinkPolicy) -> Result<Snapshot> {
+        match self {
+            Source::Dir(dir) => snapshot(dir, symlinks),
+            #[cfg(feature = "s3")]
+            Source::S3 { bucket, prefix } => crate::s3::snapshot(bucket, prefix),
+            #[cfg(not(feature = "s3"))]
+            Source::S3 { .. } => bail!("s3:// inputs need a build with --features s3"),
+            Source::Journal(file) => crate::journal::snapshot(file.as_deref()),
+        }
+    }
+}
+
+/// What to do with symbolic links found while walking the input directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SymlinkPolicy {
+    /// Follow links to files and directories, including each target only once
+    #[default]
+    Follow,
+    /// Ignore links entirely
+    Skip,
+    /// Keep links as symlink entries in the archive, with targets relative to the input directory
+    Store,
+}
+
+pub struct SkippedFile {
+    pub name: String,
+    pub reason: String,
+}
+
+pub struct Snapshot {
+    pub names: Vec<String>,
+    /// Links kept as links under `SymlinkPolicy::Store`.
+    pub links: Vec<Symlink>,
+    pub skipped: Vec<SkippedFile>,
+    pub elapsed: Duration,
+}
+
+/// Errors that mean the file went away or cannot be read, as opposed to a broken run.
+pub fn is_skippable(err: &io::Error) -> bool {
+    matches!(err.kind(), io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied)
+}
+
+/// Path of `name` inside the directory entry `prefix`, with `/` separators as recorded in archives.
+fn entry_name(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() { name.to_string() } else { format!("{}/{}", prefix, name) }
+}
+
+/// Resolves `target` lexically against `base`, both relative to the input root;
+/// `None` when a `..` climbs above the root.
+pub fn resolve_inside(base: &Path, target: &Path) -> Option<PathBuf> {
+    let mut resolved = PathBuf::new();
+    for component in base.components().chain(target.components()) {
+        match component {
+            Component::Normal(part) => resolved.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(resolved)
+}
+
+/// Rewrites a link target so the stored entry never embeds an absolute path.
+///
+/// Absolute targets inside `root` become relative to the link's directory;
+/// targets that resolve outside `root`, absolute or through `..`, cannot be
+/// represented and are rejected.
+fn store_target(root: &Path, link_dir: &str, target: &Path) -> Option<String> {
+    if target.is_relative() {
+        resolve_inside(Path::new(link_dir), target)?;
+        return target.to_str().map(str::to_string);
+    }
+    let inside = resolve_inside(Path::new(""), target.strip_prefix(root).ok()?)?;
+    let depth = Path::new(link_dir).components().filter(|c| matches!(c, Component::Normal(_))).count();
+    let mut relative = PathBuf::new();
+    for _ in 0..depth {
+        relative.push("..");
+    }
+    relative.push(inside);
+    relative.to_str().map(|s| s.replace('\\', "/"))
+}
+
+/// Copies every regular file under `dir` into the workspace, applying `symlinks` to links.
+fn snapshot(dir: &Path, symlinks: SymlinkPolicy) -> Result<Snapshot> {
+    let start = Instant::now();
+    let root = fs::canonicalize(dir).with_context(|| format!("failed to read input directory {}", dir.display()))?;
+    let mut names = Vec::new();
+    let mut links = Vec::new();
+    let mut skipped = Vec::new();
+    // Canonical paths already taken, so followed links neither double-count nor loop.
+    let mut seen = HashSet::from([root.clone()]);
+    let mut pending = vec![(root.clone(), String::new())];
+
+    let pb = progress_bar(0);
+    while let Some((path, prefix)) = pending.pop() {
+        let mut entries = match fs::read_dir(&path).and_then(|entries| entries.collect::<io::Result<Vec<_>>>()) {
+            Ok(entries) => entries,
+            Err(err) if is_skippable(&err) && !prefix.is_empty() => {
+                pb.println(format!("⚠️  Skipping {}: {}", prefix, err));
+                skipped.push(SkippedFile { name: prefix, reason: err.to_string() });
+                continue;
+            }
+            Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+        };
+        entries.sort_by_key(|entry| entry.file_name());
+        pb.inc_length(entries.len() as u64);
+
+        for entry in entries {
+            pb.inc(1);
+            let Ok(file_name) = entry.file_name().into_string() else {
+                skipped.push(SkippedFile {
+                    name: entry_name(&prefix, &entry.file_name().to_string_lossy()),
+                    reason: "file name is not valid UTF-8".to_string(),
+                });
+                continue;
+            };
+            let name = entry_name(&prefix, &file_name);
+            let mut skip = |name: String, reason: String| {
+                pb.println(format!("⚠️  Skipping {}: {}", name, reason));
+                skipped.push(SkippedFile { name, reason });
+            };
+
+            let result = (|| -> io::Result<()> {
+                let is_link = fs::symlink_metadata(entry.path())?.file_type().is_symlink();
+                if is_link && symlinks == SymlinkPolicy::Skip {
+                    return Ok(());
+                }
+                if is_link && symlinks == SymlinkPolicy::Store {
+                    let target = fs::read_link(entry.path())?;
+                    match store_target(&root, &prefix, &target) {
+                        Some(target) => links.push(Symlink { name: name.clone(), target }),
+                        None => skip(name.clone(), format!("link target {} is outside the input directory", target.display())),
+                    }
+                    return Ok(());
+                }
+
+                let metadata = fs::metadata(entry.path())?;
+                if !metadata.is_dir() && !metadata.is_file() {
+                    return Ok(());
+                }
+                let canonical = fs::canonicalize(entry.path())?;
+                if !seen.insert(canonical.clone()) {
+                    skip(name.clone(), format!("{} is already included", canonical.display()));
+                    return Ok(());
+                }
+                if metadata.is_dir() {
+                    pending.push((entry.path(), name.clone()));
+                } else {
+                    let copy = output_dir().join(&name);
+                    if let Some(parent) = copy.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::copy(entry.path(), copy)?;
+                    names.push(name.clone());
+                }
+                Ok(())
+            })();
+            match result {
+                Ok(()) => {}
+                Err(err) if is_skippable(&err) => skip(name, err.to_string()),
+                Err(err) => return Err(err).with_context(|| format!("failed to copy {}", name)),
+            }
+        }
+    }
+    pb.finish_with_message("Input snapshot complete!");
+    names.sort();
+
+    Ok(Snapshot { names, links, skipped, elapsed: start.elapsed() })
+}
+
+pub fn print_skipped(skipped: &[SkippedFile]) {
+    for file in skipped {
+        println!("  {}: {}", file.name, file.reason);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn store_target_keeps_only_links_resolving_inside_the_input() {
+        let outside = tempfile::tempdir().unwrap();
+        let input = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(input.path()).unwrap();
+        fs::create_dir(root.join("logs")).unwrap();
+        fs::write(root.join("app.json"), "{}").unwrap();
+        symlink("../app.json", root.join("logs/relative-inside")).unwrap();
+        symlink(root.join("app.json"), root.join("logs/absolute-inside")).unwrap();
+        symlink("../../escape.json", root.join("logs/relative-outside")).unwrap();
+        symlink(outside.path(), root.join("logs/absolute-outside")).unwrap();
+
+        let mut stored = Vec::new();
+        for entry in fs::read_dir(root.join("logs")).unwrap() {
+            let entry = entry.unwrap();
+            let target = fs::read_link(entry.path()).unwrap();
+            if let Some(target) = store_target(&root, "logs", &target) {
+                stored.push((entry.file_name().into_string().unwrap(), target));
+            }
+        }
+        stored.sort();
+        assert_eq!(
+            stored,
+            [("absolute-inside".to_string(), "../app.json".to_string()), ("relative-inside".to_string(), "../app.json".to_string())]
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! `inspect`: the structure of existing `.gz`, `.zst` and `.tar.zst` files,
+//! without extracting them.
+//!
+//! Every gzip member and zstd frame is walked from its header: what it declares
+//! (ISIZE, frame content size, window, dictionary ID, checksum) is printed next
+//! to what it actually decodes to, so truncated, concatenated or mislabeled
+//! files stand out. The decompression memory is what a streaming decoder would
+//! allocate for the largest window in the file.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use anyhow::{bail, ensure, Context, Result};
+use flate2::bufread::DeflateDecoder;
+use flate2::read::MultiGzDecoder;
+use flate2::Crc;
+use memmap2::Mmap;
+use zstd::zstd_safe::{self, zstd_sys};
+use crate::{dictionary, format_bytes};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Skippable frames use any magic from 0x184D2A50 to 0x184D2A5F.
+const SKIPPABLE_MAGIC: u32 = 0x184d_2a50;
+
+/// zlib's inflate state (about 7 KiB) plus the fixed 32 KiB DEFLATE window.
+const GZIP_DECODE_MEMORY: u64 = 39 * 1024;
+
+/// Members or frames listed per file; the totals always cover all of them.
+const MAX_ROWS: usize = 20;
+
+/// One gzip member or zstd frame.
+struct Unit {
+    offset: u64,
+    compressed: u64,
+    /// Size the header or trailer claims, if it claims one.
+    declared: Option<u64>,
+    /// Size it decodes to, unless it could not be decoded.
+    actual: Option<u64>,
+    window: Option<u64>,
+    /// What else the header says: dictionary, checksum, file name, skippable payload.
+    notes: Vec<String>,
+    /// Why the declared and actual sizes disagree or decoding failed.
+    problem: Option<String>,
+}
+
+/// Parses the gzip member header at the start of `data`, returning its length and notes.
+fn gzip_header(data: &[u8]) -> Result<(usize, Vec<String>)> {
+    ensure!(data.len() >= 10 && data[..2] == GZIP_MAGIC, "not a gzip member");
+    ensure!(data[2] == 8, "unknown gzip compression method {}", data[2]);
+    let flags = data[3];
+    let mtime = u32::from_le_bytes(data[4..8].try_into().unwrap());
+    let os = data[9];
+    let mut notes = Vec::new();
+    if mtime != 0 {
+        let mtime = chrono::DateTime::from_timestamp(mtime as i64, 0).map_or(mtime.to_string(), |time| time.to_rfc3339());
+        notes.push(format!("mtime {}", mtime));
+    }
+    notes.push(format!("OS {}", os));
+
+    let mut pos = 10;
+    let truncated = || anyhow::anyhow!("gzip header is truncated");
+    if flags & 0x04 != 0 {
+        let len = u16::from_le_bytes(data.get(pos..pos + 2).ok_or_else(truncated)?.try_into().unwrap()) as usize;
+        pos += 2 + len;
+        notes.push(format!("{} B extra field", len));
+    }
+    let zero_terminated = |pos: &mut usize| -> Result<String> {
+        let len = data.get(*pos..).and_then(|rest| rest.iter().position(|&b| b == 0)).ok_or_else(truncated)?;
+        let text = String::from_utf8_lossy(&data[*pos..*pos + len]).into_owned();
+        *pos += len + 1;
+        Ok(text)
+    };
+    if flags & 0x08 != 0 {
+        notes.push(format!("name {}", zero_terminated(&mut pos)?));
+    }
+    if flags & 0x10 != 0 {
+        zero_terminated(&mut pos)?;
+        notes.push("comment".to_string());
+    }
+    if flags & 0x02 != 0 {
+        pos += 2;
+        notes.push("header CRC".to_string());
+    }
+    ensure!(pos <= data.len(), "gzip header is truncated");
+    Ok((pos, notes))
+}
+
+/// Walks every gzip member, decoding each to check its CRC and ISIZE.
+fn gzip_members(data: &[u8]) -> Result<(Vec<Unit>, u64)> {
+    let mut units = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() && data[pos..].starts_with(&GZIP_MAGIC) {
+        let (header_len, notes) = gzip_header(&data[pos..])?;
+        let mut decoder = DeflateDecoder::new(&data[pos + header_len..]);
+        let mut crc = Crc::new();
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let n = decoder.read(&mut buffer).with_context(|| format!("member at offset {} is corrupt", pos))?;
+            if n == 0 {
+                break;
+            }
+            crc.update(&buffer[..n]);
+        }
+        let trailer = pos + header_len + decoder.total_in() as usize;
+        let Some(trailer_bytes) = data.get(trailer..trailer + 8) else {
+            bail!("member at offset {} is truncated before its trailer", pos);
+        };
+        let stored_crc = u32::from_le_bytes(trailer_bytes[..4].try_into().unwrap());
+        let isize = u32::from_le_bytes(trailer_bytes[4..].try_into().unwrap()) as u64;
+        let actual = crc.amount() as u64;
+        let problem = if stored_crc != crc.sum() {
+            Some(format!("CRC32 {:08x} does not match the content ({:08x})", stored_crc, crc.sum()))
+        } else if isize != actual % (1 << 32) {
+            Some(format!("ISIZE says {} but the member decodes to {}", format_bytes(isize), format_bytes(actual)))
+        } else {
+            None
+        };
+        let end = trailer + 8;
+        units.push(Unit {
+            offset: pos as u64,
+            compressed: (end - pos) as u64,
+            declared: Some(isize),
+            actual: Some(actual),
+            window: Some(32 * 1024),
+            notes,
+            problem,
+        });
+        pos = end;
+    }
+    Ok((units, (data.len() - pos) as u64))
+}
+
+/// Decodes the header of the zstd frame at the start of `data`.
+fn zstd_frame_header(data: &[u8]) -> Result<zstd_sys::ZSTD_FrameHeader> {
+    // SAFETY: the header is only filled in from `data`, which outlives the call.
+    unsafe {
+        let mut header = std::mem::zeroed::<zstd_sys::ZSTD_FrameHeader>();
+        let code = zstd_sys::ZSTD_getFrameHeader(&mut header, data.as_ptr().cast(), data.len());
+        if zstd_sys::ZSTD_isError(code) != 0 {
+            bail!("invalid zstd frame header: {}", zstd_safe::get_error_name(code));
+        }
+        ensure!(code == 0, "zstd frame header is truncated");
+        Ok(header)
+    }
+}
+
+/// Walks every zstd and skippable frame, decoding the zstd ones.
+fn zstd_frames(data: &[u8], dict: Option<&[u8]>) -> Result<(Vec<Unit>, u64, u64)> {
+    let mut units = Vec::new();
+    let mut memory = 0;
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        let magic = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+        let frame = &data[pos..];
+        let len = zstd_safe::find_frame_compressed_size(frame)
+            .map_err(|code| anyhow::anyhow!("frame at offset {} is truncated or corrupt: {}", pos, zstd_safe::get_error_name(code)))?;
+        if magic & 0xffff_fff0 == SKIPPABLE_MAGIC {
+            units.push(Unit {
+                offset: pos as u64,
+                compressed: len as u64,
+                declared: None,
+                actual: None,
+                window: None,
+                notes: vec![format!("skippable (magic {:x}), {} payload", magic, format_bytes(len as u64 - 8))],
+                problem: None,
+            });
+            pos += len;
+            continue;
+        }
+        ensure!(frame.starts_with(&ZSTD_MAGIC), "offset {} is neither a zstd nor a skippable frame", pos);
+        let header = zstd_frame_header(frame)?;
+        // SAFETY: estimates from a header that was just decoded successfully.
+        memory = memory.max(unsafe { zstd_sys::ZSTD_estimateDStreamSize_fromFrame(frame.as_ptr().cast(), frame.len()) } as u64);
+
+        let declared = (header.frameContentSize != zstd_sys::ZSTD_CONTENTSIZE_UNKNOWN as u64).then_some(header.frameContentSize);
+        let mut notes = Vec::new();
+        if header.dictID != 0 {
+            notes.push(format!("dictionary {}", header.dictID));
+        }
+        if header.checksumFlag != 0 {
+            notes.push("checksum".to_string());
+        }
+        let given = dict.and_then(dictionary::id);
+        let (actual, problem) = if header.dictID != 0 && given != Some(header.dictID) {
+            (None, Some(format!("needs dictionary {}; pass it with --dict to decode", header.dictID)))
+        } else {
+            let decoded = zstd::stream::read::Decoder::with_dictionary(&frame[..len], dict.unwrap_or_default())
+                .map(zstd::stream::read::Decoder::single_frame)
+                .and_then(|mut decoder| io::copy(&mut decoder, &mut io::sink()));
+            match decoded {
+                Ok(actual) => {
+                    let problem = declared
+                        .filter(|&declared| declared != actual)
+                        .map(|declared| format!("declares {} but decodes to {}", format_bytes(declared), format_bytes(actual)));
+                    (Some(actual), problem)
+                }
+                Err(err) => (None, Some(format!("does not decode: {}", err))),
+            }
+        };
+        units.push(Unit {
+            offset: pos as u64,
+            compressed: len as u64,
+            declared,
+            actual,
+            window: Some(header.windowSize),
+            notes,
+            problem,
+        });
+        pos += len;
+    }
+    Ok((units, (data.len() - pos) as u64, memory))
+}
+
+/// Entry count, content bytes and largest entry of a tar stream.
+#[derive(Default)]
+struct TarSummary {
+    entries: usize,
+    bytes: u64,
+    largest: Option<(String, u64)>,
+}
+
+fn tar_summary(stream: impl Read) -> Result<TarSummary> {
+    let mut archive = tar::Archive::new(stream);
+    let mut summary = TarSummary::default();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let size = entry.header().size()?;
+        summary.entries += 1;
+        summary.bytes += size;
+        if summary.largest.as_ref().is_none_or(|(_, largest)| size > *largest) {
+            summary.largest = Some((entry.path()?.display().to_string(), size));
+        }
+    }
+    Ok(summary)
+}
+
+fn is_tar(path: &Path) -> bool {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    [".tar.gz", ".tgz", ".tar.zst", ".tzst"].iter().any(|suffix| name.ends_with(suffix))
+}
+
+fn print_units(units: &[Unit], kind: &str) {
+    let size = |size: Option<u64>| size.map_or("-".to_string(), format_bytes);
+    println!(
+        "  {:<7} {:>12} {:>12} {:>12} {:>12} {:>10}  Header",
+        kind, "Offset", "Compressed", "Declared", "Actual", "Window"
+    );
+    for (i, unit) in units.iter().enumerate().take(MAX_ROWS) {
+        let row = format!(
+            "  {:<7} {:>12} {:>12} {:>12} {:>12} {:>10}  {}",
+            i,
+            unit.offset,
+            format_bytes(unit.compressed),
+            size(unit.declared),
+            size(unit.actual),
+            size(unit.window),
+            unit.notes.join(", ")
+        );
+        println!("{}", row.trim_end());
+    }
+    if units.len() > MAX_ROWS {
+        println!("  … {} more", units.len() - MAX_ROWS);
+    }
+}
+
+/// Prints the structure of one file and returns how many problems it has.
+fn inspect(path: &Path, dict: Option<&[u8]>) -> Result<usize> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    // SAFETY: the file is only read, and inspecting a file that changes underneath is the caller's risk.
+    let data = unsafe { Mmap::map(&file)? };
+    let (format, kind, units, trailing, memory) = if data.starts_with(&GZIP_MAGIC) {
+        let (units, trailing) = gzip_members(&data)?;
+        ("gzip", "Member", units, trailing, GZIP_DECODE_MEMORY)
+    } else if data.len() >= 4 && (data.starts_with(&ZSTD_MAGIC) || u32::from_le_bytes(data[..4].try_into().unwrap()) & 0xffff_fff0 == SKIPPABLE_MAGIC) {
+        let (units, trailing, memory) = zstd_frames(&data, dict)?;
+        ("zstd", "Frame", units, trailing, memory)
+    } else {
+        bail!("neither gzip nor zstd data");
+    };
+
+    let actual: Option<u64> = units.iter().filter(|unit| unit.window.is_some()).map(|unit| unit.actual).sum();
+    println!(
+        "🔍 {}: {}, {} {}{}, {} → {}",
+        path.display(),
+        format,
+        units.len(),
+        kind.to_lowercase(),
+        if units.len() == 1 { "" } else { "s" },
+        format_bytes(data.len() as u64),
+        actual.map_or("?".to_string(), format_bytes)
+    );
+    print_units(&units, kind);
+    let window = units.iter().filter_map(|unit| unit.window).max().unwrap_or(0);
+    println!("  Largest window: {}, decompression memory: ~{}", format_bytes(window), format_bytes(memory));
+
+    let mut problems = 0;
+    for (i, unit) in units.iter().enumerate() {
+        if let Some(problem) = &unit.problem {
+            println!("  ⚠️  {} {}: {}", kind.to_lowercase(), i, problem);
+            problems += 1;
+        }
+    }
+    if trailing > 0 {
+        println!("  ⚠️  {} trailing bytes after the last {}", trailing, kind.to_lowercase());
+        problems += 1;
+    }
+
+    if is_tar(path) && problems == 0 {
+        let summary = if format == "gzip" {
+            tar_summary(MultiGzDecoder::new(&data[..]))?
+        } else {
+            tar_summary(zstd::stream::read::Decoder::with_dictionary(&data[..], dict.unwrap_or_default())?)?
+        };
+        print!("  Tar: {} entries, {} of content", summary.entries, format_bytes(summary.bytes));
+        match summary.largest {
+            Some((name, size)) => println!(", largest {} ({})", name, format_bytes(size)),
+            None => println!(),
+        }
+    }
+    Ok(problems)
+}
+
+/// Inspects every path in turn, returning false if any could not be read or has problems.
+pub fn run(paths: &[std::path::PathBuf], dict: Option<&[u8]>) -> Result<bool> {
+    let mut ok = true;
+    for (i, path) in paths.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match inspect(path, dict) {
+            Ok(0) => {}
+            Ok(_) => ok = false,
+            Err(err) => {
+                println!("⚠️  {}: {:#}", path.display(), err);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
@@ -0,0 +1,41 @@
+//! Ctrl-C during a comparison run. The first press lets the current step
+//! finish, so no archive is left half written, skips every later optional step
+//! and writes what was measured to the results outputs, marked partial. The
+//! gzip and zstd steps always complete, since every result compares against
+//! them, while `--sweep`, `--matrix` and `--scatter` stop after the codec they
+//! are measuring. A second press stops at once, still removing the workspace.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use indicatif::ProgressBar;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes the first Ctrl-C stop the run gracefully instead of ending the process.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Whether Ctrl-C asked the run to stop after the current step.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Called from the Ctrl-C handler: true when this press only asks the run to stop,
+/// false when the process should exit now.
+pub fn intercept() -> bool {
+    if !ENABLED.load(Ordering::SeqCst) || REQUESTED.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+    eprintln!("\n🛑 Interrupted; finishing the current step and writing partial results. Press Ctrl-C again to stop now");
+    true
+}
+
+/// Finishes a step's progress bar, or leaves it where it stopped when the step was cut short.
+pub fn finish(pb: &ProgressBar, message: &'static str) {
+    if requested() {
+        pb.abandon_with_message("Interrupted");
+    } else {
+        pb.finish_with_message(message);
+    }
+}
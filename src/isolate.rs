@@ -0,0 +1,202 @@
+//! `--isolate`: measures each `--sweep` and `--matrix` codec in a child process
+//! of its own, so a segfault in a native codec library or an OOM kill ends only
+//! that child and is recorded as a failed measurement with its exit status and
+//! the tail of its stderr.
+//!
+//! The child is this binary again, run as the hidden `isolated-measure`
+//! command. It reads a `Request` as JSON from stdin, loads the corpus from the
+//! workspace, finds the codec by name among every configuration the sweep
+//! options allow, and writes `progress N` lines while it works and a final
+//! `result {...}` line to stdout.
+
+use std::env;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::bench::{self, Measurement};
+use crate::codec::{Family, MemoryEstimate};
+use crate::sweep::{self, SweepOptions};
+use crate::watchdog::Outcome;
+
+/// How often the child reports how many files it has done.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lines of the child's stderr kept with a failure.
+const STDERR_LINES: usize = 20;
+
+/// The corpus every child measures.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Corpus {
+    pub dir: PathBuf,
+    pub names: Vec<String>,
+    pub options: SweepOptions,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    codec: String,
+    corpus: Corpus,
+}
+
+/// A measurement as plain numbers, as the child reports it and `--cache` stores it.
+#[derive(Serialize, Deserialize)]
+pub struct Report {
+    compressed_size: u64,
+    compress_secs: f64,
+    decompress_secs: f64,
+    memory: Option<(u64, u64)>,
+}
+
+impl Report {
+    pub fn new(measurement: &Measurement) -> Self {
+        Report {
+            compressed_size: measurement.compressed_size,
+            compress_secs: measurement.compress_time.as_secs_f64(),
+            decompress_secs: measurement.decompress_time.as_secs_f64(),
+            memory: measurement.memory.map(|memory| (memory.compress, memory.decompress)),
+        }
+    }
+
+    pub fn measurement(&self) -> Measurement {
+        Measurement {
+            compressed_size: self.compressed_size,
+            compress_time: Duration::from_secs_f64(self.compress_secs),
+            decompress_time: Duration::from_secs_f64(self.decompress_secs),
+            memory: self.memory.map(|(compress, decompress)| MemoryEstimate { compress, decompress }),
+        }
+    }
+}
+
+enum Message {
+    Progress(usize),
+    Report(Report),
+}
+
+/// Measures the codec named `codec` in a child process, killing it after `limit`.
+pub fn measure(corpus: &Corpus, codec: &str, limit: Option<Duration>) -> Result<Outcome> {
+    let deadline = limit.map(|limit| Instant::now() + limit);
+    let mut child = Command::new(env::current_exe()?)
+        .arg("isolated-measure")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to start an isolated child")?;
+    let request = Request { codec: codec.to_string(), corpus: corpus.clone() };
+    // Dropping stdin closes it, so the child sees the end of the request.
+    serde_json::to_writer(child.stdin.take().expect("stdin is piped"), &request)?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let message = if let Some(progress) = line.strip_prefix("progress ") {
+                progress.parse().ok().map(Message::Progress)
+            } else if let Some(report) = line.strip_prefix("result ") {
+                serde_json::from_str(report).ok().map(Message::Report)
+            } else {
+                None
+            };
+            if let Some(message) = message {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stderr = thread::spawn(move || {
+        let mut output = String::new();
+        let _ = stderr.read_to_string(&mut output);
+        output
+    });
+
+    let (mut progress, mut report) = (0, None);
+    loop {
+        let message = match deadline {
+            Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match message {
+            Ok(Message::Progress(done)) => progress = done,
+            Ok(Message::Report(done)) => report = Some(done),
+            Err(RecvTimeoutError::Timeout) => {
+                child.kill()?;
+                child.wait()?;
+                return Ok(Outcome::TimedOut { progress });
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    let status = child.wait()?;
+    let stderr = stderr.join().unwrap_or_default();
+    Ok(match report {
+        Some(report) if status.success() => Outcome::Measured(report.measurement()),
+        _ => Outcome::Failed { reason: describe(status), stderr: tail(&stderr) },
+    })
+}
+
+fn describe(status: ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return match signal {
+                4 => "killed by SIGILL".to_string(),
+                6 => "killed by SIGABRT".to_string(),
+                7 => "killed by SIGBUS".to_string(),
+                8 => "killed by SIGFPE".to_string(),
+                9 => "killed by SIGKILL (the OOM killer?)".to_string(),
+                11 => "killed by SIGSEGV".to_string(),
+                other => format!("killed by signal {}", other),
+            };
+        }
+    }
+    match status.code() {
+        Some(0) => "exited without reporting a measurement".to_string(),
+        Some(code) => format!("exited with status {}", code),
+        None => status.to_string(),
+    }
+}
+
+fn tail(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().collect();
+    lines[lines.len().saturating_sub(STDERR_LINES)..].join("\n")
+}
+
+/// The child side: measures the requested codec and reports on stdout.
+pub fn worker() -> Result<()> {
+    let request: Request = serde_json::from_reader(io::stdin().lock()).context("invalid isolated-measure request")?;
+    let corpus = &request.corpus;
+    let codec = Family::ALL
+        .iter()
+        .flat_map(|&family| sweep::codecs(family, &corpus.options))
+        .find(|codec| codec.name() == request.codec)
+        .with_context(|| format!("no codec configuration is named {}", request.codec))?;
+    let files = bench::load_corpus(&corpus.dir, &corpus.names)?;
+
+    let progress = AtomicUsize::new(0);
+    let done = AtomicBool::new(false);
+    let measurement = thread::scope(|scope| {
+        scope.spawn(|| {
+            while !done.load(Ordering::Relaxed) {
+                println!("progress {}", progress.load(Ordering::Relaxed));
+                thread::sleep(PROGRESS_INTERVAL);
+            }
+        });
+        let measurement = bench::measure_tracked(codec.as_ref(), &files, &progress);
+        done.store(true, Ordering::Relaxed);
+        measurement
+    })?;
+
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "result {}", serde_json::to_string(&Report::new(&measurement))?)?;
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! `--input journal:`: systemd journal entries as the corpus, read from
+//! `journalctl -o export` or from a saved export-format stream.
+//!
+//! The export format is a sequence of entries separated by blank lines. Each
+//! field is either `NAME=value` on one line or, for values that are binary or
+//! contain newlines, `NAME`, a little-endian `u64` length, the raw value and a
+//! newline. Entries are kept byte for byte and grouped `ENTRIES_PER_FILE` to a
+//! file, so every codec sees the journal data exactly as exported.
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Instant;
+use anyhow::{bail, ensure, Context, Result};
+use crate::input::Snapshot;
+use crate::progress_bar;
+use crate::workspace::output_dir;
+
+/// Entries per corpus file, roughly the size of a small rotated log.
+const ENTRIES_PER_FILE: usize = 1000;
+
+/// Reads one entry into `entry`, returning false at the end of the stream.
+fn read_entry(reader: &mut impl BufRead, entry: &mut Vec<u8>) -> Result<bool> {
+    entry.clear();
+    loop {
+        let start = entry.len();
+        if reader.read_until(b'\n', entry)? == 0 {
+            // A last entry without its blank separator still counts.
+            if entry.is_empty() {
+                return Ok(false);
+            }
+            entry.push(b'\n');
+            return Ok(true);
+        }
+        let line = &entry[start..];
+        if line == b"\n" {
+            // The blank separator ends the entry; runs of them between entries are dropped.
+            if start == 0 {
+                entry.clear();
+                continue;
+            }
+            return Ok(true);
+        }
+        if !line.contains(&b'=') {
+            // A binary field: its name line is followed by the length and the raw value.
+            let mut len = [0; 8];
+            reader.read_exact(&mut len).context("export stream ends inside a binary field")?;
+            entry.extend_from_slice(&len);
+            let len = u64::from_le_bytes(len);
+            let read = reader.take(len + 1).read_to_end(entry)?;
+            ensure!(read as u64 == len + 1, "export stream ends inside a binary field");
+            ensure!(entry.last() == Some(&b'\n'), "binary field is not followed by a newline");
+        }
+    }
+}
+
+/// Splits the export stream from `reader` into corpus files in the workspace.
+fn split(reader: impl Read) -> Result<Vec<String>> {
+    let mut reader = BufReader::new(reader);
+    let dir = output_dir().join("journal");
+    fs::create_dir_all(&dir)?;
+    let pb = progress_bar(0);
+    let mut names = Vec::new();
+    let mut entry = Vec::new();
+    let mut writer: Option<BufWriter<File>> = None;
+    let mut entries = 0;
+    while read_entry(&mut reader, &mut entry)? {
+        if entries % ENTRIES_PER_FILE == 0 {
+            if let Some(mut writer) = writer.take() {
+                writer.flush()?;
+            }
+            let name = format!("journal/{:06}.export", names.len());
+            writer = Some(BufWriter::new(File::create(output_dir().join(&name))?));
+            names.push(name);
+            pb.inc_length(1);
+            pb.inc(1);
+        }
+        writer.as_mut().expect("a file is open once an entry was read").write_all(&entry)?;
+        entries += 1;
+    }
+    if let Some(mut writer) = writer {
+        writer.flush()?;
+    }
+    pb.finish_with_message(format!("Read {} journal entries!", entries));
+    Ok(names)
+}
+
+/// Snapshots the journal export in `file` (`-` for stdin), or the output of `journalctl -o export`.
+pub fn snapshot(file: Option<&Path>) -> Result<Snapshot> {
+    let start = Instant::now();
+    let names = match file {
+        Some(path) if path == Path::new("-") => split(io::stdin().lock())?,
+        Some(path) => split(File::open(path).with_context(|| format!("failed to open journal export {}", path.display()))?)?,
+        None => {
+            let mut child = Command::new("journalctl")
+                .args(["-o", "export", "--no-pager"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .context("failed to run journalctl; pass journal:FILE with a saved `journalctl -o export` stream instead")?;
+            let names = split(child.stdout.take().expect("stdout is piped"))?;
+            let status = child.wait()?;
+            if !status.success() {
+                bail!("journalctl -o export failed with {}", status);
+            }
+            names
+        }
+    };
+    ensure!(!names.is_empty(), "the journal export has no entries");
+    Ok(Snapshot { names, links: Vec::new(), skipped: Vec::new(), elapsed: start.elapsed() })
+}
@@ -0,0 +1,114 @@
+//! `--fs-latency`: artificial latency on the benchmark's file I/O, modelling
+//! NFS or EFS-style storage where every operation is a round trip to a server.
+//!
+//! Opening or creating a file costs one round trip and closing it another
+//! (NFS flushes on close). Data moves in `TRANSFER_SIZE` requests, so every
+//! started `TRANSFER_SIZE` bytes read or written costs one more. Only the
+//! stages that compare the codecs go through here: per-file gzip compression
+//! and decompression, the null sink, writing and extracting the zstd archive,
+//! and the full scans of `--query`.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+/// Bytes per read or write request, the common NFS `rsize`/`wsize`.
+pub const TRANSFER_SIZE: u64 = 1024 * 1024;
+
+static LATENCY: OnceLock<Duration> = OnceLock::new();
+static ROUND_TRIPS: AtomicU64 = AtomicU64::new(0);
+
+/// Injects `latency` into every following file operation made through this module.
+pub fn set(latency: Duration) {
+    let _ = LATENCY.set(latency);
+}
+
+/// The injected latency per round trip, if any.
+pub fn get() -> Option<Duration> {
+    LATENCY.get().copied()
+}
+
+/// Round trips simulated so far.
+pub fn round_trips() -> u64 {
+    ROUND_TRIPS.load(Ordering::Relaxed)
+}
+
+/// Time `round_trips` cost at the injected latency.
+pub fn injected(round_trips: u64) -> Duration {
+    get().unwrap_or_default() * round_trips as u32
+}
+
+fn delay(round_trips: u64) {
+    if let (Some(latency), true) = (get(), round_trips > 0) {
+        ROUND_TRIPS.fetch_add(round_trips, Ordering::Relaxed);
+        thread::sleep(latency * round_trips as u32);
+    }
+}
+
+/// Opens `path` for reading, one round trip.
+pub fn open(path: impl AsRef<Path>) -> io::Result<Remote> {
+    delay(1);
+    Ok(Remote { file: File::open(path)?, transferred: 0 })
+}
+
+/// Creates `path` for writing, one round trip.
+pub fn create(path: impl AsRef<Path>) -> io::Result<Remote> {
+    delay(1);
+    Ok(Remote { file: File::create(path)?, transferred: 0 })
+}
+
+/// A file whose reads, writes and close pay the injected latency.
+pub struct Remote {
+    file: File,
+    transferred: u64,
+}
+
+impl Remote {
+    pub fn get_ref(&self) -> &File {
+        &self.file
+    }
+
+    /// Charges a round trip for every transfer request `bytes` more bytes start.
+    fn transfer(&mut self, bytes: usize) {
+        let before = self.transferred.div_ceil(TRANSFER_SIZE);
+        self.transferred += bytes as u64;
+        delay(self.transferred.div_ceil(TRANSFER_SIZE) - before);
+    }
+}
+
+impl Read for Remote {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.file.read(buf)?;
+        self.transfer(n);
+        Ok(n)
+    }
+}
+
+impl Write for Remote {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.file.write(buf)?;
+        self.transfer(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        delay(1);
+    }
+}
+
+/// Prints how much of a stage's time `round_trips` injected, when `--fs-latency` is set.
+pub fn print(round_trips: u64) {
+    if get().is_some() {
+        println!("    of which simulated latency: {} ({} round trips)", crate::render::duration(injected(round_trips)), round_trips);
+    }
+}
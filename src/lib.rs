@@ -0,0 +1,33 @@
+//! The measurement side of gz-vs-zstd as a library: the [`codec::Codec`] trait
+//! and built-in codecs, the in-memory harness in [`bench`], the generated log
+//! corpus and the result types behind `--json`, `--summary` and `--chart`,
+//! which [`artifact`] stores compressed when asked, the formatting in
+//! [`render`] that every human-readable output shares, and the multi-file
+//! archive format in [`archive_format`].
+//!
+//! `examples/custom_codec.rs` benchmarks a codec defined outside this crate.
+
+pub mod archive_format;
+pub mod artifact;
+pub mod bench;
+pub mod chart;
+pub mod codec;
+pub mod corpus;
+pub mod render;
+pub mod results;
+pub mod summary;
+
+/// Formats a byte count in the current `render::Units`, e.g. `1.50 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    let units = render::units();
+    let names = units.names();
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+    
+    while size >= units.base() && unit_index < names.len() - 1 {
+        size /= units.base();
+        unit_index += 1;
+    }
+    
+    format!("{:.2} {}", size, names[unit_index])
+}
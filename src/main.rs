@@ -1,144 +1,685 @@
+mod archive;
+mod bundle;
+mod cache;
+mod chunking;
+#[cfg(feature = "parquet")]
+mod columnar;
+mod compact;
+mod corpora;
+mod dictionary;
+mod diff;
+mod durability;
+mod extract;
+mod framing;
+mod index;
+mod input;
+mod inspect;
+mod interrupt;
+mod isolate;
+mod journal;
+mod latency;
+mod matched;
+mod matrix;
+mod minify;
+mod mixed;
+mod naming;
+mod overhead;
+mod parse;
+mod partition;
+mod pipeline;
+mod plan;
+mod priority;
+mod report;
+mod query;
+mod readers;
+mod recompress;
+mod reference;
+mod reproducible;
+#[cfg(feature = "parquet")]
+mod results_parquet;
+mod sample;
+mod scatter;
+#[cfg(feature = "s3")]
+mod s3;
+mod score;
+mod search;
+mod soak;
+mod stats;
+mod strategies;
+mod sweep;
+mod update;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod uring;
+mod validate;
+mod watchdog;
+mod workspace;
+
+use gz_vs_zstd::corpus::{self, generate_json_with};
+use gz_vs_zstd::{artifact, bench, chart, codec, format_bytes, render, results, summary};
 use std::fs::{self, File};
-use std::io::{BufReader, BufWriter, Write};
-use std::path::Path;
-use std::time::Instant;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use anyhow::Result;
-use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use clap::{Args, Parser, Subcommand};
+use durability::Durability;
+use reproducible::GzipHeader;
+use workspace::{output_dir, Workspace};
+use flate2::{read::GzDecoder, Compression};
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::prelude::*;
-use serde::{Deserialize, Serialize};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
-const OUTPUT_DIR: &str = "mock_logs";
 const NUM_FILES: usize = 10_000;
 
-const FIXED_KEYS: [&str; 15] = [
-    "timestamp", "level", "message", "source_ip", "user_id", "request_id",
-    "http_method", "http_path", "http_status", "user_agent", "response_time_ms",
-    "app_version", "service_name", "region", "payload"
-];
-
-#[derive(Serialize, Deserialize)]
-struct LogEntry {
-    timestamp: String,
-    level: String,
-    message: String,
-    source_ip: String,
-    user_id: String,
-    request_id: String,
-    http_method: String,
-    http_path: String,
-    http_status: u16,
-    user_agent: String,
-    response_time_ms: u32,
-    app_version: String,
-    service_name: String,
-    region: String,
-    payload: String,
+#[derive(Parser)]
+#[command(about = "Compares individual gzip vs multi-file zstd compression on realistic JSON logs")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Keep intermediate artifacts in `mock_logs/` instead of a temporary workspace
+    #[arg(long, global = true)]
+    keep_workspace: bool,
+
+    /// Keep intermediate artifacts in this directory instead of a temporary workspace
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "keep_workspace")]
+    output_dir: Option<PathBuf>,
+
+    /// File names of the generated corpus; `{prefix}`, `{index}` (zero padded with `{index:WIDTH}`) and `{ext}` are substituted
+    #[arg(long, global = true, value_name = "TEMPLATE", default_value = naming::DEFAULT_TEMPLATE)]
+    name_template: naming::NameTemplate,
+
+    /// Value of `{prefix}` in `--name-template`
+    #[arg(long, global = true, default_value = "log")]
+    name_prefix: String,
+
+    /// Run at this CPU niceness, from -20 (highest priority) to 19 (lowest); Linux only
+    #[arg(long, global = true, allow_negative_numbers = true, value_parser = clap::value_parser!(i32).range(-20..=19))]
+    nice: Option<i32>,
+
+    /// Run in this I/O scheduling class: `idle`, `best-effort[:LEVEL]` or `realtime[:LEVEL]`; Linux only
+    #[arg(long, global = true, value_name = "CLASS")]
+    ionice: Option<priority::IoPriority>,
+
+    /// Show sizes and rates in SI units (kB, MB, GB) or binary units (KiB, MiB, GiB)
+    #[arg(long, global = true, value_enum, default_value_t)]
+    units: render::Units,
+
+    #[command(flatten)]
+    run: RunArgs,
 }
 
-fn random_string(length: usize) -> String {
-    thread_rng()
-        .sample_iter(&rand::distributions::Alphanumeric)
-        .take(length)
-        .map(char::from)
-        .collect()
+#[derive(Args)]
+struct RunArgs {
+    /// Also compress each file with zstd using a dictionary trained on the corpus, or the one in FILE,
+    /// which then also compresses the zstd archive
+    #[arg(long, value_name = "FILE", num_args = 0..=1)]
+    dict: Option<Option<PathBuf>>,
+
+    /// Dictionary sizes to train and compare, e.g. `16K,64K,112K,512K`
+    #[arg(long, value_delimiter = ',', value_parser = parse_size, requires = "dict")]
+    dict_sizes: Vec<usize>,
+
+    /// Also train one dictionary per distinct value of this JSON field, e.g. `service_name`
+    #[arg(long, value_name = "FIELD", requires = "dict")]
+    dict_partition: Option<String>,
+
+    /// Pick each other codec's level closest to this codec's compression speed, e.g. `gzip:6`
+    #[arg(long, value_name = "CODEC:LEVEL")]
+    match_speed: Option<codec::CodecSpec>,
+
+    /// Compare gzip, zlib and raw DEFLATE framings of each file
+    #[arg(long)]
+    framings: bool,
+
+    /// Compare zstd frames with and without a content checksum and the content size in the header
+    #[arg(long)]
+    zstd_frame_options: bool,
+
+    /// Also compress the corpus as one stream cut into independent chunks of this size, e.g. `4MiB`,
+    /// with every codec, and compare with compressing the stream whole
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    chunk_size: Option<usize>,
+
+    /// Rename every JSON key to a short form, e.g. `response_time_ms` to `rtm`, and compare
+    /// compressed sizes with the original keys
+    #[arg(long)]
+    minify_keys: bool,
+
+    /// Sweep every level of these codecs, e.g. `gzip,zstd`
+    #[arg(long, value_delimiter = ',')]
+    sweep: Vec<codec::Family>,
+
+    /// Brotli window sizes (lgwin, 10-24) to cover when sweeping brotli
+    #[cfg(feature = "brotli")]
+    #[arg(long, value_delimiter = ',', value_parser = clap::value_parser!(u32).range(10..=24))]
+    brotli_lgwin: Vec<u32>,
+
+    /// LZ4 framings to cover when sweeping lz4 (`frame`, `block`)
+    #[cfg(feature = "lz4")]
+    #[arg(long, value_delimiter = ',')]
+    lz4_modes: Vec<codec::Lz4Mode>,
+
+    /// LZ4 acceleration factors to cover when sweeping lz4
+    #[cfg(feature = "lz4")]
+    #[arg(long, value_delimiter = ',', value_parser = clap::value_parser!(u32).range(1..=65537))]
+    lz4_acceleration: Vec<u32>,
+
+    /// Run every enabled codec at every configured level and print one consolidated table
+    #[arg(long)]
+    matrix: bool,
+
+    /// Time decompression followed by JSON parsing for these codecs, e.g. `gzip:6,zstd:3`
+    #[arg(long, value_delimiter = ',', value_name = "CODEC:LEVEL")]
+    parse: Vec<codec::CodecSpec>,
+
+    /// Time decompressing, deserializing into the log schema and validating every record for these codecs
+    #[arg(long, value_delimiter = ',', value_name = "CODEC:LEVEL")]
+    pipeline: Vec<codec::CodecSpec>,
+
+    /// Run compression and decompression concurrently with these codecs and report the slowdown against each alone
+    #[arg(long, value_delimiter = ',', value_name = "CODEC:LEVEL")]
+    mixed: Vec<codec::CodecSpec>,
+
+    /// Compressor:decompressor worker counts to run with `--mixed`
+    #[arg(long, value_delimiter = ',', value_name = "C:D", default_value = "1:1,1:3,3:1")]
+    mixed_ratios: Vec<mixed::Mix>,
+
+    /// How long each `--mixed` measurement runs
+    #[arg(long, value_parser = soak::parse_duration, default_value = "3s")]
+    mixed_duration: Duration,
+
+    /// Record each file's original size, compressed size and times with these codecs, e.g. `gzip:6,zstd:3`
+    #[arg(long, value_delimiter = ',', value_name = "CODEC:LEVEL")]
+    scatter: Vec<codec::CodecSpec>,
+
+    /// Write the solid zstd archive here instead of into the workspace, keeping it after the run
+    #[arg(long, value_name = "PATH")]
+    archive_path: Option<PathBuf>,
+
+    /// Compress the zstd archive on this many worker threads (0 compresses on the calling thread)
+    #[arg(long, default_value_t = 0, value_name = "N")]
+    zstd_workers: u32,
+
+    /// Store files whose content is already in the zstd archive as references to the first copy
+    #[arg(long)]
+    dedup: bool,
+
+    /// Convert the log records to Parquet with these page compressions and compare against compressed NDJSON
+    #[cfg(feature = "parquet")]
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "COMPRESSION")]
+    parquet: Vec<columnar::ParquetCompression>,
+
+    /// Also write one zstd archive per time bucket of the `timestamp` field
+    #[arg(long, value_enum, value_name = "BUCKET")]
+    partition_by: Option<partition::PartitionBy>,
+
+    /// List and extract the zstd archive with the buffered and the memory-mapped reader and compare them
+    #[arg(long)]
+    reader_bench: bool,
+
+    /// Also write a multi-frame zstd archive with a sidecar field index that `--query` can use to skip frames
+    #[arg(long)]
+    index: bool,
+
+    /// Count lines matching `FIELD=VALUE` by streaming the gzip files and the zstd archive, e.g. `level=ERROR`
+    #[arg(long, value_name = "FIELD=VALUE")]
+    query: Option<query::Predicate>,
+
+    /// Only count `--query` lines of entries with a `timestamp` at or after this one, e.g. `2024-05-01T00:00:00Z`
+    #[arg(long, value_name = "TIMESTAMP", requires = "query")]
+    since: Option<String>,
+
+    /// Only count `--query` lines of entries with a `timestamp` at or before this one
+    #[arg(long, value_name = "TIMESTAMP", requires = "query")]
+    until: Option<String>,
+
+    /// Repeat file generation and per-file gzip with batched io_uring I/O and compare against blocking I/O
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    #[arg(long)]
+    io_uring: bool,
+
+    /// Also time gzip decompression into a byte-counting sink, without writing files
+    #[arg(long)]
+    null_sink: bool,
+
+    /// When to fsync compressed outputs; sync time is included in compression time
+    #[arg(long, value_enum, default_value_t)]
+    durability: Durability,
+
+    /// What the gzip headers of Step 2 record; `strip` makes outputs byte-reproducible
+    #[arg(long, value_enum, default_value_t)]
+    gzip_header: GzipHeader,
+
+    /// Give up on a `--sweep` or `--matrix` codec that runs longer than this, e.g. `30m`,
+    /// record it as timed out and go on with the next
+    #[arg(long, value_name = "DURATION", value_parser = soak::parse_duration)]
+    stage_timeout: Option<Duration>,
+
+    /// Measure each `--sweep` and `--matrix` codec in a child process, so a crash or OOM kill
+    /// is recorded as a failed codec instead of ending the run
+    #[arg(long)]
+    isolate: bool,
+
+    /// Measure every `--sweep` and `--matrix` codec afresh instead of reusing earlier
+    /// measurements of the same corpus from the cache file
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Keep `--sweep` and `--matrix` measurements in this file
+    #[arg(long, value_name = "FILE", default_value = cache::DEFAULT_PATH, conflicts_with = "no_cache")]
+    cache_file: PathBuf,
+
+    /// Add this latency to every file open, close and 1 MiB transfer of the gzip and zstd stages,
+    /// modelling NFS or EFS storage, e.g. `5ms`
+    #[arg(long, value_name = "DURATION", value_parser = soak::parse_duration)]
+    fs_latency: Option<Duration>,
+
+    /// Count this much overhead per stored object on top of its compressed size, e.g. `1K` of
+    /// HTTP headers per object-storage request or `4K` of filesystem metadata
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_size)]
+    object_overhead: Option<usize>,
+
+    /// Benchmark the files in this directory, under this `s3://bucket/prefix`, or the systemd journal
+    /// (`journal:` runs `journalctl -o export`, `journal:FILE` reads a saved export), instead of generating a corpus
+    #[arg(long, value_name = "DIR|S3_URL|journal:[FILE]")]
+    input: Option<input::Source>,
+
+    /// How to treat symbolic links under `--input`
+    #[arg(long, value_enum, default_value_t, requires = "input")]
+    symlinks: input::SymlinkPolicy,
+
+    /// Draw bar charts of stage durations and compressed sizes per codec after the results
+    #[arg(long)]
+    chart: bool,
+
+    /// Run on this percentage of the corpus, chosen at random with `--sample-seed`, e.g. `1%`
+    #[arg(long, value_name = "PERCENT", value_parser = sample::parse_percent, conflicts_with = "limit")]
+    sample: Option<f64>,
+
+    /// Run on this many files of the corpus, chosen at random with `--sample-seed`
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    limit: Option<u64>,
+
+    /// Seed for choosing the `--sample` or `--limit` subset; the same seed picks the same files
+    #[arg(long, default_value_t = 0)]
+    sample_seed: u64,
+
+    /// Seed for the generated corpus; the same seed generates the same files. Random when not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Validate the options and print estimated sizes and codec times from a small sample, without running
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Declare the winner by a weighted score instead of size alone, e.g. `size=0.5,compress_time=0.2,decompress_time=0.3`
+    #[arg(long, value_name = "COMPONENT=WEIGHT,...")]
+    weights: Option<score::Weights>,
+
+    /// Write machine-readable results to this JSON file
+    #[arg(long, value_name = "PATH")]
+    json: Option<PathBuf>,
+
+    /// Write every stage, per-file and matrix measurement to this Parquet file
+    #[cfg(feature = "parquet")]
+    #[arg(long, value_name = "PATH")]
+    output_parquet: Option<PathBuf>,
+
+    /// Write a compact Markdown summary table to this file
+    #[arg(long, value_name = "PATH")]
+    summary: Option<PathBuf>,
+
+    /// Results from an earlier `--json` run to compute the summary's deltas against
+    #[arg(long, value_name = "PATH", requires = "summary")]
+    baseline: Option<PathBuf>,
+
+    /// Pack the arguments, corpus seed, environment and results into this `.tar.zst` for `replay`
+    #[arg(long, value_name = "PATH")]
+    bundle: Option<PathBuf>,
+
+    /// Show the run next to `--bundle` bundles from other machines: this bundle, or every one in this directory
+    #[arg(long, value_name = "PATH")]
+    reference: Option<PathBuf>,
 }
 
-fn random_value(key: &str) -> serde_json::Value {
-    match key {
-        "timestamp" => {
-            let hour = thread_rng().gen_range(0..24);
-            let minute = thread_rng().gen_range(0..60);
-            let second = thread_rng().gen_range(0..60);
-            let millisecond = thread_rng().gen_range(0..1000);
-            serde_json::Value::String(format!("2025-07-09T{:02}:{:02}:{:02}.{:03}Z", hour, minute, second, millisecond))
-        }
-        "level" => {
-            let levels = ["INFO", "WARN", "ERROR", "DEBUG"];
-            serde_json::Value::String(levels[thread_rng().gen_range(0..levels.len())].to_string())
-        }
-        "message" => {
-            serde_json::Value::String(random_string(thread_rng().gen_range(50..151)))
-        }
-        "source_ip" => {
-            serde_json::Value::String(format!(
-                "{}.{}.{}.{}",
-                thread_rng().gen_range(1..255),
-                thread_rng().gen_range(1..255),
-                thread_rng().gen_range(1..255),
-                thread_rng().gen_range(1..255)
-            ))
-        }
-        "user_id" => {
-            serde_json::Value::String(format!("user-{}", thread_rng().gen_range(1000..10000)))
-        }
-        "request_id" => {
-            serde_json::Value::String(random_string(32))
-        }
-        "http_method" => {
-            let methods = ["GET", "POST", "PUT", "DELETE"];
-            serde_json::Value::String(methods[thread_rng().gen_range(0..methods.len())].to_string())
-        }
-        "http_path" => {
-            let segments = thread_rng().gen_range(1..4);
-            let path = (0..segments)
-                .map(|_| random_string(thread_rng().gen_range(5..11)))
-                .collect::<Vec<_>>()
-                .join("/");
-            serde_json::Value::String(format!("/{}", path))
-        }
-        "http_status" => {
-            let statuses = [200, 201, 400, 404, 500];
-            serde_json::Value::Number(statuses[thread_rng().gen_range(0..statuses.len())].into())
+impl RunArgs {
+    fn sweep_options(&self) -> sweep::SweepOptions {
+        sweep::SweepOptions {
+            #[cfg(feature = "brotli")]
+            brotli_lgwin: self.brotli_lgwin.clone(),
+            #[cfg(feature = "lz4")]
+            lz4_modes: self.lz4_modes.clone(),
+            #[cfg(feature = "lz4")]
+            lz4_acceleration: self.lz4_acceleration.clone(),
         }
-        "user_agent" => {
-            serde_json::Value::String("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36".to_string())
-        }
-        "response_time_ms" => {
-            serde_json::Value::Number(thread_rng().gen_range(10..501).into())
-        }
-        "app_version" => {
-            serde_json::Value::String(format!(
-                "{}.{}.{}",
-                thread_rng().gen_range(1..6),
-                thread_rng().gen_range(0..10),
-                thread_rng().gen_range(0..10)
-            ))
-        }
-        "service_name" => {
-            let services = ["auth-service", "product-service", "order-service"];
-            serde_json::Value::String(services[thread_rng().gen_range(0..services.len())].to_string())
-        }
-        "region" => {
-            let regions = ["us-east-1", "us-west-2", "eu-central-1"];
-            serde_json::Value::String(regions[thread_rng().gen_range(0..regions.len())].to_string())
-        }
-        "payload" => {
-            serde_json::Value::String(random_string(2500))
-        }
-        _ => serde_json::Value::String(random_string(10))
     }
 }
 
-fn generate_json() -> serde_json::Value {
-    let mut data = serde_json::Map::new();
-    for key in FIXED_KEYS {
-        data.insert(key.to_string(), random_value(key));
-    }
-    serde_json::Value::Object(data)
+#[derive(Subcommand)]
+enum Command {
+    /// Compare per-file and solid archiving strategies for both codecs
+    Strategies,
+    /// Print the lines of archive entries matching a regex, prefixed with their file name
+    Search {
+        /// Regular expression to look for
+        pattern: regex::bytes::Regex,
+
+        /// Archive written by the zstd step, e.g. `mock_logs/all_logs.zst` after `--keep-workspace`
+        archive: PathBuf,
+
+        /// Only print the names of entries with at least one match
+        #[arg(short = 'l', long)]
+        files_with_matches: bool,
+
+        /// Dictionary the archive was compressed with
+        #[arg(long, value_name = "FILE")]
+        dict: Option<PathBuf>,
+
+        /// Only search entries with a `timestamp` at or after this one, e.g. `2024-05-01T00:00:00Z`
+        #[arg(long, value_name = "TIMESTAMP")]
+        since: Option<String>,
+
+        /// Only search entries with a `timestamp` at or before this one
+        #[arg(long, value_name = "TIMESTAMP")]
+        until: Option<String>,
+    },
+    /// Print the size and name of every entry in an archive
+    List {
+        /// Archive written by the zstd step, e.g. `mock_logs/all_logs.zst` after `--keep-workspace`
+        archive: PathBuf,
+
+        /// Dictionary the archive was compressed with
+        #[arg(long, value_name = "FILE")]
+        dict: Option<PathBuf>,
+    },
+    /// Extract an archive, or the entries with a timestamp in [FROM, TO) from `--partition-by` archives
+    Extract {
+        /// Archive file, or directory of partition archives, e.g. `mock_logs/partitions` after `--keep-workspace`
+        source: PathBuf,
+
+        /// Start of the range (inclusive), RFC 3339, e.g. `2025-07-09T13:00:00Z`
+        #[arg(long, requires = "to")]
+        from: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// End of the range (exclusive), RFC 3339
+        #[arg(long, requires = "from")]
+        to: Option<chrono::DateTime<chrono::Utc>>,
+
+        /// Directory to write the extracted files into
+        #[arg(long, default_value = "extracted", conflicts_with = "to_tar")]
+        output: PathBuf,
+
+        /// Stream the entries as a tar archive to PATH instead, or to stdout with `-`
+        #[arg(long, value_name = "PATH")]
+        to_tar: Option<PathBuf>,
+
+        /// Skip checking each entry's checksum before writing it
+        #[arg(long)]
+        no_verify: bool,
+
+        /// Dictionary the archives were compressed with
+        #[arg(long, value_name = "FILE")]
+        dict: Option<PathBuf>,
+    },
+    /// Work with existing zstd archives
+    Archive {
+        #[command(subcommand)]
+        command: ArchiveCommand,
+    },
+    /// Work with zstd dictionaries
+    Dict {
+        #[command(subcommand)]
+        command: DictCommand,
+    },
+    /// Convert every .gz file under a directory to .zst, verifying each against its original
+    Recompress {
+        /// Directory to search for .gz files, recursively
+        dir: PathBuf,
+
+        /// zstd compression level
+        #[arg(long, default_value_t = 3)]
+        level: i32,
+
+        /// How to check each .zst against its .gz before it counts as converted
+        #[arg(long, value_enum, default_value_t)]
+        verify: recompress::Verify,
+
+        /// Only measure: write the .zst files into the workspace and leave the directory untouched
+        #[arg(long, conflicts_with = "delete_originals")]
+        dry_run: bool,
+
+        /// Delete each .gz once its .zst has been verified
+        #[arg(long)]
+        delete_originals: bool,
+
+        /// Write a JSON report listing every converted, deleted and failed file
+        #[arg(long, value_name = "PATH")]
+        report: Option<PathBuf>,
+
+        /// Sign the report with HMAC-SHA256 using the contents of this file as the key
+        #[arg(long, value_name = "PATH", requires = "report")]
+        signing_key: Option<PathBuf>,
+    },
+    /// Run the same codecs over every corpus listed in a JSON config and compare their rankings
+    Corpora {
+        /// Config listing the codecs and the corpora to run them on
+        config: PathBuf,
+    },
+    /// Run the same codecs over an old and a new version of a corpus and report how their compressed size changed
+    CompareCorpora {
+        /// Corpus before the change: a directory, or a single file
+        old: PathBuf,
+
+        /// Corpus after the change: a directory, or a single file
+        new: PathBuf,
+
+        /// Codecs to run on both, e.g. `gzip:6,zstd:3`; every enabled family at its default level when not given
+        #[arg(long, value_delimiter = ',', value_name = "CODEC:LEVEL")]
+        codecs: Vec<codec::CodecSpec>,
+    },
+    /// Compress a continuous synthetic event stream in rolling chunks and report how each codec holds up over time
+    Soak {
+        /// Events generated per second
+        #[arg(long, default_value_t = 5000)]
+        rate: u64,
+
+        /// How long to run, e.g. `2h`, `30m` or `90s`
+        #[arg(long, value_parser = soak::parse_duration, default_value = "10m")]
+        duration: Duration,
+
+        /// Stream time covered by each compressed chunk
+        #[arg(long, value_parser = soak::parse_duration, default_value = "10s")]
+        chunk: Duration,
+
+        /// How often to print a window of results
+        #[arg(long, value_parser = soak::parse_duration, default_value = "1m")]
+        interval: Duration,
+
+        /// Codecs to compress each chunk with
+        #[arg(long, value_delimiter = ',', value_name = "CODEC:LEVEL", default_value = "gzip:6,zstd:3")]
+        codecs: Vec<codec::CodecSpec>,
+
+        /// Seed for the event stream. Random when not given
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Write every window to this JSON file
+        #[arg(long, value_name = "PATH")]
+        json: Option<PathBuf>,
+    },
+    /// Watch a log directory and compress, verify and optionally delete every file log rotation leaves behind
+    Compact {
+        /// Directory the logs are rotated in
+        dir: PathBuf,
+
+        /// Codec to compress rotated files with
+        #[arg(long, default_value = "zstd:3", value_name = "CODEC:LEVEL")]
+        codec: codec::CodecSpec,
+
+        /// Regular expression matching the names of rotated files
+        #[arg(long, default_value = compact::DEFAULT_PATTERN)]
+        pattern: regex::Regex,
+
+        /// How long a rotated file must stay unchanged before it is compressed
+        #[arg(long, value_parser = soak::parse_duration, default_value = "10s")]
+        settle: Duration,
+
+        /// How often to look for rotated files
+        #[arg(long, value_parser = soak::parse_duration, default_value = "2s")]
+        poll: Duration,
+
+        /// Delete each original once its compressed copy has been verified
+        #[arg(long)]
+        delete_originals: bool,
+
+        /// File the lifetime statistics are kept in; defaults to `.gz-vs-zstd-compact.json` in DIR
+        #[arg(long, value_name = "PATH")]
+        state: Option<PathBuf>,
+
+        /// Compress the files that are ready now and exit instead of watching
+        #[arg(long)]
+        once: bool,
+    },
+    /// Report the member and frame structure of existing .gz, .zst and .tar.zst files
+    Inspect {
+        /// Files to inspect
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Dictionary the zstd frames were compressed with
+        #[arg(long, value_name = "FILE")]
+        dict: Option<PathBuf>,
+    },
+    /// Print per-stage and per-codec deltas between two `--json` results files
+    Diff {
+        /// Results of the earlier run
+        old: PathBuf,
+
+        /// Results of the later run
+        new: PathBuf,
+
+        /// Highlight size changes of at least this many percent
+        #[arg(long, value_parser = sample::parse_percent, default_value = "1%", value_name = "PERCENT")]
+        size_threshold: f64,
+
+        /// Highlight time changes of at least this many percent
+        #[arg(long, value_parser = sample::parse_percent, default_value = "5%", value_name = "PERCENT")]
+        time_threshold: f64,
+    },
+    /// Check a `--json` results file against the current schema version, optionally migrating it forward
+    Validate {
+        /// Results file written by `--json`
+        results: PathBuf,
+
+        /// Rewrite a conforming file of an older schema version in the current one
+        #[arg(long)]
+        migrate: bool,
+    },
+    /// Run the configuration recorded in a `--bundle` again and compare against its results
+    Replay {
+        /// Bundle written by `--bundle`
+        bundle: PathBuf,
+    },
+    /// Work with results written by `--json`
+    Report {
+        #[command(subcommand)]
+        command: ReportCommand,
+    },
+    /// Measure one codec for `--isolate`, as requested on stdin
+    #[command(hide = true)]
+    IsolatedMeasure,
 }
 
-fn format_bytes(bytes: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
-    let mut size = bytes as f64;
-    let mut unit_index = 0;
-    
-    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size /= 1024.0;
-        unit_index += 1;
-    }
-    
-    format!("{:.2} {}", size, UNITS[unit_index])
+#[derive(Subcommand)]
+enum ArchiveCommand {
+    /// Compress only the files of a directory that are new or changed since an archive into an incremental one
+    Update {
+        /// Archive to update: a full archive, or the latest increment of its chain
+        base: PathBuf,
+
+        /// Directory to compare against the archive's manifest
+        #[arg(long, value_name = "DIR")]
+        input: PathBuf,
+
+        /// Incremental archive to write, in the same directory as the base archive
+        #[arg(long, value_name = "PATH")]
+        output: PathBuf,
+
+        /// zstd compression level
+        #[arg(long, default_value_t = 3)]
+        level: i32,
+
+        /// Dictionary the archive was compressed with; the increment is compressed with it too
+        #[arg(long, value_name = "FILE")]
+        dict: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DictCommand {
+    /// Train a dictionary like `--dict` does and write it out for `--dict FILE` or `zstd -D`
+    Train {
+        /// Train on the files under this directory instead of a generated corpus
+        #[arg(long, value_name = "DIR")]
+        input: Option<PathBuf>,
+
+        /// Seed of the generated corpus to train on, the `--seed` of the run the dictionary is for.
+        /// Random when not given
+        #[arg(long, conflicts_with = "input")]
+        seed: Option<u64>,
+
+        /// Maximum dictionary size, e.g. `64K`
+        #[arg(long, value_parser = parse_size, default_value = "112K")]
+        size: usize,
+
+        /// File to write the dictionary to
+        #[arg(long, value_name = "FILE")]
+        output: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// Serve a results file as an HTML page that reloads whenever the file changes
+    Serve {
+        /// Results file written by `--json`
+        #[arg(long, default_value = "results.json")]
+        results: PathBuf,
+
+        /// Address to listen on; the default is reachable from the local network
+        #[arg(long, default_value = "0.0.0.0")]
+        bind: String,
+
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+}
+
+/// Parses sizes such as `512`, `16K`, `64KB` or `4MiB`. `KiB`, `MiB` and `GiB` are
+/// powers of 1024 and `KB`, `MB` and `GB` powers of 1000, as in `--units si`; the bare
+/// `K`, `M` and `G` shorthand is binary, as in `zstd --memory` and `dd`.
+fn parse_size(s: &str) -> std::result::Result<usize, String> {
+    let upper = s.trim().to_ascii_uppercase();
+    let digits = upper.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier: usize = match &upper[digits.len()..] {
+        "" | "B" => 1,
+        "K" | "KIB" => 1 << 10,
+        "M" | "MIB" => 1 << 20,
+        "G" | "GIB" => 1 << 30,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        unit => return Err(format!("unknown size unit `{}`", unit)),
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid size `{}`", s))
 }
 
 fn get_directory_size(path: &Path) -> Result<u64> {
@@ -161,166 +702,957 @@ fn get_directory_size(path: &Path) -> Result<u64> {
     Ok(total_size)
 }
 
-fn main() -> Result<()> {
-    println!("🚀 Starting compression comparison project");
-    println!("Generating {} fake JSON files...", NUM_FILES);
-    
-    // Create output directory
-    fs::create_dir_all(OUTPUT_DIR)?;
-    
-    // Step 1: Generate JSON files
-    println!("\n📝 Step 1: Generating JSON files");
-    let start = Instant::now();
-    let pb = ProgressBar::new(NUM_FILES as u64);
+/// Total size of the generated JSON files, ignoring any other artifacts in the workspace.
+fn corpus_size(names: &[String]) -> Result<u64> {
+    let mut total_size = 0;
+    for name in names {
+        total_size += fs::metadata(output_dir().join(name))?.len();
+    }
+    Ok(total_size)
+}
+
+fn progress_bar(len: usize) -> ProgressBar {
+    let pb = ProgressBar::new(len as u64);
     pb.set_style(ProgressStyle::default_bar()
         .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos:>7}/{len:7} {msg}")
         .unwrap()
         .progress_chars("=>-"));
-    
-    for i in 0..NUM_FILES {
-        let filename = format!("log_{:04}.json", i);
-        let filepath = Path::new(OUTPUT_DIR).join(&filename);
+    pb
+}
+
+/// Writes one pretty-printed log entry per name into the workspace, drawn from `seed`.
+fn generate_corpus(names: &[String], seed: u64) -> Result<Duration> {
+    fs::create_dir_all(output_dir())?;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let start = Instant::now();
+    let pb = progress_bar(names.len());
+    for filename in names {
+        let filepath = output_dir().join(filename);
+        if let Some(parent) = filepath.parent() {
+            fs::create_dir_all(parent)?;
+        }
         let file = File::create(&filepath)?;
         let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &generate_json())?;
+        serde_json::to_writer_pretty(writer, &generate_json_with(&mut rng))?;
         pb.inc(1);
     }
     pb.finish_with_message("JSON files generated!");
+
+    Ok(start.elapsed())
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    render::set_units(cli.units);
+    // Before any worker thread exists, so all of them inherit it.
+    priority::apply(cli.nice, cli.ionice)?;
+    if let Some(Command::Replay { bundle }) = &cli.command {
+        return replay(bundle);
+    }
+    // A long-running watcher has no use for a scratch workspace, and must not leave one behind.
+    if let Some(Command::Compact { dir, codec, pattern, settle, poll, delete_originals, state, once }) = cli.command {
+        let state = state.unwrap_or_else(|| dir.join(".gz-vs-zstd-compact.json"));
+        compact::run(&dir, &compact::Options { codec, pattern, settle, poll, delete_originals, state, once })?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // Inspecting existing files needs no generated corpus either.
+    if let Some(Command::Inspect { paths, dict }) = &cli.command {
+        let dict = dict.as_deref().map(dictionary::load).transpose()?;
+        return Ok(if inspect::run(paths, dict.as_deref())? { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+    }
+    if let Some(Command::Validate { results, migrate }) = &cli.command {
+        return Ok(if validate::run(results, *migrate)? { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+    }
+    // An isolated child measures in its parent's workspace.
+    if let Some(Command::IsolatedMeasure) = &cli.command {
+        isolate::worker()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    // Dropped at the end of `main`, so the workspace is also removed when a stage panics.
+    let _workspace = Workspace::create(cli.keep_workspace, cli.output_dir.as_deref())?;
+    let generated_names = cli.name_template.names(&cli.name_prefix, NUM_FILES)?;
+    match cli.command {
+        Some(Command::Strategies) => strategies::run(&generated_names, cli.run.object_overhead.map(|overhead| overhead as u64))?,
+        Some(Command::Search { pattern, archive, files_with_matches, dict, since, until }) => {
+            let dict = dict.as_deref().map(dictionary::load).transpose()?;
+            let range = index::TimeRange { since, until };
+            // Like grep, exit with 1 when nothing matched.
+            if search::run(&pattern, &archive, dict.as_deref(), files_with_matches, &range)? == 0 {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Some(Command::List { archive, dict }) => {
+            let dict = dict.as_deref().map(dictionary::load).transpose()?;
+            readers::list(&archive, dict.as_deref())?
+        }
+        Some(Command::Extract { source, from, to, output, to_tar, no_verify, dict }) => {
+            let dict = dict.as_deref().map(dictionary::load).transpose()?;
+            extract::run(&source, dict.as_deref(), from.zip(to), &output, to_tar.as_deref(), !no_verify)?
+        }
+        Some(Command::Archive { command: ArchiveCommand::Update { base, input, output, level, dict } }) => {
+            let dict = dict.as_deref().map(dictionary::load).transpose()?;
+            update::run(&base, &input, &output, &update::Options { level, dict: dict.as_deref() })?
+        }
+        Some(Command::Dict { command: DictCommand::Train { input, seed, size, output } }) => {
+            dictionary::train_to_file(input.as_deref(), seed.unwrap_or_else(rand::random), size, &output)?
+        }
+        Some(Command::Recompress { dir, level, verify, dry_run, delete_originals, report, signing_key }) => {
+            let options = recompress::Options { level, verify, dry_run, delete_originals, report, signing_key };
+            recompress::run(&dir, &options)?
+        }
+        Some(Command::Corpora { config }) => corpora::run(&config)?,
+        Some(Command::CompareCorpora { old, new, codecs }) => corpora::compare(&old, &new, &codecs)?,
+        Some(Command::Soak { rate, duration, chunk, interval, codecs, seed, json }) => {
+            let seed = seed.unwrap_or_else(rand::random);
+            soak::run(&soak::Options { rate, duration, chunk, interval, codecs, seed, json })?
+        }
+        Some(Command::Compact { .. }) => unreachable!("handled before the workspace is created"),
+        Some(Command::Inspect { .. }) => unreachable!("handled before the workspace is created"),
+        Some(Command::Diff { old, new, size_threshold, time_threshold }) => {
+            diff::run(&old, &new, diff::Thresholds { size: size_threshold, time: time_threshold })?
+        }
+        Some(Command::Validate { .. }) => unreachable!("handled before the workspace is created"),
+        Some(Command::Replay { .. }) => unreachable!("handled before the workspace is created"),
+        Some(Command::IsolatedMeasure) => unreachable!("handled before the workspace is created"),
+        Some(Command::Report { command: ReportCommand::Serve { results, bind, port } }) => {
+            report::serve(results, &bind, port)?
+        }
+        None => {
+            run_comparison(&cli.run, generated_names)?;
+            if interrupt::requested() {
+                return Ok(ExitCode::from(130));
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// `replay`: runs the arguments recorded in `bundle_path` in their own workspace.
+fn replay(bundle_path: &Path) -> Result<ExitCode> {
+    let (manifest, recorded) = bundle::read(bundle_path)?;
+    let cli = Cli::try_parse_from(std::iter::once("gz-vs-zstd".to_string()).chain(manifest.args.iter().cloned()))?;
+    anyhow::ensure!(cli.command.is_none(), "{} does not record a comparison run", bundle_path.display());
+    // Applied before the environments are compared, so a recorded `--nice` does not show up as a difference.
+    priority::apply(cli.nice, cli.ionice)?;
+    bundle::print_replay(bundle_path, &manifest);
+    let _workspace = Workspace::create(cli.keep_workspace, cli.output_dir.as_deref())?;
+    let generated_names = cli.name_template.names(&cli.name_prefix, NUM_FILES)?;
+    if let Some(replayed) = run_comparison(&cli.run, generated_names)? {
+        println!();
+        println!("🔁 Replay vs the recorded run:");
+        bundle::print_comparison(&recorded, &replayed);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Runs every enabled step and returns the results, or `None` for `--dry-run`.
+fn run_comparison(args: &RunArgs, generated_names: Vec<String>) -> Result<Option<results::Results>> {
+    println!("🚀 Starting compression comparison project");
+    // Read up front so a bad path fails before the run rather than after it.
+    let baseline = args.baseline.as_deref().map(results::Results::read).transpose()?;
+    let references = args.reference.as_deref().map(reference::load).transpose()?.unwrap_or_default();
+    let dict_file = match &args.dict {
+        Some(Some(path)) => {
+            anyhow::ensure!(args.dict_sizes.is_empty(), "--dict-sizes only applies when training, not with --dict FILE");
+            Some(dictionary::load(path)?)
+        }
+        _ => None,
+    };
+    if args.dry_run {
+        plan::print(args)?;
+        return Ok(None);
+    }
+    let seed = args.seed.unwrap_or_else(rand::random);
+    interrupt::enable();
+    if let Some(latency) = args.fs_latency {
+        latency::set(latency);
+    }
     
-    let json_generation_time = start.elapsed();
-    let json_size = get_directory_size(Path::new(OUTPUT_DIR))?;
+    // Step 1: Generate JSON files, or snapshot the input directory
+    let subset = sample::Subset::from_args(args.sample, args.limit.map(|limit| limit as usize));
+    let choose = |names: Vec<String>| match subset {
+        Some(subset) => sample::choose(names, subset, args.sample_seed),
+        None => names,
+    };
+    let (names, links, json_generation_time, skipped, full_files, full_size) = match &args.input {
+        Some(source) => {
+            println!("\n📝 Step 1: Snapshotting {}", source);
+            let snapshot = source.snapshot(args.symlinks)?;
+            let (full_files, full_size) = (snapshot.names.len(), corpus_size(&snapshot.names)?);
+            (choose(snapshot.names), snapshot.links, snapshot.elapsed, snapshot.skipped, full_files, Some(full_size))
+        }
+        None => {
+            let names = choose(generated_names);
+            println!("Generating {} fake JSON files...", names.len());
+            println!("\n📝 Step 1: Generating JSON files (seed {})", seed);
+            let generation_time = generate_corpus(&names, seed)?;
+            (names, Vec::new(), generation_time, Vec::new(), NUM_FILES, None)
+        }
+    };
+    anyhow::ensure!(!names.is_empty(), "no readable files to compress");
+    let json_size = corpus_size(&names)?;
+    if subset.is_some() {
+        println!("🎯 Sampled {} of {} files (seed {})", names.len(), full_files, args.sample_seed);
+    }
+
+    // Step 1a: Characterize the corpus before anything is compressed
+    println!("\n🔎 Step 1a: Analyzing the corpus");
+    let corpus_stats = stats::analyze(output_dir(), &names)?;
+    stats::print(&corpus_stats, names.len(), json_size);
     
     // Step 2: Compress each file with gzip
     println!("\n🗜️  Step 2: Compressing individual files with gzip");
+    let gzip_dir = output_dir().join("gzip");
+    fs::create_dir_all(&gzip_dir)?;
     let start = Instant::now();
-    let pb = ProgressBar::new(NUM_FILES as u64);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos:>7}/{len:7} {msg}")
-        .unwrap()
-        .progress_chars("=>-"));
+    let pb = progress_bar(names.len());
+    let mut gzip_sync_time = Duration::ZERO;
+    let trips = latency::round_trips();
+    let mut gz_paths = Vec::with_capacity(names.len());
+    let mut file_measurements = Vec::with_capacity(2 * names.len());
     
-    for i in 0..NUM_FILES {
-        let json_filename = format!("log_{:04}.json", i);
-        let gz_filename = format!("log_{:04}.json.gz", i);
-        let json_path = Path::new(OUTPUT_DIR).join(&json_filename);
-        let gz_path = Path::new(OUTPUT_DIR).join(&gz_filename);
+    for name in &names {
+        let file_start = Instant::now();
+        let json_path = output_dir().join(name);
+        let gz_path = gzip_dir.join(format!("{}.gz", name));
+        if let Some(parent) = gz_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
         
-        let input_file = File::open(&json_path)?;
-        let output_file = File::create(&gz_path)?;
-        let mut encoder = GzEncoder::new(output_file, Compression::default());
+        let input_file = latency::open(&json_path)?;
+        let output_file = latency::create(&gz_path)?;
+        let mut encoder = args.gzip_header.builder(&json_path)?.write(output_file, Compression::default());
         
-        std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
-        encoder.finish()?;
+        let bytes_in = std::io::copy(&mut BufReader::new(input_file), &mut encoder)?;
+        let output_file = encoder.finish()?;
+        if args.durability == Durability::PerFile {
+            gzip_sync_time += durability::sync_file(output_file.get_ref())?;
+        }
+        file_measurements.push(results::FileMeasurement {
+            stage: "gzip_compress",
+            name: name.clone(),
+            bytes_in,
+            bytes_out: output_file.get_ref().metadata()?.len(),
+            time: file_start.elapsed(),
+        });
+        gz_paths.push(gz_path);
         pb.inc(1);
     }
+    if args.durability == Durability::Batched {
+        gzip_sync_time += durability::sync_paths(&gz_paths)?;
+    }
+    if args.durability != Durability::None {
+        gzip_sync_time += durability::sync_dir(&gzip_dir)?;
+    }
     pb.finish_with_message("Individual gzip compression complete!");
     
     let gzip_compression_time = start.elapsed();
-    let _gzip_size = get_directory_size(Path::new(OUTPUT_DIR))?;
+    let gzip_compress_trips = latency::round_trips() - trips;
+    let _gzip_size = get_directory_size(&gzip_dir)?;
+    let gzip_digest = reproducible::digest(&gz_paths)?;
     
     // Step 3: Decompress each gzip file
     println!("\n📦 Step 3: Decompressing gzip files");
+    let gunzip_dir = output_dir().join("gunzip");
+    fs::create_dir_all(&gunzip_dir)?;
     let start = Instant::now();
-    let pb = ProgressBar::new(NUM_FILES as u64);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos:>7}/{len:7} {msg}")
-        .unwrap()
-        .progress_chars("=>-"));
+    let trips = latency::round_trips();
+    let pb = progress_bar(names.len());
     
-    for i in 0..NUM_FILES {
-        let gz_filename = format!("log_{:04}.json.gz", i);
-        let decompressed_filename = format!("log_{:04}_decompressed.json", i);
-        let gz_path = Path::new(OUTPUT_DIR).join(&gz_filename);
-        let decompressed_path = Path::new(OUTPUT_DIR).join(&decompressed_filename);
+    for (name, gz_path) in names.iter().zip(&gz_paths) {
+        let file_start = Instant::now();
+        let decompressed_path = gunzip_dir.join(name);
+        if let Some(parent) = decompressed_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
         
-        let input_file = File::open(&gz_path)?;
-        let output_file = File::create(&decompressed_path)?;
+        let input_file = latency::open(gz_path)?;
+        let output_file = latency::create(&decompressed_path)?;
         let mut decoder = GzDecoder::new(BufReader::new(input_file));
         
-        std::io::copy(&mut decoder, &mut BufWriter::new(output_file))?;
+        let bytes_out = std::io::copy(&mut decoder, &mut BufWriter::new(output_file))?;
+        file_measurements.push(results::FileMeasurement {
+            stage: "gzip_decompress",
+            name: name.clone(),
+            bytes_in: decoder.into_inner().get_ref().get_ref().metadata()?.len(),
+            bytes_out,
+            time: file_start.elapsed(),
+        });
         pb.inc(1);
     }
     pb.finish_with_message("Gzip decompression complete!");
     
     let gzip_decompression_time = start.elapsed();
+    let gzip_decompress_trips = latency::round_trips() - trips;
+    
+    // Same decode again, discarding the output, to separate codec speed from write throughput
+    let gzip_sink_time = if args.null_sink {
+        println!("\n🕳️  Step 3b: Decompressing gzip files into a null sink");
+        let start = Instant::now();
+        let pb = progress_bar(names.len());
+        let mut decoded = 0;
+        for gz_path in &gz_paths {
+            let mut decoder = GzDecoder::new(BufReader::new(latency::open(gz_path)?));
+            decoded += std::io::copy(&mut decoder, &mut std::io::sink())?;
+            pb.inc(1);
+        }
+        pb.finish_with_message("Null-sink decompression complete!");
+        anyhow::ensure!(decoded == json_size, "null-sink decompression produced {} of {} bytes", decoded, json_size);
+        Some(start.elapsed())
+    } else {
+        None
+    };
     
     // Step 4: Compress all original JSON files with zstd
     println!("\n🗜️  Step 4: Compressing all files with zstd");
     let start = Instant::now();
+    let trips = latency::round_trips();
     
-    let zstd_archive_path = Path::new(OUTPUT_DIR).join("all_logs.zst");
-    let mut zstd_encoder = zstd::Encoder::new(File::create(&zstd_archive_path)?, 3)?;
+    let zstd_archive_path = args.archive_path.clone().unwrap_or_else(|| output_dir().join("all_logs.zst"));
+    let archive_dir = match zstd_archive_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(archive_dir)?;
+    let pb = progress_bar(names.len());
+    let archive_options = archive::Options { level: 3, workers: args.zstd_workers, dedup: args.dedup, dict: dict_file.as_deref() };
+    let dedup = archive::write_archive(output_dir(), &names, &links, &zstd_archive_path, archive_options, &pb)?;
+    // A single archive has nothing to batch, so both policies sync it once.
+    let zstd_sync_time = if args.durability == Durability::None {
+        Duration::ZERO
+    } else {
+        durability::sync_paths(std::slice::from_ref(&zstd_archive_path))? + durability::sync_dir(archive_dir)?
+    };
+    pb.finish_with_message("Zstd compression complete!");
     
-    let pb = ProgressBar::new(NUM_FILES as u64);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos:>7}/{len:7} {msg}")
-        .unwrap()
-        .progress_chars("=>-"));
+    // Dedup hashing is verification overhead, not codec work, so it is reported on its own.
+    let zstd_compression_time = start.elapsed().saturating_sub(Duration::from_secs_f64(dedup.hash_secs));
+    let zstd_size = fs::metadata(&zstd_archive_path)?.len();
+    let zstd_digest = reproducible::digest(std::slice::from_ref(&zstd_archive_path))?;
+    let zstd_compress_trips = latency::round_trips() - trips;
     
-    for i in 0..NUM_FILES {
-        let json_filename = format!("log_{:04}.json", i);
-        let json_path = Path::new(OUTPUT_DIR).join(&json_filename);
-        
-        let mut input_file = File::open(&json_path)?;
-        
-        // Write filename header for the archive
-        let filename_bytes = json_filename.as_bytes();
-        zstd_encoder.write_all(&(filename_bytes.len() as u32).to_le_bytes())?;
-        zstd_encoder.write_all(filename_bytes)?;
-        
-        // Write file content
-        let file_size = input_file.metadata()?.len();
-        zstd_encoder.write_all(&(file_size as u32).to_le_bytes())?;
-        std::io::copy(&mut input_file, &mut zstd_encoder)?;
-        
-        pb.inc(1);
-    }
+    // Step 4a: Extract the archive, so a weighted verdict can compare decompression too
+    let mut zstd_decompress_trips = 0;
+    let zstd_decompression_time = if args.weights.is_some() {
+        println!("\n📦 Step 4a: Decompressing the zstd archive");
+        let unzstd_dir = output_dir().join("unzstd");
+        let start = Instant::now();
+        let trips = latency::round_trips();
+        let pb = progress_bar(names.len());
+        archive::for_each_entry(&zstd_archive_path, dict_file.as_deref(), true, |entry, content| {
+            if !entry.is_symlink {
+                let target = unzstd_dir.join(&entry.name);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                std::io::copy(content, &mut BufWriter::new(latency::create(target)?))?;
+                pb.inc(1);
+            }
+            Ok(())
+        })?;
+        pb.finish_with_message("Zstd decompression complete!");
+        zstd_decompress_trips = latency::round_trips() - trips;
+        Some(start.elapsed())
+    } else {
+        None
+    };
     
-    zstd_encoder.finish()?;
-    pb.finish_with_message("Zstd compression complete!");
+    // Step 4b: Multi-frame archive with a sidecar field index
+    let indexed = if args.index && !interrupt::requested() {
+        println!("\n🗂️  Step 4b: Writing the indexed zstd archive");
+        let archive_path = output_dir().join("all_logs.indexed.zst");
+        let index_path = index::sidecar(&archive_path);
+        let pb = progress_bar(names.len());
+        let index = index::write_indexed(&names, &archive_path, &index_path, 3, &pb)?;
+        pb.finish_with_message("Indexed archive complete!");
+        Some((archive_path, index_path, index))
+    } else {
+        None
+    };
     
-    let zstd_compression_time = start.elapsed();
-    let zstd_size = fs::metadata(&zstd_archive_path)?.len();
+    // Step 4c: One archive per time bucket
+    let partitioned = match args.partition_by.filter(|_| !interrupt::requested()) {
+        Some(by) => {
+            println!("\n🕐 Step 4c: Writing time-partitioned archives");
+            let pb = progress_bar(names.len());
+            let partitioned = partition::write(&names, by, &output_dir().join("partitions"), 3, &pb)?;
+            pb.finish_with_message("Partitioned archives complete!");
+            Some(partitioned)
+        }
+        None => None,
+    };
+    
+    // Step 4d: Read the archive back through both readers
+    let reader_results = if args.reader_bench && !interrupt::requested() {
+        println!("\n📖 Step 4d: Listing and extracting the zstd archive with each reader");
+        Some(readers::run(&zstd_archive_path, dict_file.as_deref(), &output_dir().join("extracted"))?)
+    } else {
+        None
+    };
+    
+    // Step 5: Per-file zstd with trained dictionaries
+    let dict_results = if args.dict.is_some() && !interrupt::requested() {
+        let sizes = if args.dict_sizes.is_empty() { vec![dictionary::DEFAULT_DICT_SIZE] } else { args.dict_sizes.clone() };
+        let files = bench::load_corpus(output_dir(), &names)?;
+        // An empty dictionary gives the plain per-file zstd baseline.
+        let (baseline, _, _) = dictionary::measure(&files, &[], 3)?;
+        let global = match &dict_file {
+            Some(dict) => {
+                println!("\n📚 Step 5: Compressing with the zstd dictionary from --dict");
+                vec![dictionary::reuse(&files, dict, 3)?]
+            }
+            None => {
+                println!("\n📚 Step 5: Training zstd dictionaries");
+                dictionary::sweep(&files, &sizes, 3)?
+            }
+        };
+        let partitioned = match &args.dict_partition {
+            Some(field) => Some(dictionary::sweep_partitioned(&files, field, &sizes, 3)?),
+            None => None,
+        };
+        Some((global, partitioned, baseline))
+    } else {
+        None
+    };
+    
+    // Step 6: Matched-speed comparison
+    let matched = match args.match_speed.filter(|_| !interrupt::requested()) {
+        Some(reference) => {
+            println!("\n⚖️  Step 6: Matching compression speed to {}", reference.build().name());
+            let files = bench::load_corpus(output_dir(), &names)?;
+            Some(matched::run(reference, &files, json_size)?)
+        }
+        None => None,
+    };
+    
+    // Step 7: gzip vs zlib vs raw DEFLATE framing
+    let framings = if args.framings && !interrupt::requested() {
+        println!("\n🧾 Step 7: Comparing DEFLATE framings");
+        let files = bench::load_corpus(output_dir(), &names)?;
+        Some(framing::run(&files, 6)?)
+    } else {
+        None
+    };
+
+    // Step 7b: zstd frame checksum and content size
+    let zstd_frames = if args.zstd_frame_options && !interrupt::requested() {
+        println!("\n🧾 Step 7b: Comparing zstd frame options");
+        let files = bench::load_corpus(output_dir(), &names)?;
+        Some(framing::run_zstd(&files, 3)?)
+    } else {
+        None
+    };
+
+    // Step 7c: Independent chunks vs one stream
+    let chunked = match args.chunk_size.filter(|_| !interrupt::requested()) {
+        Some(chunk_size) => {
+            println!("\n🧱 Step 7c: Compressing in independent {} chunks", format_bytes(chunk_size as u64));
+            Some(chunking::run(output_dir(), &names, chunk_size)?)
+        }
+        None => None,
+    };
+    
+    // Step 7d: Shortened JSON keys
+    let minified = if args.minify_keys && !interrupt::requested() {
+        println!("\n✂️  Step 7d: Shortening JSON keys");
+        let files = bench::load_corpus(output_dir(), &names)?;
+        Some(minify::run(&files)?)
+    } else {
+        None
+    };
+    
+    // Step 8: Level sweeps
+    let isolation = args.isolate.then(|| isolate::Corpus { dir: output_dir().to_path_buf(), names: names.clone(), options: args.sweep_options() });
+    let cache = if args.no_cache || (args.sweep.is_empty() && !args.matrix) {
+        None
+    } else {
+        let paths: Vec<PathBuf> = names.iter().map(|name| output_dir().join(name)).collect();
+        Some(cache::Cache::open(&args.cache_file, reproducible::digest(&paths)?)?)
+    };
+    let mut watchdog = watchdog::Watchdog::new(args.stage_timeout, isolation, cache);
+    let sweeps = if args.sweep.is_empty() || interrupt::requested() {
+        None
+    } else {
+        println!("\n📈 Step 8: Sweeping codec levels");
+        let files = Arc::new(bench::load_corpus(output_dir(), &names)?);
+        Some(sweep::run(&args.sweep, &args.sweep_options(), &files, &mut watchdog)?)
+    };
+    
+    // Step 9: Codec × level matrix
+    let matrix_cells = if args.matrix && !interrupt::requested() {
+        println!("\n🧮 Step 9: Running the codec matrix");
+        let files = Arc::new(bench::load_corpus(output_dir(), &names)?);
+        matrix::run(&args.sweep_options(), &files, json_size, &mut watchdog)?
+    } else {
+        Vec::new()
+    };
+    
+    // Step 9b: Per-file sizes and times for plotting ratio against file size
+    let scatter_points = if args.scatter.is_empty() || interrupt::requested() {
+        Vec::new()
+    } else {
+        println!("\n🔬 Step 9b: Measuring every file with each --scatter codec");
+        let files = bench::load_corpus(output_dir(), &names)?;
+        scatter::run(&args.scatter, &names, &files)?
+    };
+    
+    // Step 10: Blocking vs io_uring I/O
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    let io_comparison = if args.io_uring && !interrupt::requested() {
+        println!("\n⚡ Step 10: Comparing blocking and io_uring I/O");
+        let docs = bench::load_corpus(output_dir(), &names)?;
+        Some(uring::run(&output_dir().join("io_uring"), &names, &docs)?)
+    } else {
+        None
+    };
+    
+    // Step 11: Decompress + parse
+    let parse_results = if args.parse.is_empty() || interrupt::requested() {
+        None
+    } else {
+        println!("\n🔍 Step 11: Decompressing and parsing JSON");
+        let files = bench::load_corpus(output_dir(), &names)?;
+        Some(parse::run(&args.parse, &names, &files)?)
+    };
+    
+    // Step 11b: Decompress, parse into the log schema and validate
+    let pipeline_results = if args.pipeline.is_empty() || interrupt::requested() {
+        None
+    } else {
+        println!("\n🧪 Step 11b: Running the end-to-end consumer pipeline");
+        let files = bench::load_corpus(output_dir(), &names)?;
+        Some(pipeline::run(&args.pipeline, &names, &files)?)
+    };
+    
+    // Step 11c: Compression and decompression at the same time
+    let mixed_results = if args.mixed.is_empty() || interrupt::requested() {
+        None
+    } else {
+        println!("\n🔀 Step 11c: Running the mixed workload");
+        let files = bench::load_corpus(output_dir(), &names)?;
+        Some(mixed::run(&args.mixed, &args.mixed_ratios, args.mixed_duration, &files)?)
+    };
+    
+    // Step 12: Streaming query over the compressed artifacts
+    let query_results = match args.query.as_ref().filter(|_| !interrupt::requested()) {
+        Some(predicate) => {
+            println!("\n🔎 Step 12: Querying the compressed artifacts");
+            Some(query::run(
+                predicate,
+                &index::TimeRange { since: args.since.clone(), until: args.until.clone() },
+                &gz_paths,
+                &zstd_archive_path,
+                dict_file.as_deref(),
+                indexed.as_ref().map(|(archive_path, _, index)| (archive_path.as_path(), index)),
+            )?)
+        }
+        None => None,
+    };
+    
+    // Step 13: The same records as Parquet
+    #[cfg(feature = "parquet")]
+    let columnar = if args.parquet.is_empty() || interrupt::requested() {
+        None
+    } else {
+        println!("\n🧱 Step 13: Converting log records to Parquet");
+        let files = bench::load_corpus(output_dir(), &names)?;
+        Some(columnar::run(&args.parquet, &names, &files)?)
+    };
     
     // Calculate sizes for comparison
-    let individual_gz_size: u64 = (0..NUM_FILES)
-        .map(|i| {
-            let gz_filename = format!("log_{:04}.json.gz", i);
-            let gz_path = Path::new(OUTPUT_DIR).join(&gz_filename);
-            fs::metadata(&gz_path).map(|m| m.len()).unwrap_or(0)
-        })
+    let individual_gz_size: u64 = gz_paths
+        .iter()
+        .map(|gz_path| fs::metadata(gz_path).map(|m| m.len()).unwrap_or(0))
         .sum();
     
     // Display results
     println!("\n📊 COMPRESSION COMPARISON RESULTS");
     println!("=====================================");
-    println!("Original JSON files:");
-    println!("  Size: {}", format_bytes(json_size));
-    println!("  Generation time: {:.2?}", json_generation_time);
+    let environment = bundle::Environment::current();
+    println!(
+        "Machine: {} ({} CPUs, {})",
+        environment.cpu_model.as_deref().unwrap_or(&environment.arch),
+        environment.cpus,
+        if cfg!(feature = "no-asm") { "zstd assembly disabled" } else { "zstd assembly enabled" }
+    );
+    if !environment.cpu_features.is_empty() {
+        println!("  CPU features: {}", environment.cpu_features.join(", "));
+    }
+    if let Some(priority) = environment.priority.as_ref().filter(|priority| !priority.is_default()) {
+        println!("  Priority: {}", priority);
+    }
+    println!();
+    match &args.input {
+        Some(source) => {
+            println!("Input files ({}):", source);
+            println!("  Files: {}", names.len());
+            if !links.is_empty() {
+                println!("  Symlinks stored in the zstd archive: {}", links.len());
+            }
+            println!("  Size: {}", format_bytes(json_size));
+            match source {
+                input::Source::Dir(_) => println!("  Snapshot time: {}", render::duration(json_generation_time)),
+                input::Source::S3 { .. } => println!("  Download time: {}", render::duration(json_generation_time)),
+                input::Source::Journal(_) => println!("  Export time: {}", render::duration(json_generation_time)),
+            }
+        }
+        None => {
+            println!("Original JSON files:");
+            println!("  Size: {}", format_bytes(json_size));
+            println!("  Generation time: {}", render::duration(json_generation_time));
+        }
+    }
     println!();
     println!("Individual gzip compression:");
     println!("  Size: {}", format_bytes(individual_gz_size));
-    println!("  Compression time: {:.2?}", gzip_compression_time);
-    println!("  Decompression time: {:.2?}", gzip_decompression_time);
-    println!("  Compression ratio: {:.2}%", (individual_gz_size as f64 / json_size as f64) * 100.0);
+    println!("  Compression time: {}", render::duration(gzip_compression_time));
+    if args.durability != Durability::None {
+        println!("    of which fsync ({}): {}", args.durability, render::duration(gzip_sync_time));
+    }
+    latency::print(gzip_compress_trips);
+    println!("  Output digest: {} (gzip header: {})", &gzip_digest[..16], args.gzip_header);
+    println!(
+        "  Compression throughput: {} ({} per core, 1 thread), {}",
+        render::rate(json_size, gzip_compression_time),
+        render::mb_per_sec(bench::throughput_per_core(json_size, gzip_compression_time, 1)),
+        render::files_rate(names.len(), gzip_compression_time)
+    );
+    println!("  Decompression time: {}", render::duration(gzip_decompression_time));
+    latency::print(gzip_decompress_trips);
+    if let Some(sink_time) = gzip_sink_time {
+        println!("    into a null sink: {} ({})", render::duration(sink_time), render::rate(json_size, sink_time));
+        println!("    file output overhead: {}", render::duration(gzip_decompression_time.saturating_sub(sink_time)));
+    }
+    println!("  Compression ratio: {}", render::percent(individual_gz_size as f64 / json_size as f64));
     println!();
     println!("Multi-file zstd compression:");
+    if args.archive_path.is_some() {
+        println!("  Archive: {}", zstd_archive_path.display());
+    }
     println!("  Size: {}", format_bytes(zstd_size));
-    println!("  Compression time: {:.2?}", zstd_compression_time);
-    println!("  Compression ratio: {:.2}%", (zstd_size as f64 / json_size as f64) * 100.0);
+    println!("  Compression time: {}", render::duration(zstd_compression_time));
+    if args.durability != Durability::None {
+        println!("    of which fsync ({}): {}", args.durability, render::duration(zstd_sync_time));
+    }
+    latency::print(zstd_compress_trips);
+    println!("  Output digest: {}", &zstd_digest[..16]);
+    let zstd_threads = args.zstd_workers.max(1);
+    println!(
+        "  Compression throughput: {} ({} per core, {} thread{})",
+        render::rate(json_size, zstd_compression_time),
+        render::mb_per_sec(bench::throughput_per_core(json_size, zstd_compression_time, zstd_threads)),
+        zstd_threads,
+        if zstd_threads == 1 { "" } else { "s" }
+    );
+    if let Some(time) = zstd_decompression_time {
+        println!("  Decompression time: {}", render::duration(time));
+        latency::print(zstd_decompress_trips);
+    }
+    println!("  Compression ratio: {}", render::percent(zstd_size as f64 / json_size as f64));
+    if let Some(dict) = &dict_file {
+        println!("  Dictionary: ID {} ({}, not included in the size)", dictionary::id(dict).unwrap_or_default(), format_bytes(dict.len() as u64));
+    }
+    if args.dedup {
+        // Dedup and compression savings are separate: the codec only ever saw the unique content.
+        let unique = json_size - dedup.saved_bytes;
+        println!(
+            "  Dedup: {} duplicate files stored as references, saving {} ({} of the JSON) before compression",
+            dedup.references,
+            format_bytes(dedup.saved_bytes),
+            render::percent(dedup.saved_bytes as f64 / json_size as f64)
+        );
+        println!("  Compression ratio of the unique content: {} of {}", render::percent(zstd_size as f64 / unique as f64), format_bytes(unique));
+        println!("  Dedup hashing (BLAKE3, not in the compression time): {}", render::seconds(dedup.hash_secs));
+    }
+    if let Some(partitioned) = &partitioned {
+        println!();
+        println!("Time-partitioned zstd archives:");
+        partition::print(partitioned, zstd_size, json_size);
+    }
+    if let Some(reader_results) = &reader_results {
+        println!();
+        println!("Archive readers:");
+        readers::print(reader_results, json_size);
+    }
+    if let Some((archive_path, index_path, index)) = &indexed {
+        let archive_size = fs::metadata(archive_path)?.len();
+        let index_size = fs::metadata(index_path)?.len();
+        println!();
+        println!("Indexed zstd archive ({} frames):", index.frames.len());
+        println!(
+            "  Size: {} ({} vs solid) + {} index ({} of the archive)",
+            format_bytes(archive_size),
+            render::change(archive_size as f64, zstd_size as f64),
+            format_bytes(index_size),
+            render::percent(index_size as f64 / archive_size as f64)
+        );
+        println!("  Indexed fields: {}", index.values.keys().cloned().collect::<Vec<_>>().join(", "));
+    }
+    if let Some((results, partitioned, baseline)) = &dict_results {
+        println!();
+        println!("Per-file zstd with dictionary:");
+        println!("  Without dictionary: {}", format_bytes(*baseline));
+        dictionary::print_results(results, *baseline, json_size);
+        if let Some(partitioned) = partitioned {
+            println!();
+            println!("Per-partition vs global dictionaries:");
+            dictionary::print_partitioned(partitioned, results);
+        }
+    }
+    if let Some(matched) = &matched {
+        println!();
+        println!("Matched-speed comparison (per-file, in memory):");
+        matched::print(matched, json_size);
+    }
+    if let Some(framings) = &framings {
+        println!();
+        println!("DEFLATE framing overhead (per-file, in memory):");
+        framing::print(framings, names.len(), json_size);
+    }
+    if let Some(zstd_frames) = &zstd_frames {
+        println!();
+        println!("zstd frame option overhead (per-file, in memory):");
+        framing::print_zstd(zstd_frames, names.len(), json_size);
+    }
+    if let (Some(chunked), Some(chunk_size)) = (&chunked, args.chunk_size) {
+        println!();
+        println!("Chunked vs whole-stream compression ({} chunks, streamed from disk):", format_bytes(chunk_size as u64));
+        chunking::print(chunked, chunk_size, json_size);
+    }
+    if let Some(minified) = &minified {
+        println!();
+        println!("Key minification (in memory):");
+        minify::print(minified);
+    }
+    if let Some(sweeps) = &sweeps {
+        sweep::print(sweeps, json_size);
+    }
+    if !matrix_cells.is_empty() {
+        println!();
+        println!("Codec matrix (per-file, in memory):");
+        matrix::print(&matrix_cells);
+    }
+    if let Some(cache) = watchdog.cache.as_ref().filter(|cache| cache.hits > 0) {
+        println!();
+        println!("♻️  Reused {} measurements from {}; --no-cache measures them afresh", cache.hits, args.cache_file.display());
+    }
+    if !watchdog.timeouts.is_empty() {
+        println!();
+        println!("⏱️  Timed out (--stage-timeout):");
+        for timed_out in &watchdog.timeouts {
+            println!("  {}", timed_out);
+        }
+    }
+    if !watchdog.failures.is_empty() {
+        println!();
+        println!("💥 Failed (--isolate):");
+        for failure in &watchdog.failures {
+            println!("  {}", failure);
+            for line in failure.stderr.lines() {
+                println!("    | {}", line);
+            }
+        }
+    }
+    if !scatter_points.is_empty() {
+        println!();
+        println!("Ratio by file size (per-file, in memory; every file is in the JSON and Parquet output):");
+        scatter::print(&scatter_points);
+    }
+    if let Some(parse_results) = &parse_results {
+        println!();
+        println!("Decompress + parse (per-file, in memory):");
+        parse::print(parse_results, json_size);
+    }
+    if let Some(pipeline_results) = &pipeline_results {
+        println!();
+        println!("End-to-end pipeline (decompress, deserialize and validate each record, per-file, in memory):");
+        pipeline::print(pipeline_results, json_size);
+    }
+    if let Some(mixed_results) = &mixed_results {
+        println!();
+        println!("Mixed workload (compressing and decompressing concurrently, per worker, in memory):");
+        mixed::print(mixed_results);
+    }
+    if let Some(query_results) = &query_results {
+        println!();
+        println!("Streaming query (decompress and filter, nothing written):");
+        query::print(query_results, json_size);
+    }
+    #[cfg(feature = "parquet")]
+    if let Some(columnar) = &columnar {
+        println!();
+        println!("Columnar storage (in memory):");
+        columnar::print(columnar, json_size);
+    }
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    if let Some(comparison) = &io_comparison {
+        println!();
+        println!("I/O backend comparison:");
+        uring::print(comparison);
+    }
+    if !skipped.is_empty() {
+        println!();
+        println!("⚠️  Skipped {} input entries (excluded from every total):", skipped.len());
+        input::print_skipped(&skipped);
+    }
+    // With --object-overhead the verdict weighs what storage and egress are billed for, not payload alone.
+    let stored = [
+        overhead::Stored { label: "per-file gzip".to_string(), objects: names.len() as u64, payload: individual_gz_size },
+        overhead::Stored { label: "solid zstd".to_string(), objects: 1, payload: zstd_size },
+    ];
+    let object_overhead = args.object_overhead.unwrap_or(0) as u64;
+    let (gzip_total, zstd_total) = (stored[0].effective(object_overhead), stored[1].effective(object_overhead));
+    if args.object_overhead.is_some() {
+        println!();
+        println!("📦 Effective size with {} per object (--object-overhead):", format_bytes(object_overhead));
+        overhead::print(&stored, object_overhead);
+    }
     println!();
     println!("🏆 WINNER:");
-    if zstd_size < individual_gz_size {
-        let savings = individual_gz_size - zstd_size;
-        let savings_percent = (savings as f64 / individual_gz_size as f64) * 100.0;
-        println!("  Zstd wins by {} ({:.2}% smaller)", format_bytes(savings), savings_percent);
+    if let (Some(weights), Some(zstd_decompression_time)) = (args.weights, zstd_decompression_time) {
+        let contenders = [
+            score::Contender {
+                label: "per-file gzip",
+                size: gzip_total,
+                compress_time: gzip_compression_time,
+                decompress_time: gzip_decompression_time,
+            },
+            score::Contender {
+                label: "solid zstd",
+                size: zstd_total,
+                compress_time: zstd_compression_time,
+                decompress_time: zstd_decompression_time,
+            },
+        ];
+        let winner = score::print(&contenders, weights);
+        println!("  {} wins on the weighted score", if winner == 0 { "Gzip" } else { "Zstd" });
+    } else if zstd_total < gzip_total {
+        let savings = gzip_total - zstd_total;
+        println!("  Zstd wins by {} ({} smaller)", format_bytes(savings), render::percent(savings as f64 / gzip_total as f64));
     } else {
-        let savings = zstd_size - individual_gz_size;
-        let savings_percent = (savings as f64 / zstd_size as f64) * 100.0;
-        println!("  Gzip wins by {} ({:.2}% smaller)", format_bytes(savings), savings_percent);
+        let savings = zstd_total - gzip_total;
+        println!("  Gzip wins by {} ({} smaller)", format_bytes(savings), render::percent(savings as f64 / zstd_total as f64));
     }
     
-    println!("\n✅ Compression comparison complete!");
+    let mut results = results::Results::new(results::CorpusInfo {
+        files: names.len(),
+        bytes: json_size,
+        generation_secs: json_generation_time.as_secs_f64(),
+        skipped: skipped.iter().map(|file| file.name.clone()).collect(),
+        sampled_from: subset.map(|_| full_files),
+        stats: Some(corpus_stats),
+    });
+    results.stages = vec![
+        results::Stage::new("gzip_compress", "gzip-6", json_size, individual_gz_size, gzip_compression_time)
+            .with_objects(names.len() as u64)
+            .with_sync(gzip_sync_time)
+            .with_latency(latency::injected(gzip_compress_trips))
+            .with_digest(gzip_digest.clone()),
+        results::Stage::new("gzip_decompress", "gzip-6", individual_gz_size, json_size, gzip_decompression_time)
+            .with_latency(latency::injected(gzip_decompress_trips)),
+        results::Stage::new("zstd_compress", "zstd-3", json_size, zstd_size, zstd_compression_time)
+            .with_sync(zstd_sync_time)
+            .with_latency(latency::injected(zstd_compress_trips))
+            .with_threads(args.zstd_workers)
+            .with_digest(zstd_digest.clone())
+            .with_objects(1),
+    ];
+    if let Some(time) = zstd_decompression_time {
+        results.stages.push(
+            results::Stage::new("zstd_decompress", "zstd-3", zstd_size, json_size, time).with_latency(latency::injected(zstd_decompress_trips)),
+        );
+    }
+    if let Some(sink_time) = gzip_sink_time {
+        results.stages.push(results::Stage::new("gzip_decompress_sink", "gzip-6", individual_gz_size, json_size, sink_time));
+    }
+    for result in pipeline_results.iter().flatten() {
+        results.stages.push(results::Stage::new("pipeline", &result.codec, result.compressed_bytes, json_size, result.total()));
+    }
+    results.matrix = matrix_cells;
+    results.timeouts = watchdog.timeouts;
+    results.failures = watchdog.failures;
+    results.sweep = sweeps.as_deref().map_or_else(Vec::new, |sweeps| sweep::cells(sweeps, json_size));
+    results.dedup = args.dedup.then_some(dedup);
+    results.scatter = scatter_points;
+    results.files = file_measurements;
+    results.partial = interrupt::requested();
+    results.object_overhead = args.object_overhead.map(|overhead| overhead as u64);
+    if args.chart {
+        chart::print(&results);
+    }
+    if !references.is_empty() {
+        println!();
+        println!("🖥️  Reference machines (--reference):");
+        reference::print(&results, &references);
+    }
+    if subset.is_some() {
+        // Generated files all have about the same size, so scale by file count when the full size is unknown.
+        let factor = match full_size {
+            Some(full_size) => full_size as f64 / json_size as f64,
+            None => full_files as f64 / names.len() as f64,
+        };
+        println!();
+        println!("📐 Extrapolated to the full corpus:");
+        sample::print_extrapolated(&results, factor, full_files);
+    }
+    
+    if let Some(path) = &args.json {
+        let written = results.write(path)?;
+        println!("\n💾 Results written to {} ({})", path.display(), written);
+    }
+    #[cfg(feature = "parquet")]
+    if let Some(path) = &args.output_parquet {
+        let rows = results_parquet::write(path, &results)?;
+        println!("\n💾 {} measurements written to {}", rows, path.display());
+    }
+    if let Some(path) = &args.summary {
+        summary::write(path, &results, baseline.as_ref())?;
+        if !references.is_empty() {
+            reference::append_summary(path, &results, &references)?;
+        }
+        println!("\n📝 Summary written to {}", path.display());
+    }
+    if let Some(path) = &args.bundle {
+        bundle::write(path, bundle::effective_args(std::env::args().skip(1), seed), seed, &results)?;
+        println!("\n📦 Run bundle written to {}", path.display());
+    }
     
-    Ok(())
+    if results.partial {
+        println!("\n🛑 Comparison stopped early by Ctrl-C; the results above are partial");
+    } else {
+        println!("\n✅ Compression comparison complete!");
+    }
+    
+    Ok(Some(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_size;
+
+    #[test]
+    fn parse_size_reads_decimal_units_as_powers_of_1000() {
+        assert_eq!(parse_size("64KB"), Ok(64_000));
+        assert_eq!(parse_size("4MB"), Ok(4_000_000));
+        assert_eq!(parse_size("2GB"), Ok(2_000_000_000));
+        assert_eq!(parse_size("1kb"), Ok(1_000));
+    }
+
+    #[test]
+    fn parse_size_reads_binary_units_as_powers_of_1024() {
+        assert_eq!(parse_size("64KiB"), Ok(64 << 10));
+        assert_eq!(parse_size("4MiB"), Ok(4 << 20));
+        assert_eq!(parse_size("2GiB"), Ok(2 << 30));
+        assert_eq!(parse_size("112K"), Ok(112 << 10));
+        assert_eq!(parse_size("4m"), Ok(4 << 20));
+    }
+
+    #[test]
+    fn parse_size_rejects_unknown_units_and_overflow() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert!(parse_size("4TB").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size(&format!("{}GiB", usize::MAX)).is_err());
+    }
 }
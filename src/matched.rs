@@ -0,0 +1,70 @@
+//! Matched-speed comparison: pick each codec's level whose compression throughput
+//! is closest to a reference codec, then compare ratios at equal CPU cost.
+
+use anyhow::Result;
+use crate::bench::{measure, throughput, Measurement};
+use crate::codec::{CodecSpec, Family};
+use crate::render::{Align, Table};
+use crate::{progress_bar, render};
+
+pub struct Matched {
+    pub reference: (CodecSpec, Measurement),
+    pub matches: Vec<(CodecSpec, Measurement)>,
+}
+
+pub fn run(reference: CodecSpec, files: &[Vec<u8>], json_size: u64) -> Result<Matched> {
+    let others: Vec<Family> = Family::ALL.iter().copied().filter(|&f| f != reference.family).collect();
+    let total = 1 + others.iter().map(|f| f.levels().count()).sum::<usize>();
+    let pb = progress_bar(total);
+
+    let reference_measurement = measure(reference.build().as_ref(), files)?;
+    let target = throughput(json_size, reference_measurement.compress_time);
+    pb.inc(1);
+
+    let mut matches = Vec::with_capacity(others.len());
+    for family in others {
+        let mut best: Option<(CodecSpec, Measurement, f64)> = None;
+        for level in family.levels() {
+            let spec = CodecSpec { family, level };
+            let measurement = measure(spec.build().as_ref(), files)?;
+            // Compare on a log scale so 2x faster and 2x slower are equally far away.
+            let distance = (throughput(json_size, measurement.compress_time) / target).ln().abs();
+            if best.as_ref().is_none_or(|(_, _, d)| distance < *d) {
+                best = Some((spec, measurement, distance));
+            }
+            pb.inc(1);
+        }
+        if let Some((spec, measurement, _)) = best {
+            matches.push((spec, measurement));
+        }
+    }
+    pb.finish_with_message("Matched-speed sweep complete!");
+
+    Ok(Matched { reference: (reference, reference_measurement), matches })
+}
+
+pub fn print(matched: &Matched, json_size: u64) {
+    let (reference, reference_measurement) = &matched.reference;
+    let mut table = Table::new(&[
+        ("Codec", Align::Left),
+        ("Compress", Align::Right),
+        ("Decompress", Align::Right),
+        ("Ratio", Align::Right),
+        ("vs reference", Align::Right),
+    ]);
+    let mut row = |spec: &CodecSpec, m: &Measurement, delta: String| {
+        table.row(vec![
+            spec.build().name(),
+            render::mb_per_sec(throughput(json_size, m.compress_time)),
+            render::mb_per_sec(throughput(json_size, m.decompress_time)),
+            render::percent(m.compressed_size as f64 / json_size as f64),
+            delta,
+        ]);
+    };
+    row(reference, reference_measurement, "reference".to_string());
+    for (spec, measurement) in &matched.matches {
+        let delta = render::change(measurement.compressed_size as f64, reference_measurement.compressed_size as f64);
+        row(spec, measurement, format!("{} size", delta));
+    }
+    print!("{}", table.terminal());
+}
@@ -0,0 +1,62 @@
+//! Full codec × level matrix over one corpus, reported as a single table.
+
+use anyhow::Result;
+use std::sync::Arc;
+use crate::codec::Family;
+use crate::interrupt;
+use crate::render::{self, Align, Table};
+use crate::results::MatrixCell;
+use crate::sweep::{self, SweepOptions};
+use crate::watchdog::Watchdog;
+use crate::{format_bytes, progress_bar};
+
+/// Measures every configuration of every enabled codec family, leaving out any that
+/// `watchdog` gives up on.
+pub fn run(options: &SweepOptions, files: &Arc<Vec<Vec<u8>>>, json_size: u64, watchdog: &mut Watchdog) -> Result<Vec<MatrixCell>> {
+    let plan: Vec<_> = Family::ALL
+        .iter()
+        .flat_map(|&family| sweep::codecs(family, options).into_iter().map(move |codec| (family, codec)))
+        .collect();
+    let pb = progress_bar(plan.len());
+
+    let mut cells = Vec::with_capacity(plan.len());
+    for (family, codec) in plan {
+        if interrupt::requested() {
+            break;
+        }
+        let name = codec.name();
+        pb.set_message(name.clone());
+        if let Some(measurement) = watchdog.measure("matrix", codec, files, &pb)? {
+            cells.push(MatrixCell::new(&family.to_string(), name, &measurement, json_size));
+        }
+        pb.inc(1);
+    }
+    interrupt::finish(&pb, "Codec matrix complete!");
+
+    Ok(cells)
+}
+
+pub fn print(cells: &[MatrixCell]) {
+    let memory = |bytes: Option<u64>| bytes.map(format_bytes).unwrap_or_else(|| "-".to_string());
+    let mut table = Table::new(&[
+        ("Codec", Align::Left),
+        ("Size", Align::Right),
+        ("Ratio", Align::Right),
+        ("Compress", Align::Right),
+        ("Decompress", Align::Right),
+        ("Enc memory", Align::Right),
+        ("Dec memory", Align::Right),
+    ]);
+    for cell in cells {
+        table.row(vec![
+            cell.codec.clone(),
+            format_bytes(cell.compressed_bytes),
+            render::percent(cell.ratio),
+            render::seconds(cell.compress_secs),
+            render::seconds(cell.decompress_secs),
+            memory(cell.compress_memory),
+            memory(cell.decompress_memory),
+        ]);
+    }
+    print!("{}", table.terminal());
+}
@@ -0,0 +1,215 @@
+//! `--minify-keys`: how much shortening JSON keys, e.g. `response_time_ms` to
+//! `rtm`, still saves once the logs are compressed.
+//!
+//! Every key in the corpus, nested ones included, is renamed to the initials of
+//! its words, with a number appended where two keys would clash; the most
+//! frequent keys claim the bare initials first. Files are re-serialized in the
+//! same style on both sides (pretty-printed for one document, one compact value
+//! per line otherwise), so only the keys differ. Files that are not JSON are
+//! kept as they are on both sides.
+
+use std::collections::{HashMap, HashSet};
+use anyhow::{ensure, Result};
+use serde_json::{Deserializer, Map, Value};
+use crate::bench::measure;
+use crate::codec::{Codec, Gzip, Zstd};
+use crate::render::{self, Align, Table};
+use crate::{format_bytes, progress_bar};
+
+/// Keys listed in the renaming table, the ones saving the most raw bytes.
+const LISTED_KEYS: usize = 10;
+
+pub struct RenamedKey {
+    pub name: String,
+    pub short: String,
+    pub occurrences: u64,
+}
+
+impl RenamedKey {
+    /// Uncompressed bytes the renaming saves over the whole corpus.
+    pub fn saved(&self) -> i64 {
+        (self.name.len() as i64 - self.short.len() as i64) * self.occurrences as i64
+    }
+}
+
+/// One way of storing the corpus, with and without minified keys.
+pub struct Row {
+    pub label: String,
+    pub original: u64,
+    pub minified: u64,
+}
+
+pub struct Minified {
+    pub keys: Vec<RenamedKey>,
+    pub rows: Vec<Row>,
+}
+
+/// Every JSON value of a file, or `None` when the file is not JSON.
+fn parse(file: &[u8]) -> Option<Vec<Value>> {
+    Deserializer::from_slice(file).into_iter::<Value>().collect::<serde_json::Result<_>>().ok()
+}
+
+fn serialize(values: &[Value]) -> Result<Vec<u8>> {
+    if let [value] = values {
+        return Ok(serde_json::to_vec_pretty(value)?);
+    }
+    let mut out = Vec::new();
+    for value in values {
+        serde_json::to_writer(&mut out, value)?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+fn count_keys(value: &Value, counts: &mut HashMap<String, u64>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                *counts.entry(key.clone()).or_default() += 1;
+                count_keys(value, counts);
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|value| count_keys(value, counts)),
+        _ => {}
+    }
+}
+
+/// Lowercase initials of the words in `key`, split on `_`, `-`, `.` and camelCase humps.
+fn initials(key: &str) -> String {
+    let mut out = String::new();
+    let mut previous: Option<char> = None;
+    for c in key.chars() {
+        let starts_word = match previous {
+            None => true,
+            Some(previous) => matches!(previous, '_' | '-' | '.') || (previous.is_lowercase() && c.is_uppercase()),
+        };
+        if starts_word && c.is_alphanumeric() {
+            out.extend(c.to_lowercase());
+        }
+        previous = Some(c);
+    }
+    out
+}
+
+/// Short names for every key, most frequent first, never shared by two keys.
+fn shorten(counts: HashMap<String, u64>) -> Vec<RenamedKey> {
+    let mut keys: Vec<(String, u64)> = counts.into_iter().collect();
+    keys.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let mut taken = HashSet::new();
+    keys.into_iter()
+        .map(|(name, occurrences)| {
+            let mut base = initials(&name);
+            if base.is_empty() || base.len() >= name.len() {
+                base = name.clone();
+            }
+            let mut short = base.clone();
+            let mut suffix = 2;
+            while !taken.insert(short.clone()) {
+                short = format!("{}{}", base, suffix);
+                suffix += 1;
+            }
+            RenamedKey { name, short, occurrences }
+        })
+        .collect()
+}
+
+fn rename(value: Value, names: &HashMap<&str, &str>) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (names.get(key.as_str()).map_or(key, |short| short.to_string()), rename(value, names)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(|value| rename(value, names)).collect()),
+        value => value,
+    }
+}
+
+/// Per-file gzip and zstd, and one solid zstd stream, of the corpus before and after renaming its keys.
+pub fn run(files: &[Vec<u8>]) -> Result<Minified> {
+    let parsed: Vec<Option<Vec<Value>>> = files.iter().map(|file| parse(file)).collect();
+    let mut counts = HashMap::new();
+    parsed.iter().flatten().flatten().for_each(|value| count_keys(value, &mut counts));
+    let mut keys = shorten(counts);
+    let names: HashMap<&str, &str> = keys.iter().map(|key| (key.name.as_str(), key.short.as_str())).collect();
+    let expand: HashMap<&str, &str> = keys.iter().map(|key| (key.short.as_str(), key.name.as_str())).collect();
+
+    let mut original = Vec::with_capacity(files.len());
+    let mut minified = Vec::with_capacity(files.len());
+    for (file, values) in files.iter().zip(parsed) {
+        let Some(values) = values else {
+            original.push(file.clone());
+            minified.push(file.clone());
+            continue;
+        };
+        let renamed: Vec<Value> = values.iter().cloned().map(|value| rename(value, &names)).collect();
+        // Renaming is only worth measuring if it can be undone.
+        let restored: Vec<Value> = renamed.iter().cloned().map(|value| rename(value, &expand)).collect();
+        ensure!(restored == values, "minified keys do not map back to the original keys");
+        original.push(serialize(&values)?);
+        minified.push(serialize(&renamed)?);
+    }
+
+    let size = |files: &[Vec<u8>]| files.iter().map(|file| file.len() as u64).sum();
+    let mut rows = vec![Row { label: "raw JSON".to_string(), original: size(&original), minified: size(&minified) }];
+    let codecs: [(Box<dyn Codec>, bool); 3] =
+        [(Box::new(Gzip { level: 6 }), false), (Box::new(Zstd { level: 3 }), false), (Box::new(Zstd { level: 3 }), true)];
+    let pb = progress_bar(codecs.len());
+    for (codec, solid) in codecs {
+        pb.set_message(codec.name());
+        let (before, after) = if solid {
+            (measure(codec.as_ref(), &[original.concat()])?, measure(codec.as_ref(), &[minified.concat()])?)
+        } else {
+            (measure(codec.as_ref(), &original)?, measure(codec.as_ref(), &minified)?)
+        };
+        let label = format!("{} {}", if solid { "solid" } else { "per-file" }, codec.name());
+        rows.push(Row { label, original: before.compressed_size, minified: after.compressed_size });
+        pb.inc(1);
+    }
+    pb.finish_with_message("Key minification comparison complete!");
+
+    keys.sort_by(|a, b| b.saved().cmp(&a.saved()).then_with(|| a.name.cmp(&b.name)));
+    keys.retain(|key| key.short != key.name);
+    Ok(Minified { keys, rows })
+}
+
+pub fn print(minified: &Minified) {
+    let mut renamed = Table::new(&[
+        ("Key", Align::Left),
+        ("Short", Align::Left),
+        ("Occurrences", Align::Right),
+        ("Raw bytes saved", Align::Right),
+    ]);
+    for key in minified.keys.iter().take(LISTED_KEYS) {
+        renamed.row(vec![key.name.clone(), key.short.clone(), key.occurrences.to_string(), signed(key.saved())]);
+    }
+    print!("{}", renamed.terminal());
+    if minified.keys.len() > LISTED_KEYS {
+        println!("  … and {} more keys renamed", minified.keys.len() - LISTED_KEYS);
+    }
+    println!();
+
+    let mut sizes = Table::new(&[
+        ("Storage", Align::Left),
+        ("Original keys", Align::Right),
+        ("Minified keys", Align::Right),
+        ("Saved", Align::Right),
+        ("Saved %", Align::Right),
+    ]);
+    for row in &minified.rows {
+        let saved = row.original as i64 - row.minified as i64;
+        sizes.row(vec![
+            row.label.clone(),
+            format_bytes(row.original),
+            format_bytes(row.minified),
+            signed(saved),
+            render::percent(saved as f64 / row.original as f64),
+        ]);
+    }
+    print!("{}", sizes.terminal());
+}
+
+fn signed(bytes: i64) -> String {
+    let sign = if bytes < 0 { "-" } else { "" };
+    format!("{}{}", sign, format_bytes(bytes.unsigned_abs()))
+}
@@ -0,0 +1,170 @@
+//! `--mixed`: compression and decompression running at the same time, as on a
+//! log node that compresses new data while serving reads of older data.
+//!
+//! For every codec, one worker compresses alone and one decompresses alone to
+//! get the uncontended throughput. Then each `--mixed-ratios` mix runs its
+//! compressors and decompressors concurrently for `--mixed-duration`, each
+//! worker cycling through the corpus in memory. The slowdown of the average
+//! worker against its solo throughput is what the contention costs.
+
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::{ensure, Result};
+use crate::bench::throughput;
+use crate::codec::CodecSpec;
+use crate::render::{Align, Table};
+use crate::{progress_bar, render};
+
+/// Workers of each direction running at once, e.g. `1:3` for one compressor and three decompressors.
+#[derive(Clone, Copy, Debug)]
+pub struct Mix {
+    pub compressors: usize,
+    pub decompressors: usize,
+}
+
+impl FromStr for Mix {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((compressors, decompressors)) = s.split_once(':') else {
+            anyhow::bail!("expected COMPRESSORS:DECOMPRESSORS, e.g. `1:3`, got `{}`", s);
+        };
+        let mix = Mix { compressors: compressors.trim().parse()?, decompressors: decompressors.trim().parse()? };
+        ensure!(mix.compressors > 0 && mix.decompressors > 0, "a mix needs at least one worker in each direction, got `{}`", s);
+        Ok(mix)
+    }
+}
+
+impl fmt::Display for Mix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.compressors, self.decompressors)
+    }
+}
+
+/// Average per-worker throughput of each direction in one run, in MB/s.
+#[derive(Clone, Copy, Default)]
+pub struct Rates {
+    pub compress: f64,
+    pub decompress: f64,
+}
+
+pub struct MixedResult {
+    pub codec: String,
+    pub solo: Rates,
+    pub mixes: Vec<(Mix, Rates)>,
+}
+
+/// Runs `compressors` and `decompressors` workers together for `duration` and averages their throughput.
+fn drive(spec: CodecSpec, files: &[Vec<u8>], frames: &[Vec<u8>], compressors: usize, decompressors: usize, duration: Duration) -> Result<Rates> {
+    let stop = AtomicBool::new(false);
+    // Each worker starts at a different file so they do not all hit the same one at once.
+    let stride = files.len() / (compressors + decompressors).max(1);
+    let (compressed, decompressed) = thread::scope(|scope| {
+        let compress: Vec<_> = (0..compressors)
+            .map(|worker| {
+                let stop = &stop;
+                scope.spawn(move || -> Result<f64> {
+                    let codec = spec.build();
+                    let (start, mut bytes, mut i) = (Instant::now(), 0, worker * stride);
+                    while !stop.load(Ordering::Relaxed) {
+                        let file = &files[i % files.len()];
+                        codec.compress_bytes(file)?;
+                        bytes += file.len() as u64;
+                        i += 1;
+                    }
+                    Ok(throughput(bytes, start.elapsed()))
+                })
+            })
+            .collect();
+        let decompress: Vec<_> = (0..decompressors)
+            .map(|worker| {
+                let stop = &stop;
+                scope.spawn(move || -> Result<f64> {
+                    let codec = spec.build();
+                    let (start, mut bytes, mut i) = (Instant::now(), 0, (compressors + worker) * stride);
+                    let mut output = Vec::new();
+                    while !stop.load(Ordering::Relaxed) {
+                        let mut frame = frames[i % frames.len()].as_slice();
+                        output.clear();
+                        bytes += codec.decoder(&mut frame)?.read_to_end(&mut output)? as u64;
+                        i += 1;
+                    }
+                    Ok(throughput(bytes, start.elapsed()))
+                })
+            })
+            .collect();
+        thread::sleep(duration);
+        stop.store(true, Ordering::Relaxed);
+        let join = |handles: Vec<thread::ScopedJoinHandle<Result<f64>>>| -> Result<Vec<f64>> {
+            handles.into_iter().map(|handle| handle.join().expect("mixed-workload worker panicked")).collect()
+        };
+        Ok::<_, anyhow::Error>((join(compress)?, join(decompress)?))
+    })?;
+    let average = |rates: &[f64]| if rates.is_empty() { 0.0 } else { rates.iter().sum::<f64>() / rates.len() as f64 };
+    Ok(Rates { compress: average(&compressed), decompress: average(&decompressed) })
+}
+
+/// Measures every codec alone in each direction and then under every mix.
+pub fn run(specs: &[CodecSpec], mixes: &[Mix], duration: Duration, files: &[Vec<u8>]) -> Result<Vec<MixedResult>> {
+    ensure!(!files.is_empty(), "the corpus is empty");
+    let pb = progress_bar(specs.len() * (2 + mixes.len()));
+    let mut results = Vec::with_capacity(specs.len());
+    for &spec in specs {
+        let codec = spec.build();
+        pb.set_message(codec.name());
+        let frames = files.iter().map(|file| codec.compress_bytes(file)).collect::<Result<Vec<_>>>()?;
+
+        let compress = drive(spec, files, &frames, 1, 0, duration)?.compress;
+        pb.inc(1);
+        let decompress = drive(spec, files, &frames, 0, 1, duration)?.decompress;
+        pb.inc(1);
+        let mut result = MixedResult { codec: codec.name(), solo: Rates { compress, decompress }, mixes: Vec::new() };
+        for &mix in mixes {
+            result.mixes.push((mix, drive(spec, files, &frames, mix.compressors, mix.decompressors, duration)?));
+            pb.inc(1);
+        }
+        results.push(result);
+    }
+    pb.finish_with_message("Mixed workload complete!");
+    Ok(results)
+}
+
+pub fn print(results: &[MixedResult]) {
+    let mut table = Table::new(&[
+        ("Codec", Align::Left),
+        ("Mix", Align::Left),
+        ("Compress/worker", Align::Right),
+        ("vs solo", Align::Right),
+        ("Decomp./worker", Align::Right),
+        ("vs solo", Align::Right),
+    ]);
+    for result in results {
+        table.row(vec![
+            result.codec.clone(),
+            "solo".to_string(),
+            render::mb_per_sec(result.solo.compress),
+            String::new(),
+            render::mb_per_sec(result.solo.decompress),
+        ]);
+        for (mix, rates) in &result.mixes {
+            table.row(vec![
+                String::new(),
+                mix.to_string(),
+                render::mb_per_sec(rates.compress),
+                render::change(rates.compress, result.solo.compress),
+                render::mb_per_sec(rates.decompress),
+                render::change(rates.decompress, result.solo.decompress),
+            ]);
+        }
+    }
+    print!("{}", table.terminal());
+    let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+    let widest = results.iter().flat_map(|result| &result.mixes).map(|(mix, _)| mix.compressors + mix.decompressors).max().unwrap_or(0);
+    if widest > cpus {
+        println!("  ⚠️  Mixes of up to {} workers on {} CPUs: part of the slowdown is time-slicing, not contention", widest, cpus);
+    }
+}
@@ -0,0 +1,84 @@
+//! File names of the generated corpus, from a `--name-template` such as
+//! `{prefix}_{index:06}.{ext}`.
+
+use std::path::{Component, Path};
+use std::str::FromStr;
+use anyhow::{bail, Context, Result};
+
+pub const DEFAULT_TEMPLATE: &str = "{prefix}_{index:04}.{ext}";
+
+/// Extension of every generated file; the corpus is always JSON.
+const EXTENSION: &str = "json";
+
+#[derive(Clone, Debug)]
+enum Part {
+    Literal(String),
+    Prefix,
+    Extension,
+    /// The file's index, zero padded to this many digits.
+    Index(usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct NameTemplate {
+    parts: Vec<Part>,
+}
+
+impl FromStr for NameTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                parts.push(Part::Literal(rest[..open].to_string()));
+            }
+            let close = rest[open..].find('}').map(|close| open + close).context("unclosed `{` in name template")?;
+            parts.push(match &rest[open + 1..close] {
+                "prefix" => Part::Prefix,
+                "ext" => Part::Extension,
+                "index" => Part::Index(0),
+                placeholder => match placeholder.strip_prefix("index:") {
+                    Some(width) => Part::Index(width.parse().with_context(|| format!("invalid index width `{}`", width))?),
+                    None => bail!("unknown placeholder `{{{}}}`, expected {{prefix}}, {{index}}, {{index:WIDTH}} or {{ext}}", placeholder),
+                },
+            });
+            rest = &rest[close + 1..];
+        }
+        if rest.contains('}') {
+            bail!("unmatched `}}` in name template");
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        if !parts.iter().any(|part| matches!(part, Part::Index(_))) {
+            bail!("name template must contain {{index}} so every file gets its own name");
+        }
+        Ok(NameTemplate { parts })
+    }
+}
+
+impl NameTemplate {
+    fn render(&self, prefix: &str, index: usize) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Prefix => prefix.to_string(),
+                Part::Extension => EXTENSION.to_string(),
+                Part::Index(width) => format!("{:0width$}", index, width = *width),
+            })
+            .collect()
+    }
+
+    /// The names of `count` generated files, in generation order.
+    pub fn names(&self, prefix: &str, count: usize) -> Result<Vec<String>> {
+        // Names are joined onto the workspace, so they must stay inside it.
+        let sample = self.render(prefix, 0);
+        if !Path::new(&sample).components().all(|component| matches!(component, Component::Normal(_))) {
+            bail!("--name-template and --name-prefix must produce relative paths without `..`, got {:?}", sample);
+        }
+        Ok((0..count).map(|index| self.render(prefix, index)).collect())
+    }
+}
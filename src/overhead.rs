@@ -0,0 +1,41 @@
+//! `--object-overhead`: a fixed number of bytes counted for every stored object
+//! on top of its compressed payload, such as the HTTP request and response
+//! headers of each PUT or GET on object storage, or a filesystem's inode and
+//! block slack. 10,000 small gzip objects pay it 10,000 times; one archive pays
+//! it once, which payload bytes alone do not show.
+
+use crate::format_bytes;
+use crate::render::{Align, Table};
+
+/// How one strategy's output is stored.
+pub struct Stored {
+    pub label: String,
+    pub objects: u64,
+    pub payload: u64,
+}
+
+impl Stored {
+    pub fn effective(&self, overhead: u64) -> u64 {
+        self.payload + self.objects * overhead
+    }
+}
+
+pub fn print(rows: &[Stored], overhead: u64) {
+    let mut table = Table::new(&[
+        ("Strategy", Align::Left),
+        ("Objects", Align::Right),
+        ("Payload", Align::Right),
+        ("Overhead", Align::Right),
+        ("Effective", Align::Right),
+    ]);
+    for row in rows {
+        table.row(vec![
+            row.label.clone(),
+            row.objects.to_string(),
+            format_bytes(row.payload),
+            format_bytes(row.objects * overhead),
+            format_bytes(row.effective(overhead)),
+        ]);
+    }
+    print!("{}", table.terminal());
+}
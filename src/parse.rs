@@ -0,0 +1,142 @@
+//! Decompress-and-parse benchmark: the end-to-end cost of getting from a
+//! compressed log back to parsed JSON values, per codec and per parser.
+
+use std::io::Read;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use crate::bench::throughput;
+use crate::codec::{Codec, CodecSpec};
+use crate::render::{Align, Table};
+use crate::{progress_bar, render};
+
+/// How a file's JSON is laid out; generated files are single documents, real logs are often NDJSON.
+#[derive(Clone, Copy)]
+pub enum Layout {
+    Document,
+    Lines,
+}
+
+impl Layout {
+    pub fn detect(file: &[u8]) -> Layout {
+        if serde_json::from_slice::<serde::de::IgnoredAny>(file).is_ok() { Layout::Document } else { Layout::Lines }
+    }
+}
+
+pub fn is_blank(line: &[u8]) -> bool {
+    line.iter().all(u8::is_ascii_whitespace)
+}
+
+fn parse_serde_json(data: &[u8], layout: Layout) -> Result<()> {
+    match layout {
+        Layout::Document => {
+            serde_json::from_slice::<serde_json::Value>(data)?;
+        }
+        Layout::Lines => {
+            for line in data.split(|&b| b == b'\n').filter(|line| !is_blank(line)) {
+                serde_json::from_slice::<serde_json::Value>(line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// simd-json parses in place, so it takes the decompressed buffer mutably.
+#[cfg(feature = "simd-json")]
+fn parse_simd_json(data: &mut [u8], layout: Layout) -> Result<()> {
+    match layout {
+        Layout::Document => {
+            simd_json::to_owned_value(data)?;
+        }
+        Layout::Lines => {
+            for line in data.split_mut(|&b| b == b'\n').filter(|line| !is_blank(line)) {
+                simd_json::to_owned_value(line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn decompress(codec: &dyn Codec, frame: &[u8], capacity: usize) -> Result<Vec<u8>> {
+    let mut input = frame;
+    let mut output = Vec::with_capacity(capacity);
+    codec.decoder(&mut input)?.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+pub struct ParseResult {
+    pub spec: CodecSpec,
+    pub decompress: Duration,
+    pub serde_json: Duration,
+    #[cfg(feature = "simd-json")]
+    pub simd_json: Duration,
+}
+
+/// Times decompression alone and decompression followed by each parser, file by file.
+pub fn run(specs: &[CodecSpec], names: &[String], files: &[Vec<u8>]) -> Result<Vec<ParseResult>> {
+    let layouts: Vec<Layout> = files.iter().map(|file| Layout::detect(file)).collect();
+    let passes = if cfg!(feature = "simd-json") { 4 } else { 3 };
+    let pb = progress_bar(specs.len() * passes);
+    let mut results = Vec::with_capacity(specs.len());
+
+    for &spec in specs {
+        let codec = spec.build();
+        let frames = files.iter().map(|file| codec.compress_bytes(file)).collect::<Result<Vec<_>>>()?;
+        pb.inc(1);
+
+        let start = Instant::now();
+        for (frame, file) in frames.iter().zip(files) {
+            decompress(codec.as_ref(), frame, file.len())?;
+        }
+        let decompress_time = start.elapsed();
+        pb.inc(1);
+
+        let start = Instant::now();
+        for (i, (frame, file)) in frames.iter().zip(files).enumerate() {
+            let data = decompress(codec.as_ref(), frame, file.len())?;
+            parse_serde_json(&data, layouts[i]).with_context(|| format!("serde_json could not parse {}", names[i]))?;
+        }
+        let serde_json_time = start.elapsed();
+        pb.inc(1);
+
+        #[cfg(feature = "simd-json")]
+        let simd_json_time = {
+            let start = Instant::now();
+            for (i, (frame, file)) in frames.iter().zip(files).enumerate() {
+                let mut data = decompress(codec.as_ref(), frame, file.len())?;
+                parse_simd_json(&mut data, layouts[i]).with_context(|| format!("simd-json could not parse {}", names[i]))?;
+            }
+            pb.inc(1);
+            start.elapsed()
+        };
+
+        results.push(ParseResult {
+            spec,
+            decompress: decompress_time,
+            serde_json: serde_json_time,
+            #[cfg(feature = "simd-json")]
+            simd_json: simd_json_time,
+        });
+    }
+    pb.finish_with_message("Decompress-and-parse benchmark complete!");
+
+    Ok(results)
+}
+
+pub fn print(results: &[ParseResult], json_size: u64) {
+    let cell = |time: Duration| format!("{} ({})", render::duration(time), render::mb_per_sec(throughput(json_size, time)));
+    let mut columns = vec![("Codec", Align::Left), ("Decompress", Align::Right), ("+ serde_json", Align::Right)];
+    if cfg!(feature = "simd-json") {
+        columns.push(("+ simd-json", Align::Right));
+    }
+    let mut table = Table::new(&columns);
+    for result in results {
+        #[allow(unused_mut)]
+        let mut row = vec![result.spec.build().name(), cell(result.decompress), cell(result.serde_json)];
+        #[cfg(feature = "simd-json")]
+        row.push(cell(result.simd_json));
+        table.row(row);
+    }
+    print!("{}", table.terminal());
+    #[cfg(not(feature = "simd-json"))]
+    println!("  (build with --features simd-json to include simd-json)");
+}
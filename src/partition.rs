@@ -0,0 +1,120 @@
+//! Time-partitioned archives: one solid archive per time bucket, named after the
+//! bucket, so time-bounded extraction only opens the partitions it needs.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use clap::ValueEnum;
+use indicatif::ProgressBar;
+use crate::workspace::output_dir;
+use crate::{archive, format_bytes, render};
+
+/// Bucket for entries without a usable `timestamp`.
+const UNKNOWN_BUCKET: &str = "unknown";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PartitionBy {
+    Hour,
+    Day,
+}
+
+impl fmt::Display for PartitionBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        f.write_str(value.get_name())
+    }
+}
+
+impl PartitionBy {
+    fn bucket(self, timestamp: DateTime<Utc>) -> String {
+        match self {
+            PartitionBy::Hour => timestamp.format("%Y-%m-%dT%H").to_string(),
+            PartitionBy::Day => timestamp.format("%Y-%m-%d").to_string(),
+        }
+    }
+}
+
+/// Time range covered by a partition named `bucket`, whichever granularity wrote it.
+fn bucket_range(bucket: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    if let Ok(start) = NaiveDateTime::parse_from_str(&format!("{}:00:00", bucket), "%Y-%m-%dT%H:%M:%S") {
+        let start = start.and_utc();
+        return Some((start, start + Duration::hours(1)));
+    }
+    let start = NaiveDate::parse_from_str(bucket, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0)?.and_utc();
+    Some((start, start + Duration::days(1)))
+}
+
+/// Timestamp of the first JSON record in `content`, so NDJSON files work too.
+pub fn timestamp_of(content: &[u8]) -> Option<DateTime<Utc>> {
+    let first = serde_json::Deserializer::from_slice(content).into_iter::<serde_json::Value>().next()?.ok()?;
+    let timestamp = first.get("timestamp")?.as_str()?;
+    Some(DateTime::parse_from_rfc3339(timestamp).ok()?.with_timezone(&Utc))
+}
+
+pub struct Partitioned {
+    pub by: PartitionBy,
+    /// Bucket name, entries and archive size.
+    pub partitions: Vec<(String, usize, u64)>,
+}
+
+/// Writes one archive per bucket of `names` into `dir` as `<bucket>.zst`.
+pub fn write(names: &[String], by: PartitionBy, dir: &Path, level: i32, pb: &ProgressBar) -> Result<Partitioned> {
+    fs::create_dir_all(dir)?;
+    let mut buckets: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for name in names {
+        let content = fs::read(output_dir().join(name))?;
+        let bucket = timestamp_of(&content).map_or(UNKNOWN_BUCKET.to_string(), |timestamp| by.bucket(timestamp));
+        buckets.entry(bucket).or_default().push(name.clone());
+    }
+
+    let mut partitions = Vec::with_capacity(buckets.len());
+    for (bucket, members) in buckets {
+        let path = dir.join(format!("{}.zst", bucket));
+        archive::write_archive(output_dir(), &members, &[], &path, archive::Options::level(level), pb)?;
+        partitions.push((bucket, members.len(), fs::metadata(&path)?.len()));
+    }
+    Ok(Partitioned { by, partitions })
+}
+
+pub fn print(partitioned: &Partitioned, solid_size: u64, json_size: u64) {
+    let total: u64 = partitioned.partitions.iter().map(|(_, _, size)| size).sum();
+    let sizes = partitioned.partitions.iter().map(|(_, _, size)| *size);
+    println!(
+        "  {} partitions by {}, {} to {} each",
+        partitioned.partitions.len(),
+        partitioned.by,
+        format_bytes(sizes.clone().min().unwrap_or(0)),
+        format_bytes(sizes.max().unwrap_or(0))
+    );
+    println!(
+        "  Total size: {} ({} of the JSON, {} vs the single solid archive)",
+        format_bytes(total),
+        render::percent(total as f64 / json_size as f64),
+        render::change(total as f64, solid_size as f64)
+    );
+}
+
+/// Partitions in `dir` overlapping `[from, to)`, and how many partitions there are in total.
+pub fn relevant(dir: &Path, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<(Vec<PathBuf>, usize)> {
+    let mut partitions: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("failed to read partition directory {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zst"))
+        .collect();
+    partitions.sort();
+    let total = partitions.len();
+
+    let relevant = partitions
+        .into_iter()
+        .filter(|path| {
+            let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+            bucket_range(stem).is_some_and(|(start, end)| start < to && from < end)
+        })
+        .collect();
+    Ok((relevant, total))
+}
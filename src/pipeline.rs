@@ -0,0 +1,133 @@
+//! `--pipeline`: the full consumer path for each codec — decompress every
+//! artifact, deserialize every record into the typed log schema and validate
+//! it — timed per phase and end to end.
+//!
+//! `--parse` stops at a generic JSON value; here each record becomes a
+//! [`LogEntry`] and is checked, as a log consumer would. A decompressor that
+//! wins on its own can still lose end to end if the rest of the pipeline
+//! dominates, so the ranking is printed for both.
+
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use crate::bench::throughput;
+use crate::codec::CodecSpec;
+use crate::corpus::LogEntry;
+use crate::parse::{decompress, is_blank, Layout};
+use crate::render::{Align, Table};
+use crate::{progress_bar, render};
+
+pub struct PipelineResult {
+    pub codec: String,
+    pub compressed_bytes: u64,
+    pub decompress: Duration,
+    pub parse: Duration,
+    pub validate: Duration,
+    pub records: u64,
+    /// Records that did not deserialize into the schema or failed validation.
+    pub invalid: u64,
+    pub first_problem: Option<String>,
+}
+
+impl PipelineResult {
+    pub fn total(&self) -> Duration {
+        self.decompress + self.parse + self.validate
+    }
+
+    fn reject(&mut self, name: &str, problem: String) {
+        self.invalid += 1;
+        self.first_problem.get_or_insert_with(|| format!("{}: {}", name, problem));
+    }
+}
+
+/// Compresses every file with each codec, then times decompress, parse and validate file by file.
+pub fn run(specs: &[CodecSpec], names: &[String], files: &[Vec<u8>]) -> Result<Vec<PipelineResult>> {
+    let layouts: Vec<Layout> = files.iter().map(|file| Layout::detect(file)).collect();
+    let pb = progress_bar(specs.len() * files.len());
+    let mut results = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let codec = spec.build();
+        pb.set_message(codec.name());
+        let frames = files.iter().map(|file| codec.compress_bytes(file)).collect::<Result<Vec<_>>>()?;
+        let mut result = PipelineResult {
+            codec: codec.name(),
+            compressed_bytes: frames.iter().map(|frame| frame.len() as u64).sum(),
+            decompress: Duration::ZERO,
+            parse: Duration::ZERO,
+            validate: Duration::ZERO,
+            records: 0,
+            invalid: 0,
+            first_problem: None,
+        };
+
+        for (i, (frame, file)) in frames.iter().zip(files).enumerate() {
+            let start = Instant::now();
+            let data = decompress(codec.as_ref(), frame, file.len())?;
+            result.decompress += start.elapsed();
+
+            let start = Instant::now();
+            let mut entries = Vec::new();
+            match layouts[i] {
+                Layout::Document => entries.push(serde_json::from_slice::<LogEntry>(&data)),
+                Layout::Lines => entries.extend(
+                    data.split(|&b| b == b'\n').filter(|line| !is_blank(line)).map(serde_json::from_slice::<LogEntry>),
+                ),
+            }
+            result.parse += start.elapsed();
+
+            let start = Instant::now();
+            result.records += entries.len() as u64;
+            for entry in entries {
+                match entry {
+                    Ok(entry) => {
+                        if let Err(problem) = entry.validate() {
+                            result.reject(&names[i], problem);
+                        }
+                    }
+                    Err(err) => result.reject(&names[i], err.to_string()),
+                }
+            }
+            result.validate += start.elapsed();
+            pb.inc(1);
+        }
+        results.push(result);
+    }
+    pb.finish_with_message("End-to-end pipeline complete!");
+    Ok(results)
+}
+
+pub fn print(results: &[PipelineResult], json_size: u64) {
+    let cell = |time: Duration| format!("{} ({})", render::duration(time), render::mb_per_sec(throughput(json_size, time)));
+    let mut table = Table::new(&[
+        ("Codec", Align::Left),
+        ("Decompress", Align::Right),
+        ("Parse", Align::Right),
+        ("Validate", Align::Right),
+        ("Pipeline", Align::Right),
+        ("Invalid", Align::Right),
+    ]);
+    for result in results {
+        table.row(vec![
+            result.codec.clone(),
+            cell(result.decompress),
+            render::duration(result.parse),
+            render::duration(result.validate),
+            cell(result.total()),
+            format!("{}/{}", result.invalid, result.records),
+        ]);
+    }
+    print!("{}", table.terminal());
+    for result in results {
+        if let Some(problem) = &result.first_problem {
+            println!("  ⚠️  {}: {} invalid records, e.g. {}", result.codec, result.invalid, problem);
+        }
+    }
+
+    let fastest = |time: fn(&PipelineResult) -> Duration| results.iter().min_by_key(|result| time(result)).map(|result| &result.codec);
+    if let (Some(decompress), Some(pipeline)) = (fastest(|result| result.decompress), fastest(PipelineResult::total)) {
+        if decompress == pipeline {
+            println!("  {} is fastest both decompressing and end to end", pipeline);
+        } else {
+            println!("  {} decompresses fastest, but {} is fastest end to end", decompress, pipeline);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! `--dry-run`: prints what a run would do and roughly what it would cost,
+//! without generating, copying or compressing the corpus.
+//!
+//! Sizes and times are extrapolated from a small in-memory sample: a few
+//! generated documents, or a few files read from `--input`. Timings only cover
+//! codec CPU time, so disk-bound runs will take longer than estimated.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use crate::codec::{Codec, Gzip, Zstd};
+use crate::input::Source;
+use crate::{corpus, format_bytes, latency, render, RunArgs, NUM_FILES};
+
+const SAMPLE_FILES: usize = 100;
+
+/// Sizes of every regular file under `dir`, without following symlinked directories.
+pub fn file_sizes(dir: &Path) -> Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                pending.push(entry.path());
+            } else if let Ok(metadata) = fs::metadata(entry.path()) {
+                if metadata.is_file() {
+                    files.push((entry.path(), metadata.len()));
+                }
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+struct Corpus {
+    files: usize,
+    bytes: u64,
+    samples: Vec<Vec<u8>>,
+    /// Time to produce the sample, for generated corpora.
+    generation: Option<Duration>,
+}
+
+fn generated_corpus(files: usize) -> Result<Corpus> {
+    let count = files.min(SAMPLE_FILES);
+    let start = Instant::now();
+    let samples = corpus::generate(count)?;
+    let generation = start.elapsed();
+    let sample_bytes: u64 = samples.iter().map(|sample| sample.len() as u64).sum();
+    Ok(Corpus { files, bytes: sample_bytes * files as u64 / count.max(1) as u64, samples, generation: Some(generation) })
+}
+
+fn input_corpus(dir: &Path) -> Result<Corpus> {
+    let files = file_sizes(dir)?;
+    anyhow::ensure!(!files.is_empty(), "no files under {}", dir.display());
+    let step = files.len().div_ceil(SAMPLE_FILES);
+    let samples = files.iter().step_by(step).map(|(path, _)| Ok(fs::read(path)?)).collect::<Result<Vec<_>>>()?;
+    Ok(Corpus { files: files.len(), bytes: files.iter().map(|(_, size)| size).sum(), samples, generation: None })
+}
+
+/// Optional steps that `args` turns on; none of them are estimated.
+fn optional_steps(args: &RunArgs) -> Vec<&'static str> {
+    let mut steps = Vec::new();
+    for (enabled, step) in [
+        (args.null_sink, "--null-sink"),
+        (args.index, "--index"),
+        (args.partition_by.is_some(), "--partition-by"),
+        (args.reader_bench, "--reader-bench"),
+        (args.weights.is_some(), "--weights"),
+        (args.dict.is_some(), "--dict"),
+        (args.match_speed.is_some(), "--match-speed"),
+        (args.framings, "--framings"),
+        (args.zstd_frame_options, "--zstd-frame-options"),
+        (args.chunk_size.is_some(), "--chunk-size"),
+        (args.minify_keys, "--minify-keys"),
+        (!args.sweep.is_empty(), "--sweep"),
+        (args.matrix, "--matrix"),
+        (!args.scatter.is_empty(), "--scatter"),
+        (!args.parse.is_empty(), "--parse"),
+        (!args.pipeline.is_empty(), "--pipeline"),
+        (!args.mixed.is_empty(), "--mixed"),
+        (args.query.is_some(), "--query"),
+    ] {
+        if enabled {
+            steps.push(step);
+        }
+    }
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    if args.io_uring {
+        steps.push("--io-uring");
+    }
+    #[cfg(feature = "parquet")]
+    if !args.parquet.is_empty() {
+        steps.push("--parquet");
+    }
+    steps
+}
+
+/// Scales a time measured on `sample` bytes up to `total` bytes.
+fn scale(time: Duration, sample: u64, total: u64) -> Duration {
+    time.mul_f64(total as f64 / sample.max(1) as f64)
+}
+
+/// Fails if a report could not be written where `args` asks for it.
+fn check_outputs(args: &RunArgs) -> Result<()> {
+    for path in [&args.json, &args.summary].into_iter().flatten() {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            anyhow::ensure!(parent.is_dir(), "directory {} for {} does not exist", parent.display(), path.display());
+        }
+    }
+    Ok(())
+}
+
+pub fn print(args: &RunArgs) -> Result<()> {
+    println!("🧭 Dry run: nothing will be generated or compressed");
+    check_outputs(args)?;
+    let corpus = match &args.input {
+        None => generated_corpus(NUM_FILES)?,
+        Some(Source::Dir(dir)) => input_corpus(dir)?,
+        Some(source @ Source::S3 { .. }) => {
+            println!("\nInput: {}", source);
+            println!("  Object sizes are only known after listing the bucket, so nothing is estimated.");
+            return Ok(());
+        }
+        Some(source @ Source::Journal(_)) => {
+            println!("\nInput: {}", source);
+            println!("  The journal size is only known after exporting it, so nothing is estimated.");
+            return Ok(());
+        }
+    };
+    let sample_bytes: u64 = corpus.samples.iter().map(|sample| sample.len() as u64).sum();
+
+    // Calibrate the core steps on the sample: per-file gzip both ways, one solid zstd stream.
+    let gzip = Gzip { level: 6 };
+    let start = Instant::now();
+    let frames = corpus.samples.iter().map(|sample| gzip.compress_bytes(sample)).collect::<Result<Vec<_>>>()?;
+    let gzip_time = start.elapsed();
+    let start = Instant::now();
+    for frame in &frames {
+        std::io::copy(&mut gzip.decoder(&mut frame.as_slice())?, &mut std::io::sink())?;
+    }
+    let gunzip_time = start.elapsed();
+    let start = Instant::now();
+    let solid = Zstd { level: 3 }.compress_bytes(&corpus.samples.concat())?;
+    let zstd_time = start.elapsed();
+
+    let gzip_bytes = frames.iter().map(|frame| frame.len() as u64).sum::<u64>() * corpus.bytes / sample_bytes.max(1);
+    let zstd_bytes = solid.len() as u64 * corpus.bytes / sample_bytes.max(1);
+
+    println!();
+    match &args.input {
+        Some(source) => println!("Input: {} ({} files)", source, corpus.files),
+        None => println!("Input: {} generated files", corpus.files),
+    }
+    println!("  Estimated size: {} (from {} sampled files)", format_bytes(corpus.bytes), corpus.samples.len());
+
+    println!();
+    println!("Estimated disk usage:");
+    let rows = [
+        (if args.input.is_some() { "snapshot" } else { "corpus" }, corpus.bytes),
+        ("gzip/", gzip_bytes),
+        ("gunzip/", corpus.bytes),
+        ("zstd archive", zstd_bytes),
+    ];
+    for (label, bytes) in rows {
+        println!("  {:<14} {:>12}", label, format_bytes(bytes));
+    }
+    println!("  {:<14} {:>12}", "total", format_bytes(rows.iter().map(|(_, bytes)| bytes).sum()));
+
+    println!();
+    println!("Estimated codec time (CPU only, excluding disk I/O):");
+    let mut total = Duration::ZERO;
+    if let Some(generation) = corpus.generation {
+        let time = scale(generation, sample_bytes, corpus.bytes);
+        println!("  {:<14} {:>12}", "generation", render::duration(time));
+        total += time;
+    }
+    for (label, time) in [("gzip", gzip_time), ("gunzip", gunzip_time), ("zstd archive", zstd_time)] {
+        let time = scale(time, sample_bytes, corpus.bytes);
+        println!("  {:<14} {:>12}", label, render::duration(time));
+        total += time;
+    }
+    println!("  {:<14} {:>12}", "total", render::duration(total));
+
+    if let Some(latency) = args.fs_latency {
+        // Files under a transfer size cost an open, a read and a close on each side.
+        let files = corpus.files as u64;
+        let archive_trips = 3 * files + 2 + zstd_bytes.div_ceil(latency::TRANSFER_SIZE);
+        println!();
+        println!("Estimated simulated latency ({:?} per round trip):", latency);
+        for (label, trips) in [("gzip", 6 * files), ("gunzip", 6 * files), ("zstd archive", archive_trips)] {
+            println!("  {:<14} {:>12} ({} round trips)", label, render::duration(latency * trips as u32), trips);
+        }
+    }
+
+    let optional = optional_steps(args);
+    if !optional.is_empty() {
+        println!();
+        println!("Also enabled, not estimated: {}", optional.join(", "));
+    }
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! `--nice` and `--ionice` (Linux): run at a lower CPU and I/O priority so a
+//! long benchmark on a shared machine leaves room for other work.
+//!
+//! Both are set on the main thread before any worker starts, so every thread
+//! inherits them. The priority in effect is recorded with the environment,
+//! since a niced run measures different times.
+
+use std::fmt;
+use std::str::FromStr;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// An I/O scheduling class and, for the classes that have one, its level (0 is highest, 7 lowest).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IoPriority {
+    Realtime(u8),
+    BestEffort(u8),
+    Idle,
+}
+
+impl FromStr for IoPriority {
+    type Err = anyhow::Error;
+
+    /// Parses `idle`, `best-effort[:LEVEL]` or `realtime[:LEVEL]`; the level defaults to 4.
+    fn from_str(s: &str) -> Result<Self> {
+        let (class, level) = match s.split_once(':') {
+            Some((class, level)) => (class, Some(level)),
+            None => (s, None),
+        };
+        let level = match level {
+            Some(level) => match level.parse::<u8>() {
+                Ok(level) if level <= 7 => level,
+                _ => bail!("I/O priority level must be 0 to 7, got `{}`", level),
+            },
+            None => 4,
+        };
+        match class {
+            "idle" if s == "idle" => Ok(IoPriority::Idle),
+            "best-effort" => Ok(IoPriority::BestEffort(level)),
+            "realtime" => Ok(IoPriority::Realtime(level)),
+            _ => bail!("unknown I/O priority `{}`; expected idle, best-effort[:LEVEL] or realtime[:LEVEL]", s),
+        }
+    }
+}
+
+impl fmt::Display for IoPriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IoPriority::Realtime(level) => write!(f, "realtime:{}", level),
+            IoPriority::BestEffort(level) => write!(f, "best-effort:{}", level),
+            IoPriority::Idle => write!(f, "idle"),
+        }
+    }
+}
+
+/// The priority a run had, as recorded in bundles.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Priority {
+    pub nice: i32,
+    /// I/O class and level, e.g. `idle`, or `default` when the class follows the nice value.
+    pub io: String,
+}
+
+impl Priority {
+    /// Whether anything was lowered or raised from what a process gets by default.
+    pub fn is_default(&self) -> bool {
+        self.nice == 0 && self.io == "default"
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "nice {}, I/O {}", self.nice, self.io)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::io;
+    use anyhow::{Context, Result};
+    use super::{IoPriority, Priority};
+
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+    pub fn apply(nice: Option<i32>, io: Option<IoPriority>) -> Result<()> {
+        if let Some(nice) = nice {
+            // SAFETY: changes the priority of the calling thread only.
+            if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+                let hint = if nice < 0 { " (raising priority needs CAP_SYS_NICE)" } else { "" };
+                return Err(io::Error::last_os_error()).with_context(|| format!("failed to set nice {}{}", nice, hint));
+            }
+        }
+        if let Some(io) = io {
+            let (class, level) = match io {
+                IoPriority::Realtime(level) => (1, level),
+                IoPriority::BestEffort(level) => (2, level),
+                IoPriority::Idle => (3, 0),
+            };
+            let value = (class << IOPRIO_CLASS_SHIFT) | level as libc::c_int;
+            // SAFETY: ioprio_set takes plain integers and only affects the calling thread.
+            if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, value) } != 0 {
+                let hint = if matches!(io, IoPriority::Realtime(_)) { " (the realtime class needs CAP_SYS_ADMIN)" } else { "" };
+                return Err(io::Error::last_os_error()).with_context(|| format!("failed to set I/O priority {}{}", io, hint));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn current() -> Option<Priority> {
+        // SAFETY: getpriority can legitimately return -1, so errno is cleared first to tell it from an error.
+        let nice = unsafe {
+            *libc::__errno_location() = 0;
+            let nice = libc::getpriority(libc::PRIO_PROCESS, 0);
+            if nice == -1 && *libc::__errno_location() != 0 {
+                return None;
+            }
+            nice
+        };
+        // SAFETY: ioprio_get takes plain integers.
+        let value = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, 0) };
+        if value < 0 {
+            return None;
+        }
+        let level = value & ((1 << IOPRIO_CLASS_SHIFT) - 1);
+        let io = match value >> IOPRIO_CLASS_SHIFT {
+            1 => format!("realtime:{}", level),
+            2 => format!("best-effort:{}", level),
+            3 => "idle".to_string(),
+            _ => "default".to_string(),
+        };
+        Some(Priority { nice, io })
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use anyhow::{ensure, Result};
+    use super::{IoPriority, Priority};
+
+    pub fn apply(nice: Option<i32>, io: Option<IoPriority>) -> Result<()> {
+        ensure!(nice.is_none() && io.is_none(), "--nice and --ionice are only supported on Linux");
+        Ok(())
+    }
+
+    pub fn current() -> Option<Priority> {
+        None
+    }
+}
+
+/// Lowers (or, with the privileges, raises) the CPU and I/O priority of this process.
+pub fn apply(nice: Option<i32>, io: Option<IoPriority>) -> Result<()> {
+    imp::apply(nice, io)
+}
+
+/// The priority this process runs at, where the platform reports it.
+pub fn current() -> Option<Priority> {
+    imp::current()
+}
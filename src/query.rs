@@ -0,0 +1,219 @@
+//! "grep the archive" workload: stream every compressed artifact through its
+//! decoder and count the lines matching a field predicate, without writing
+//! anything back to disk. With `--since` or `--until` only entries with a
+//! `timestamp` in that range count, and the indexed archive skips frames whose
+//! timestamp range misses it.
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use anyhow::{bail, Result};
+use flate2::read::GzDecoder;
+use crate::bench::throughput;
+use crate::index::{self, FrameIndex, Index, TimeRange};
+use crate::render::{Align, Table};
+use crate::{archive, format_bytes, latency, progress_bar, render};
+
+/// `FIELD=VALUE`: a line matches when it contains `"FIELD": VALUE`, with VALUE quoted or bare.
+#[derive(Clone, Debug)]
+pub struct Predicate {
+    field: String,
+    key: Vec<u8>,
+    value: Vec<u8>,
+}
+
+impl FromStr for Predicate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((field, value)) = s.split_once('=') else {
+            bail!("expected FIELD=VALUE, got {:?}", s);
+        };
+        Ok(Predicate {
+            field: field.to_string(),
+            key: format!("\"{}\"", field).into_bytes(),
+            value: value.as_bytes().to_vec(),
+        })
+    }
+}
+
+impl Predicate {
+    /// Whether `index` rules out every line of `frame`.
+    fn excludes(&self, index: &Index, frame: &FrameIndex) -> bool {
+        !index.may_match(frame, &self.field, &String::from_utf8_lossy(&self.value))
+    }
+
+    /// Matches both pretty-printed (`"level": "ERROR",`) and compact (`"level":"ERROR"`) JSON.
+    pub fn matches(&self, line: &[u8]) -> bool {
+        let mut rest = line;
+        while let Some(at) = find(rest, &self.key) {
+            rest = &rest[at + self.key.len()..];
+            let after_colon = match trim_start(rest).split_first() {
+                Some((b':', after)) => trim_start(after),
+                _ => continue,
+            };
+            let bare = match after_colon.split_first() {
+                Some((b'"', quoted)) => {
+                    if quoted.starts_with(&self.value) && quoted.get(self.value.len()) == Some(&b'"') {
+                        return true;
+                    }
+                    continue;
+                }
+                _ => after_colon,
+            };
+            if bare.starts_with(&self.value)
+                && bare.get(self.value.len()).is_none_or(|&b| b == b',' || b == b'}' || b.is_ascii_whitespace())
+            {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Counts `(matching, total)` lines read from `reader`.
+fn count_lines(reader: &mut impl BufRead, predicate: &Predicate, buf: &mut Vec<u8>) -> Result<(u64, u64)> {
+    let (mut matching, mut total) = (0, 0);
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', buf)? == 0 {
+            return Ok((matching, total));
+        }
+        total += 1;
+        if predicate.matches(buf) {
+            matching += 1;
+        }
+    }
+}
+
+/// Counts `(matching, total)` lines of one entry, or none when it has no timestamp in `range`.
+fn count_entry(content: impl Read, predicate: &Predicate, range: &TimeRange, buf: &mut Vec<u8>, entry: &mut Vec<u8>) -> Result<(u64, u64)> {
+    if !range.is_set() {
+        return count_lines(&mut BufReader::new(content), predicate, buf);
+    }
+    entry.clear();
+    BufReader::new(content).read_to_end(entry)?;
+    if !range.admits(entry) {
+        return Ok((0, 0));
+    }
+    count_lines(&mut entry.as_slice(), predicate, buf)
+}
+
+pub struct QueryResult {
+    pub label: &'static str,
+    pub compressed_size: u64,
+    /// Frames decoded out of the total, for the indexed archive.
+    pub frames: Option<(usize, usize)>,
+    pub matching: u64,
+    pub lines: u64,
+    pub time: Duration,
+}
+
+/// Scans the per-file gzip outputs and the solid zstd archive for `predicate`,
+/// and the indexed archive when one was written, skipping frames the index rules out.
+pub fn run(
+    predicate: &Predicate,
+    range: &TimeRange,
+    gz_paths: &[PathBuf],
+    archive_path: &Path,
+    dict: Option<&[u8]>,
+    indexed: Option<(&Path, &Index)>,
+) -> Result<Vec<QueryResult>> {
+    let pb = progress_bar(gz_paths.len() + 1 + usize::from(indexed.is_some()));
+    let (mut buf, mut entry_buf) = (Vec::new(), Vec::new());
+
+    let start = Instant::now();
+    let (mut matching, mut lines, mut compressed_size) = (0, 0, 0);
+    for path in gz_paths {
+        let file = latency::open(path)?;
+        compressed_size += file.get_ref().metadata()?.len();
+        let (m, l) = count_entry(GzDecoder::new(BufReader::new(file)), predicate, range, &mut buf, &mut entry_buf)?;
+        matching += m;
+        lines += l;
+        pb.inc(1);
+    }
+    let gzip = QueryResult { label: "Per-file gzip", compressed_size, frames: None, matching, lines, time: start.elapsed() };
+
+    let start = Instant::now();
+    let (mut matching, mut lines) = (0, 0);
+    archive::for_each_entry(archive_path, dict, true, |entry, content| {
+        if !entry.is_symlink {
+            let (m, l) = count_entry(content, predicate, range, &mut buf, &mut entry_buf)?;
+            matching += m;
+            lines += l;
+        }
+        Ok(())
+    })?;
+    let zstd = QueryResult {
+        label: "Solid zstd archive",
+        compressed_size: std::fs::metadata(archive_path)?.len(),
+        frames: None,
+        matching,
+        lines,
+        time: start.elapsed(),
+    };
+    pb.inc(1);
+    let mut results = vec![gzip, zstd];
+
+    if let Some((indexed_path, index)) = indexed {
+        let start = Instant::now();
+        let (mut matching, mut lines, mut scanned) = (0, 0, 0);
+        for frame in index.frames.iter().filter(|frame| !predicate.excludes(index, frame) && range.overlaps(frame)) {
+            scanned += 1;
+            index::read_frame(indexed_path, frame, |entry, content| {
+                if !entry.is_symlink {
+                    let (m, l) = count_entry(content, predicate, range, &mut buf, &mut entry_buf)?;
+                    matching += m;
+                    lines += l;
+                }
+                Ok(())
+            })?;
+        }
+        results.push(QueryResult {
+            label: "Indexed zstd archive",
+            compressed_size: std::fs::metadata(indexed_path)?.len(),
+            frames: Some((scanned, index.frames.len())),
+            matching,
+            lines,
+            time: start.elapsed(),
+        });
+        pb.inc(1);
+    }
+    pb.finish_with_message("Query complete!");
+
+    Ok(results)
+}
+
+pub fn print(results: &[QueryResult], json_size: u64) {
+    let mut table = Table::new(&[
+        ("Artifact", Align::Left),
+        ("Compressed", Align::Right),
+        ("Matches", Align::Right),
+        ("Lines", Align::Right),
+        ("Frames", Align::Right),
+        ("Time", Align::Right),
+        ("Scan rate", Align::Right),
+    ]);
+    for result in results {
+        table.row(vec![
+            result.label.to_string(),
+            format_bytes(result.compressed_size),
+            result.matching.to_string(),
+            result.lines.to_string(),
+            result.frames.map_or("-".to_string(), |(scanned, total)| format!("{}/{}", scanned, total)),
+            render::duration(result.time),
+            render::mb_per_sec(throughput(json_size, result.time)),
+        ]);
+    }
+    print!("{}", table.terminal());
+}
@@ -0,0 +1,121 @@
+//! `--reader-bench`: lists and extracts the solid archive with the buffered
+//! streaming reader and with the memory-mapped zero-copy reader.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::{ensure, Result};
+use crate::archive;
+use crate::bench::throughput;
+use crate::render::{Align, Table};
+use crate::{progress_bar, render};
+
+pub struct ReaderResult {
+    pub label: &'static str,
+    pub list: Duration,
+    pub extract: Duration,
+}
+
+/// Entries and content bytes seen by one pass, to check both readers agree.
+#[derive(Default, PartialEq, Debug)]
+struct Totals {
+    entries: usize,
+    bytes: u64,
+}
+
+fn create(dir: &Path, name: &str) -> Result<File> {
+    let path = dir.join(name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(File::create(path)?)
+}
+
+fn buffered(archive_path: &Path, dict: Option<&[u8]>, extract_to: Option<&Path>) -> Result<Totals> {
+    let mut totals = Totals::default();
+    archive::for_each_entry(archive_path, dict, true, |entry, content| {
+        totals.entries += 1;
+        totals.bytes += entry.len;
+        if let (Some(dir), false) = (extract_to, entry.is_symlink) {
+            io::copy(content, &mut create(dir, &entry.name)?)?;
+        }
+        Ok(())
+    })?;
+    Ok(totals)
+}
+
+fn mapped(archive_path: &Path, dict: Option<&[u8]>, extract_to: Option<&Path>) -> Result<Totals> {
+    let mut totals = Totals::default();
+    archive::for_each_entry_mapped(archive_path, dict, true, |entry| {
+        totals.entries += 1;
+        totals.bytes += entry.content.len() as u64;
+        if let (Some(dir), false) = (extract_to, entry.is_symlink) {
+            create(dir, entry.name)?.write_all(entry.content)?;
+        }
+        Ok(())
+    })?;
+    Ok(totals)
+}
+
+/// Times listing and extracting `archive_path` into `dir` with both readers.
+pub fn run(archive_path: &Path, dict: Option<&[u8]>, dir: &Path) -> Result<Vec<ReaderResult>> {
+    type Reader = fn(&Path, Option<&[u8]>, Option<&Path>) -> Result<Totals>;
+    let readers: [(&str, Reader); 2] = [("buffered", buffered), ("mmap", mapped)];
+    let pb = progress_bar(2 * readers.len());
+    let mut results = Vec::with_capacity(readers.len());
+    let mut expected = None;
+
+    for (label, reader) in readers {
+        let start = Instant::now();
+        let listed = reader(archive_path, dict, None)?;
+        let list = start.elapsed();
+        pb.inc(1);
+
+        let extract_dir = dir.join(label);
+        if extract_dir.exists() {
+            fs::remove_dir_all(&extract_dir)?;
+        }
+        fs::create_dir_all(&extract_dir)?;
+        let start = Instant::now();
+        let extracted = reader(archive_path, dict, Some(&extract_dir))?;
+        let extract = start.elapsed();
+        pb.inc(1);
+
+        ensure!(listed == extracted, "{} reader listed {:?} but extracted {:?}", label, listed, extracted);
+        let expected = expected.get_or_insert(listed);
+        ensure!(*expected == extracted, "{} reader saw {:?}, expected {:?}", label, extracted, expected);
+        results.push(ReaderResult { label, list, extract });
+    }
+    pb.finish_with_message("Archive reader comparison complete!");
+    Ok(results)
+}
+
+pub fn print(results: &[ReaderResult], json_size: u64) {
+    let cell = |time: Duration| format!("{} ({})", render::duration(time), render::mb_per_sec(throughput(json_size, time)));
+    let mut table = Table::new(&[("Reader", Align::Left), ("List", Align::Right), ("Extract", Align::Right)]);
+    for result in results {
+        table.row(vec![result.label.to_string(), cell(result.list), cell(result.extract)]);
+    }
+    print!("{}", table.terminal());
+}
+
+/// `list`: prints each entry's size and name, and the target of stored symlinks.
+pub fn list(archive_path: &Path, dict: Option<&[u8]>) -> Result<()> {
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    let result = archive::for_each_entry_mapped(archive_path, dict, true, |entry| {
+        if entry.is_symlink {
+            writeln!(out, "{:>12}  {} -> {}", "link", entry.name, String::from_utf8_lossy(entry.content))?;
+        } else {
+            writeln!(out, "{:>12}  {}", entry.content.len(), entry.name)?;
+        }
+        Ok(())
+    })
+    .and_then(|()| Ok(out.flush()?));
+    // Like `search`, stop quietly when the reader goes away, e.g. `| head`.
+    match result {
+        Err(err) if err.downcast_ref::<io::Error>().is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) => Ok(()),
+        other => other,
+    }
+}
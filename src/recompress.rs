@@ -0,0 +1,313 @@
+//! `recompress`: migrate a directory of existing .gz logs to zstd, verifying
+//! every converted file against its original before counting it.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use flate2::read::MultiGzDecoder;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use crate::{artifact, durability};
+use crate::workspace::output_dir;
+use crate::{format_bytes, progress_bar, render};
+
+const COMPARE_CHUNK: usize = 64 * 1024;
+
+/// Decoded bytes hashed at a time; large enough for BLAKE3 to spread over all cores.
+const HASH_CHUNK: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verify {
+    /// Decode both files side by side and compare them byte for byte
+    #[default]
+    Stream,
+    /// Decode both files separately, compare their BLAKE3 digests and record them in the report
+    Strict,
+}
+
+pub struct Options {
+    pub level: i32,
+    pub verify: Verify,
+    /// Write the .zst files into the workspace instead of next to the originals.
+    pub dry_run: bool,
+    pub delete_originals: bool,
+    pub report: Option<PathBuf>,
+    pub signing_key: Option<PathBuf>,
+}
+
+/// One verified conversion, as listed in the report.
+#[derive(Serialize)]
+struct Converted {
+    original: String,
+    output: Option<String>,
+    uncompressed_bytes: u64,
+    original_bytes: u64,
+    output_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blake3: Option<String>,
+    deleted_original: bool,
+}
+
+#[derive(Serialize)]
+struct Failed {
+    original: String,
+    reason: String,
+}
+
+#[derive(Serialize)]
+struct Report {
+    generated_at: String,
+    dir: String,
+    level: i32,
+    verify: Verify,
+    dry_run: bool,
+    converted: Vec<Converted>,
+    failed: Vec<Failed>,
+    /// HMAC-SHA256 over the report serialized without this field, hex encoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+/// Every `.gz` file under `dir`, not following symlinks.
+fn find_gz_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() && entry.path().extension().is_some_and(|ext| ext == "gz") {
+                files.push(entry.path());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Reads as many bytes as are available, up to `buf.len()`, stopping only at EOF.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Compares two decoded streams chunk by chunk without holding either in memory.
+pub fn streams_equal(mut a: impl Read, mut b: impl Read) -> io::Result<bool> {
+    let (mut buf_a, mut buf_b) = (vec![0; COMPARE_CHUNK], vec![0; COMPARE_CHUNK]);
+    loop {
+        let (n_a, n_b) = (read_full(&mut a, &mut buf_a)?, read_full(&mut b, &mut buf_b)?);
+        if n_a != n_b || buf_a[..n_a] != buf_b[..n_b] {
+            return Ok(false);
+        }
+        if n_a == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// BLAKE3 of everything `reader` decodes, plus the time spent hashing rather than decoding.
+fn blake3(mut reader: impl Read) -> io::Result<(String, Duration)> {
+    let mut hasher = blake3::Hasher::new();
+    let mut buf = vec![0; HASH_CHUNK];
+    let mut hash_time = Duration::ZERO;
+    loop {
+        let n = read_full(&mut reader, &mut buf)?;
+        if n == 0 {
+            return Ok((hasher.finalize().to_hex().to_string(), hash_time));
+        }
+        let start = Instant::now();
+        hasher.update_rayon(&buf[..n]);
+        hash_time += start.elapsed();
+    }
+}
+
+fn gz_reader(path: &Path) -> io::Result<MultiGzDecoder<BufReader<File>>> {
+    Ok(MultiGzDecoder::new(BufReader::new(File::open(path)?)))
+}
+
+/// Checks `zst_path` against `gz_path`, returning the content digest in strict mode
+/// and the part of the check spent hashing.
+fn verify(gz_path: &Path, zst_path: &Path, mode: Verify) -> Result<(Option<String>, Duration)> {
+    let zst_reader = || -> io::Result<_> { zstd::Decoder::new(File::open(zst_path)?) };
+    match mode {
+        Verify::Stream => {
+            if !streams_equal(gz_reader(gz_path)?, zst_reader()?)? {
+                bail!("zstd output does not match the gzip original");
+            }
+            Ok((None, Duration::ZERO))
+        }
+        Verify::Strict => {
+            let (original, original_time) = blake3(gz_reader(gz_path)?)?;
+            let (converted, converted_time) = blake3(zst_reader()?)?;
+            if original != converted {
+                bail!("BLAKE3 mismatch: gzip {} vs zstd {}", original, converted);
+            }
+            Ok((Some(original), original_time + converted_time))
+        }
+    }
+}
+
+fn sign(report: &Report, key_path: &Path) -> Result<String> {
+    let key = fs::read(key_path).with_context(|| format!("failed to read signing key {}", key_path.display()))?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    mac.update(&serde_json::to_vec(report)?);
+    Ok(hex(&mac.finalize().into_bytes()))
+}
+
+/// Recompresses every .gz under `dir` to a .zst next to it and verifies the result.
+/// Originals are only deleted with `delete_originals`, and only after verification passed.
+pub fn run(dir: &Path, options: &Options) -> Result<()> {
+    println!(
+        "🔁 Recompressing .gz files under {} to zstd level {}{}",
+        dir.display(),
+        options.level,
+        if options.dry_run { " (dry run)" } else { "" }
+    );
+    let files = find_gz_files(dir)?;
+    if files.is_empty() {
+        bail!("no .gz files under {}", dir.display());
+    }
+
+    let pb = progress_bar(files.len());
+    let (mut convert_time, mut verify_time, mut hash_time) = (Duration::ZERO, Duration::ZERO, Duration::ZERO);
+    let mut converted = Vec::new();
+    let mut failed = Vec::new();
+
+    for (i, gz_path) in files.iter().enumerate() {
+        let zst_path = if options.dry_run {
+            output_dir().join(format!("recompress_{}.zst", i))
+        } else {
+            gz_path.with_extension("zst")
+        };
+        if !options.dry_run && zst_path.exists() {
+            let reason = format!("{} already exists", zst_path.display());
+            pb.println(format!("⚠️  {}: {}", gz_path.display(), reason));
+            failed.push(Failed { original: gz_path.display().to_string(), reason });
+            pb.inc(1);
+            continue;
+        }
+        let result = (|| -> Result<(u64, Option<String>, File)> {
+            let start = Instant::now();
+            let mut encoder = zstd::Encoder::new(BufWriter::new(File::create(&zst_path)?), options.level)?;
+            let bytes = io::copy(&mut gz_reader(gz_path)?, &mut encoder)?;
+            let file = encoder.finish()?.into_inner().map_err(|err| err.into_error())?;
+            convert_time += start.elapsed();
+
+            let start = Instant::now();
+            let (digest, hashing) = verify(gz_path, &zst_path, options.verify)?;
+            verify_time += start.elapsed();
+            hash_time += hashing;
+            Ok((bytes, digest, file))
+        })();
+
+        match result {
+            Ok((bytes, blake3, file)) => {
+                let original_bytes = fs::metadata(gz_path)?.len();
+                let output_bytes = fs::metadata(&zst_path)?.len();
+                let deleted_original = options.delete_originals && !options.dry_run;
+                if deleted_original {
+                    // The .zst and its directory entry must be on disk before the only other copy goes.
+                    durability::sync_file(&file)?;
+                    durability::sync_dir(zst_path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new(".")))?;
+                    fs::remove_file(gz_path)?;
+                }
+                if options.dry_run {
+                    fs::remove_file(&zst_path)?;
+                }
+                converted.push(Converted {
+                    original: gz_path.display().to_string(),
+                    output: (!options.dry_run).then(|| zst_path.display().to_string()),
+                    uncompressed_bytes: bytes,
+                    original_bytes,
+                    output_bytes,
+                    blake3,
+                    deleted_original,
+                });
+            }
+            Err(err) => {
+                // Never leave an unverified .zst behind next to the original.
+                let _ = fs::remove_file(&zst_path);
+                pb.println(format!("⚠️  {}: {}", gz_path.display(), err));
+                failed.push(Failed { original: gz_path.display().to_string(), reason: err.to_string() });
+            }
+        }
+        pb.inc(1);
+    }
+    pb.finish_with_message("Recompression complete!");
+
+    let gz_size: u64 = converted.iter().map(|c| c.original_bytes).sum();
+    let zst_size: u64 = converted.iter().map(|c| c.output_bytes).sum();
+    println!("\n📊 RECOMPRESSION RESULTS");
+    println!("=====================================");
+    println!("  Files converted and verified ({:?}): {} of {}", options.verify, converted.len(), files.len());
+    println!("  Uncompressed: {}", format_bytes(converted.iter().map(|c| c.uncompressed_bytes).sum()));
+    println!("  gzip: {}", format_bytes(gz_size));
+    println!("  zstd: {}", format_bytes(zst_size));
+    if gz_size > 0 {
+        let reclaimed = gz_size as i64 - zst_size as i64;
+        println!(
+            "  Space {}: {}{} ({})",
+            if options.dry_run { "that would be reclaimed" } else { "reclaimed" },
+            if reclaimed < 0 { "-" } else { "" },
+            format_bytes(reclaimed.unsigned_abs()),
+            render::percent(reclaimed as f64 / gz_size as f64)
+        );
+    }
+    println!("  Conversion time: {}", render::duration(convert_time));
+    println!("  Verification time: {}", render::duration(verify_time));
+    if options.verify == Verify::Strict {
+        println!("    of which BLAKE3 hashing: {}", render::duration(hash_time));
+    }
+    let deleted = converted.iter().filter(|c| c.deleted_original).count();
+    if deleted > 0 {
+        println!("  Deleted {} verified originals", deleted);
+    }
+    let failures = failed.len();
+    if failures > 0 {
+        println!();
+        println!("⚠️  {} files could not be converted (originals left untouched):", failures);
+        for failure in &failed {
+            println!("  {}: {}", failure.original, failure.reason);
+        }
+    }
+
+    if let Some(path) = &options.report {
+        let mut report = Report {
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            dir: dir.display().to_string(),
+            level: options.level,
+            verify: options.verify,
+            dry_run: options.dry_run,
+            converted,
+            failed,
+            signature: None,
+        };
+        if let Some(key) = &options.signing_key {
+            report.signature = Some(sign(&report, key)?);
+        }
+        let written = artifact::write(path, &serde_json::to_vec_pretty(&report)?)?;
+        println!("\n💾 Report written to {} ({}{})", path.display(), written, if report.signature.is_some() { ", signed" } else { "" });
+    }
+
+    if failures > 0 {
+        bail!("{} of {} files failed to recompress", failures, files.len());
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! `--reference`: shows the run next to bundles exported with `--bundle` on
+//! other machines, to tell whether numbers are hardware- or configuration-limited.
+//!
+//! Output sizes depend only on the corpus, the codec settings and the codec
+//! versions, never on the hardware. So when a reference compressed the same
+//! corpus to the same sizes, any speed difference is the machine's; when the
+//! sizes differ, the configurations do and the speeds are not comparable.
+
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{ensure, Result};
+use crate::bench::throughput_per_core;
+use crate::bundle::{self, Environment, Manifest};
+use crate::render;
+use crate::results::{Results, Stage};
+
+/// Relative size difference above which two runs count as configured differently.
+const SIZE_TOLERANCE: f64 = 0.01;
+
+/// Per-core speed difference below which two machines count as equally fast.
+const SPEED_TOLERANCE: f64 = 0.05;
+
+pub struct Reference {
+    pub name: String,
+    pub manifest: Manifest,
+    pub results: Results,
+}
+
+/// Loads the bundle at `path`, or every `.tar.zst` bundle in it if it is a directory.
+pub fn load(path: &Path) -> Result<Vec<Reference>> {
+    let paths: Vec<PathBuf> = if path.is_dir() {
+        let mut paths: Vec<PathBuf> = fs::read_dir(path)?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|path| path.to_string_lossy().ends_with(".tar.zst"))
+            .collect();
+        paths.sort();
+        paths
+    } else {
+        vec![path.to_path_buf()]
+    };
+    ensure!(!paths.is_empty(), "{} holds no .tar.zst bundles", path.display());
+    paths
+        .into_iter()
+        .map(|path| {
+            let (manifest, results) = bundle::read(&path)?;
+            let name = path.file_name().unwrap_or_default().to_string_lossy().trim_end_matches(".tar.zst").to_string();
+            Ok(Reference { name, manifest, results })
+        })
+        .collect()
+}
+
+fn machine(environment: &Environment) -> String {
+    format!("{}, {} CPUs, zstd {}", environment.cpu_model.as_deref().unwrap_or(&environment.arch), environment.cpus, environment.zstd)
+}
+
+fn compressed(stage: &Stage) -> u64 {
+    stage.bytes_in.min(stage.bytes_out)
+}
+
+fn per_core(stage: &Stage) -> f64 {
+    throughput_per_core(stage.bytes_in.max(stage.bytes_out), Duration::from_secs_f64(stage.secs), stage.threads())
+}
+
+/// Stages both runs have, as (this run, reference).
+fn shared<'a>(results: &'a Results, reference: &'a Results) -> Vec<(&'a Stage, &'a Stage)> {
+    results
+        .stages
+        .iter()
+        .filter_map(|stage| {
+            let other = reference.stages.iter().find(|other| other.name == stage.name && other.codec == stage.codec)?;
+            Some((stage, other))
+        })
+        .collect()
+}
+
+/// Whether the difference to `reference` comes from the hardware or the configuration.
+fn verdict(results: &Results, reference: &Reference) -> String {
+    let same_machine = reference.manifest.environment.differences(&Environment::current()).is_empty();
+    let reference = &reference.results;
+    if results.corpus.files != reference.corpus.files || results.corpus.bytes != reference.corpus.bytes {
+        return "different corpus, so neither sizes nor speeds are comparable".to_string();
+    }
+    let stages = shared(results, reference);
+    if stages.is_empty() {
+        return "no stages in common".to_string();
+    }
+    let size_differs = stages.iter().any(|(stage, other)| {
+        (compressed(stage) as f64 / compressed(other).max(1) as f64 - 1.0).abs() > SIZE_TOLERANCE
+    });
+    if size_differs {
+        return "configuration-limited: output sizes differ, so codec settings or versions do".to_string();
+    }
+    // Geometric mean, so one very fast stage does not outweigh the others.
+    let log_sum: f64 = stages.iter().map(|(stage, other)| (per_core(stage) / per_core(other)).ln()).sum();
+    let speed = (log_sum / stages.len() as f64).exp();
+    if (speed - 1.0).abs() <= SPEED_TOLERANCE {
+        "same sizes and per-core speed as this machine".to_string()
+    } else if same_machine {
+        format!("same sizes on an identical machine, so the {:.2}× per-core speed is run-to-run noise or load", speed)
+    } else {
+        format!("hardware-limited: same sizes at {:.2}× the reference's per-core speed", speed)
+    }
+}
+
+pub fn print(results: &Results, references: &[Reference]) {
+    let current = Environment::current();
+    println!("  This machine: {}", machine(&current));
+    for reference in references {
+        println!();
+        println!("  {} ({})", reference.name, machine(&reference.manifest.environment));
+        for line in reference.manifest.environment.differences(&current) {
+            println!("    {}", line);
+        }
+        println!("    arguments: {}", reference.manifest.args.join(" "));
+        println!(
+            "    {:<24} {:<10} {:>10} {:>16} {:>16} {:>8}",
+            "Stage", "Codec", "Δ size", "Here per core", "There per core", "Speed"
+        );
+        for (stage, other) in shared(results, &reference.results) {
+            println!(
+                "    {:<24} {:<10} {:>10} {:>16} {:>16} {:>7.2}×",
+                stage.name,
+                stage.codec,
+                render::percent(compressed(stage) as f64 / compressed(other).max(1) as f64 - 1.0),
+                render::mb_per_sec(per_core(stage)),
+                render::mb_per_sec(per_core(other)),
+                per_core(stage) / per_core(other)
+            );
+        }
+        println!("    Verdict: {}", verdict(results, reference));
+    }
+}
+
+/// Appends a table of the references to the Markdown summary at `path`.
+pub fn append_summary(path: &Path, results: &Results, references: &[Reference]) -> Result<()> {
+    let mut out = String::from("\n| Reference | Machine | Verdict |\n|---|---|---|\n");
+    for reference in references {
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} |",
+            reference.name,
+            machine(&reference.manifest.environment),
+            verdict(results, reference)
+        );
+    }
+    OpenOptions::new().append(true).open(path)?.write_all(out.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,214 @@
+//! Human-readable rendering shared by the terminal, Markdown and HTML outputs.
+//!
+//! Durations, rates and percentages are formatted here once, so the same
+//! measurement reads the same wherever it is shown. `--json` and Parquet keep
+//! the raw seconds and bytes; nothing in this module is meant to be parsed.
+//!
+//! Sizes and rates follow the process-wide `Units`, set once from `--units`.
+
+use std::fmt::{self, Write as _};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::bench::throughput;
+
+/// Whether sizes are counted in powers of 1000 or of 1024.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Units {
+    /// kB, MB and GB of 10^3, 10^6 and 10^9 bytes, as storage is usually billed
+    Si,
+    /// KiB, MiB and GiB of 2^10, 2^20 and 2^30 bytes
+    #[default]
+    Binary,
+}
+
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Units::Si => "si",
+            Units::Binary => "binary",
+        })
+    }
+}
+
+static SI: AtomicBool = AtomicBool::new(false);
+
+impl Units {
+    /// Bytes in one kilobyte or kibibyte.
+    pub fn base(self) -> f64 {
+        match self {
+            Units::Si => 1000.0,
+            Units::Binary => 1024.0,
+        }
+    }
+
+    /// Names of 1, base, base² … bytes.
+    pub fn names(self) -> [&'static str; 5] {
+        match self {
+            Units::Si => ["B", "kB", "MB", "GB", "TB"],
+            Units::Binary => ["B", "KiB", "MiB", "GiB", "TiB"],
+        }
+    }
+}
+
+/// Sets the units every size and rate is shown in from now on.
+pub fn set_units(units: Units) {
+    SI.store(units == Units::Si, Ordering::Relaxed);
+}
+
+pub fn units() -> Units {
+    if SI.load(Ordering::Relaxed) { Units::Si } else { Units::Binary }
+}
+
+/// Formats a duration with a unit that fits its size, e.g. `850 µs`, `12.3 ms`, `4.56 s` or `3 min 07 s`.
+pub fn duration(time: Duration) -> String {
+    let secs = time.as_secs_f64();
+    if secs < 0.001 {
+        format!("{} µs", time.as_micros())
+    } else if secs < 1.0 {
+        format!("{:.1} ms", secs * 1000.0)
+    } else if secs < 60.0 {
+        format!("{:.2} s", secs)
+    } else if secs < 3600.0 {
+        let whole = secs.round() as u64;
+        format!("{} min {:02} s", whole / 60, whole % 60)
+    } else {
+        let minutes = (secs / 60.0).round() as u64;
+        format!("{} h {:02} min", minutes / 60, minutes % 60)
+    }
+}
+
+/// `duration` for a time recorded in seconds.
+pub fn seconds(secs: f64) -> String {
+    duration(Duration::from_secs_f64(secs))
+}
+
+/// Formats a throughput already in megabytes (or mebibytes) per second, as `bench::throughput` gives it.
+pub fn mb_per_sec(value: f64) -> String {
+    format!("{:.1} {}/s", value, units().names()[2])
+}
+
+/// Throughput of `bytes` of uncompressed data in `time`.
+pub fn rate(bytes: u64, time: Duration) -> String {
+    mb_per_sec(throughput(bytes, time))
+}
+
+/// Files handled per second.
+pub fn files_rate(files: usize, time: Duration) -> String {
+    format!("{:.0} files/s", files as f64 / time.as_secs_f64())
+}
+
+/// Formats a fraction as a percentage, e.g. `0.1234` as `12.34%`.
+pub fn percent(fraction: f64) -> String {
+    format!("{:.2}%", fraction * 100.0)
+}
+
+/// Relative change from `old` to `new`, e.g. `+1.23%`, or a dash when `old` is zero and there is nothing to compare.
+pub fn change(new: f64, old: f64) -> String {
+    if old > 0.0 {
+        format!("{:+.2}%", (new / old - 1.0) * 100.0)
+    } else {
+        "–".to_string()
+    }
+}
+
+/// Escapes text for HTML element content and attribute values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// A table of already formatted cells, rendered for any of the outputs.
+pub struct Table {
+    columns: Vec<(String, Align)>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(columns: &[(&str, Align)]) -> Self {
+        Table { columns: columns.iter().map(|(name, align)| (name.to_string(), *align)).collect(), rows: Vec::new() }
+    }
+
+    /// Adds a row; missing trailing cells are left empty.
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    fn cell(&self, row: &[String], column: usize) -> String {
+        row.get(column).cloned().unwrap_or_default()
+    }
+
+    /// Space-aligned columns two spaces apart, every line indented by two spaces like the rest of the terminal output.
+    pub fn terminal(&self) -> String {
+        let widths: Vec<usize> = (0..self.columns.len())
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .map(|row| self.cell(row, column).chars().count())
+                    .chain([self.columns[column].0.chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let mut out = String::new();
+        let headers: Vec<String> = self.columns.iter().map(|(name, _)| name.clone()).collect();
+        for row in std::iter::once(&headers).chain(&self.rows) {
+            let mut line = String::new();
+            for (column, (_, align)) in self.columns.iter().enumerate() {
+                let cell = self.cell(row, column);
+                let _ = match align {
+                    Align::Left => write!(line, "  {:<width$}", cell, width = widths[column]),
+                    Align::Right => write!(line, "  {:>width$}", cell, width = widths[column]),
+                };
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn markdown(&self) -> String {
+        let mut out = String::from("|");
+        for (name, _) in &self.columns {
+            let _ = write!(out, " {} |", name);
+        }
+        out.push_str("\n|");
+        for (_, align) in &self.columns {
+            out.push_str(if *align == Align::Right { "---:|" } else { "---|" });
+        }
+        out.push('\n');
+        for row in &self.rows {
+            out.push('|');
+            for column in 0..self.columns.len() {
+                let _ = write!(out, " {} |", self.cell(row, column));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn html(&self) -> String {
+        let mut out = String::from("<table>\n<tr>");
+        for (name, _) in &self.columns {
+            let _ = write!(out, "<th>{}</th>", escape(name));
+        }
+        out.push_str("</tr>\n");
+        for row in &self.rows {
+            out.push_str("<tr>");
+            for column in 0..self.columns.len() {
+                let _ = write!(out, "<td>{}</td>", escape(&self.cell(row, column)));
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+        out
+    }
+}
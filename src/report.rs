@@ -0,0 +1,219 @@
+//! `report serve`: renders a `--json` results file as HTML and serves it over HTTP.
+//!
+//! The page polls the file's modification time and reloads when a run rewrites
+//! it, so a long benchmark can be watched from another machine on the network.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+use anyhow::Result;
+use crate::artifact;
+use crate::bench::throughput_per_core;
+use crate::format_bytes;
+use crate::render::{self, escape, Align, Table};
+use crate::results::Results;
+
+const POLL_INTERVAL_MS: u32 = 2000;
+
+/// Modification time of `path` in milliseconds, or 0 while it does not exist.
+fn version(path: &Path) -> u128 {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |since| since.as_millis())
+}
+
+/// Columns of the matrix and sweep tables.
+const CELL_COLUMNS: [(&str, Align); 5] = [
+    ("Codec", Align::Left),
+    ("Size", Align::Right),
+    ("Ratio", Align::Right),
+    ("Compress", Align::Right),
+    ("Decompress", Align::Right),
+];
+
+fn render_results(results: &Results) -> String {
+    let mut body = String::new();
+    let corpus = &results.corpus;
+    let _ = writeln!(
+        body,
+        "<p>Generated {} &middot; {} files, {} &middot; corpus prepared in {}</p>",
+        escape(&results.generated_at),
+        corpus.files,
+        format_bytes(corpus.bytes),
+        render::seconds(corpus.generation_secs)
+    );
+    if results.partial {
+        body.push_str("<p>&#9888; Partial run: stopped by Ctrl-C, so later steps are missing.</p>\n");
+    }
+    if let Some(stats) = &corpus.stats {
+        let _ = writeln!(body, "<p>Corpus: {}</p>", escape(&stats.to_string()));
+    }
+
+    let mut stages = Table::new(&[
+        ("Stage", Align::Left),
+        ("Codec", Align::Left),
+        ("In", Align::Right),
+        ("Out", Align::Right),
+        ("Ratio", Align::Right),
+        ("Time", Align::Right),
+        ("Throughput", Align::Right),
+        ("Threads", Align::Right),
+        ("Per core", Align::Right),
+        ("fsync", Align::Right),
+    ]);
+    for stage in &results.stages {
+        // Stages run in either direction; ratio and throughput are relative to the uncompressed side.
+        let (compressed, uncompressed) = (stage.bytes_in.min(stage.bytes_out), stage.bytes_in.max(stage.bytes_out));
+        let time = Duration::from_secs_f64(stage.secs);
+        stages.row(vec![
+            stage.name.clone(),
+            stage.codec.clone(),
+            format_bytes(stage.bytes_in),
+            format_bytes(stage.bytes_out),
+            render::percent(compressed as f64 / uncompressed as f64),
+            render::duration(time),
+            render::rate(uncompressed, time),
+            stage.threads().to_string(),
+            render::mb_per_sec(throughput_per_core(uncompressed, time, stage.threads())),
+            stage.sync_secs.map_or(String::new(), render::seconds),
+        ]);
+    }
+    body.push_str("<h2>Stages</h2>\n");
+    body.push_str(&stages.html());
+
+    if !results.matrix.is_empty() {
+        let mut matrix = Table::new(&CELL_COLUMNS);
+        for cell in &results.matrix {
+            matrix.row(vec![
+                cell.codec.clone(),
+                format_bytes(cell.compressed_bytes),
+                render::percent(cell.ratio),
+                render::seconds(cell.compress_secs),
+                render::seconds(cell.decompress_secs),
+            ]);
+        }
+        body.push_str("<h2>Codec matrix</h2>\n");
+        body.push_str(&matrix.html());
+    }
+    if !results.sweep.is_empty() {
+        let mut sweep = Table::new(&CELL_COLUMNS);
+        for cell in &results.sweep {
+            sweep.row(vec![
+                cell.codec.clone(),
+                format_bytes(cell.compressed_bytes),
+                render::percent(cell.ratio),
+                render::rate(corpus.bytes, Duration::from_secs_f64(cell.compress_secs)),
+                render::rate(corpus.bytes, Duration::from_secs_f64(cell.decompress_secs)),
+            ]);
+        }
+        body.push_str("<h2>Level sweeps</h2>\n");
+        body.push_str(&sweep.html());
+    }
+    for timed_out in &results.timeouts {
+        let _ = writeln!(body, "<p>&#9201; {}</p>", escape(&timed_out.to_string()));
+    }
+    for failure in &results.failures {
+        let _ = writeln!(body, "<p>&#128165; {}</p>", escape(&failure.to_string()));
+        if !failure.stderr.is_empty() {
+            let _ = writeln!(body, "<pre>{}</pre>", escape(&failure.stderr));
+        }
+    }
+    if !corpus.skipped.is_empty() {
+        let _ = writeln!(body, "<p>Skipped input files: {}</p>", escape(&corpus.skipped.join(", ")));
+    }
+    body
+}
+
+fn render_page(path: &Path) -> String {
+    let body = match fs::read(path) {
+        Ok(bytes) => match artifact::decode(bytes).and_then(|bytes| Results::from_slice(&bytes)) {
+            Ok(results) => render_results(&results),
+            // Most likely caught mid-write; the next poll will pick up the finished file.
+            Err(err) => format!("<p>Could not parse {}: {}</p>", escape(&path.display().to_string()), escape(&err.to_string())),
+        },
+        Err(_) => format!("<p>Waiting for {} &hellip;</p>", escape(&path.display().to_string())),
+    };
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>gz-vs-zstd report</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #ccc; padding: 0.3em 0.8em; text-align: right; }}
+th:first-child, td:first-child {{ text-align: left; }}
+</style>
+</head>
+<body>
+<h1>gz-vs-zstd</h1>
+{body}
+<script>
+let version = "{version}";
+setInterval(async () => {{
+  try {{
+    const latest = await (await fetch("/version")).text();
+    if (latest !== version) location.reload();
+  }} catch (e) {{}}
+}}, {interval});
+</script>
+</body>
+</html>
+"#,
+        body = body,
+        version = version(path),
+        interval = POLL_INTERVAL_MS
+    )
+}
+
+fn respond(mut stream: TcpStream, path: &Path) -> Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, content_type, body) = match target {
+        "/" | "/index.html" => ("200 OK", "text/html; charset=utf-8", render_page(path)),
+        "/version" => ("200 OK", "text/plain", version(path).to_string()),
+        _ => ("404 Not Found", "text/plain", "not found".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+/// Serves `results` on `bind:port` until the process is interrupted.
+pub fn serve(results: PathBuf, bind: &str, port: u16) -> Result<()> {
+    let listener = TcpListener::bind((bind, port))?;
+    println!("📡 Serving {} on http://{}", results.display(), listener.local_addr()?);
+    println!("Press Ctrl-C to stop.");
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("⚠️  Connection failed: {}", err);
+                continue;
+            }
+        };
+        let results = results.clone();
+        thread::spawn(move || {
+            if let Err(err) = respond(stream, &results) {
+                eprintln!("⚠️  Request failed: {}", err);
+            }
+        });
+    }
+    Ok(())
+}
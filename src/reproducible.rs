@@ -0,0 +1,80 @@
+//! gzip header control and output digests, for checking that compressed
+//! outputs are byte-for-byte reproducible.
+//!
+//! A gzip member header can carry the source file name and modification time.
+//! Both vary between machines and runs, so content-addressed stores of
+//! compressed logs want them stripped; `gzip -k` and friends preserve them.
+//! With a fixed `--seed` the generated corpus is identical across runs, so a
+//! stripped run's outputs must hash to the same digest every time.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use anyhow::Result;
+use clap::ValueEnum;
+use flate2::GzBuilder;
+use crate::results::Results;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum GzipHeader {
+    /// No file name and a zero mtime, so equal input always gives equal output
+    #[default]
+    Strip,
+    /// The source file's name and mtime, as `gzip` writes them
+    Preserve,
+}
+
+impl fmt::Display for GzipHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        f.write_str(value.get_name())
+    }
+}
+
+impl GzipHeader {
+    /// A gzip builder for compressing `source` with this header policy.
+    pub fn builder(self, source: &Path) -> Result<GzBuilder> {
+        Ok(match self {
+            GzipHeader::Strip => GzBuilder::new().mtime(0),
+            GzipHeader::Preserve => {
+                let modified = fs::metadata(source)?.modified()?;
+                // The header field is 32 bits; gzip writes 0 for times it cannot represent.
+                let mtime = modified.duration_since(UNIX_EPOCH).map_or(0, |since| u32::try_from(since.as_secs()).unwrap_or(0));
+                let name = source.file_name().unwrap_or_default().as_encoded_bytes();
+                GzBuilder::new().filename(name).mtime(mtime)
+            }
+        })
+    }
+}
+
+/// BLAKE3 over the outputs of a stage, in order. Each file's length goes in ahead of
+/// its bytes, so moving bytes from one output to the next changes the digest.
+pub fn digest(paths: &[PathBuf]) -> Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    for path in paths {
+        let mut file = File::open(path)?;
+        hasher.update(&fs::metadata(path)?.len().to_le_bytes());
+        io::copy(&mut file, &mut hasher)?;
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn short(digest: &str) -> &str {
+    digest.get(..16).unwrap_or(digest)
+}
+
+/// Prints how two runs' output digests compare, for stages that recorded one in both.
+pub fn print_comparison(old: &Results, new: &Results) {
+    for stage in &new.stages {
+        let Some(digest) = &stage.output_digest else { continue };
+        let Some(before) = old.stages.iter().find(|old| old.name == stage.name && old.codec == stage.codec) else { continue };
+        let Some(before_digest) = &before.output_digest else { continue };
+        if before_digest == digest {
+            println!("  {} outputs are byte-identical ({})", stage.name, short(digest));
+        } else {
+            println!("  {} outputs differ ({} vs {})", stage.name, short(before_digest), short(digest));
+        }
+    }
+}
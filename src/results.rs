@@ -0,0 +1,360 @@
+//! Machine-readable results of a comparison run, written with `--json`.
+
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::artifact;
+use crate::bench::Measurement;
+use crate::format_bytes;
+
+/// Version of the results format this build writes. Bump it, and add a step to
+/// [`migrate`], whenever a field is renamed, removed or changes meaning.
+///
+/// - 1: the unversioned format, before `schema_version` existed.
+/// - 2: adds `schema_version`; `dedup.hash_secs` is always present.
+pub const SCHEMA_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+pub struct Results {
+    pub schema_version: u32,
+    pub generated_at: String,
+    pub corpus: CorpusInfo,
+    pub stages: Vec<Stage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matrix: Vec<MatrixCell>,
+    /// Every configuration of each `--sweep` family, decompressed from that configuration's own output.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sweep: Vec<MatrixCell>,
+    /// Codecs abandoned by `--stage-timeout`, which have no cell in `sweep` or `matrix`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timeouts: Vec<TimedOut>,
+    /// Codecs whose `--isolate` child process crashed or was killed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<Failure>,
+    /// What `--dedup` saved in the zstd archive before compression.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup: Option<Dedup>,
+    /// Per-file sizes and times of every `--scatter` codec, for plotting ratio against file size.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scatter: Vec<ScatterPoint>,
+    /// Per-file timings behind the gzip stages; too bulky for JSON, so only `--output-parquet` writes them.
+    #[serde(skip)]
+    pub files: Vec<FileMeasurement>,
+    /// Ctrl-C stopped the run early: the step it was in finished, later optional steps are missing.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    /// `--object-overhead`: bytes of request or metadata overhead counted per stored object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_overhead: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CorpusInfo {
+    pub files: usize,
+    pub bytes: u64,
+    pub generation_secs: f64,
+    /// Input files skipped because they vanished or were unreadable.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    /// Files in the full corpus when only a `--sample` or `--limit` subset was run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampled_from: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<CorpusStats>,
+}
+
+/// Shape of the corpus, measured before any codec runs.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CorpusStats {
+    pub mean_bytes: f64,
+    pub median_bytes: u64,
+    pub p99_bytes: u64,
+    pub max_bytes: u64,
+    /// Files that parse as a single JSON document.
+    pub json_files: usize,
+    /// Files whose every non-blank line is a JSON document.
+    pub ndjson_files: usize,
+    pub other_files: usize,
+    /// Order-0 Shannon entropy of the sampled bytes, in bits per byte.
+    pub entropy_bits: f64,
+    pub entropy_sample_bytes: u64,
+}
+
+impl fmt::Display for CorpusStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "files of mean {}, median {}, p99 {}, max {}; {} JSON, {} NDJSON, {} other; entropy {:.2} bits/byte",
+            format_bytes(self.mean_bytes as u64),
+            format_bytes(self.median_bytes),
+            format_bytes(self.p99_bytes),
+            format_bytes(self.max_bytes),
+            self.json_files,
+            self.ndjson_files,
+            self.other_files,
+            self.entropy_bits
+        )
+    }
+}
+
+/// One timed pass of the main comparison, e.g. per-file gzip compression.
+#[derive(Serialize, Deserialize)]
+pub struct Stage {
+    pub name: String,
+    pub codec: String,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub secs: f64,
+    /// Portion of `secs` spent in fsync, when a durability policy was active.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_secs: Option<f64>,
+    /// Portion of `secs` spent in `--fs-latency` round trips.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_secs: Option<f64>,
+    /// Threads the codec compressed on, when more than one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threads: Option<u32>,
+    /// BLAKE3 of the stage's compressed outputs, to check that runs are byte-reproducible.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_digest: Option<String>,
+    /// Objects the compressed output is stored as, e.g. one per file or one archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objects: Option<u64>,
+}
+
+impl Stage {
+    pub fn new(name: &str, codec: &str, bytes_in: u64, bytes_out: u64, time: Duration) -> Self {
+        Stage {
+            name: name.to_string(),
+            codec: codec.to_string(),
+            bytes_in,
+            bytes_out,
+            secs: time.as_secs_f64(),
+            sync_secs: None,
+            latency_secs: None,
+            threads: None,
+            output_digest: None,
+            objects: None,
+        }
+    }
+
+    pub fn with_sync(mut self, sync_time: Duration) -> Self {
+        if !sync_time.is_zero() {
+            self.sync_secs = Some(sync_time.as_secs_f64());
+        }
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        if !latency.is_zero() {
+            self.latency_secs = Some(latency.as_secs_f64());
+        }
+        self
+    }
+
+    pub fn with_threads(mut self, threads: u32) -> Self {
+        if threads > 1 {
+            self.threads = Some(threads);
+        }
+        self
+    }
+
+    pub fn with_digest(mut self, digest: String) -> Self {
+        self.output_digest = Some(digest);
+        self
+    }
+
+    pub fn with_objects(mut self, objects: u64) -> Self {
+        self.objects = Some(objects);
+        self
+    }
+
+    pub fn threads(&self) -> u32 {
+        self.threads.unwrap_or(1)
+    }
+
+    /// Compressed bytes plus `overhead` for each stored object, when the object count was recorded.
+    pub fn effective_bytes(&self, overhead: u64) -> Option<u64> {
+        Some(self.bytes_out + self.objects? * overhead)
+    }
+}
+
+/// One file's share of a per-file stage.
+#[cfg_attr(not(feature = "parquet"), allow(dead_code))]
+pub struct FileMeasurement {
+    pub stage: &'static str,
+    pub name: String,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    pub time: Duration,
+}
+
+/// One file compressed on its own by one `--scatter` codec, in memory.
+#[derive(Serialize, Deserialize)]
+pub struct ScatterPoint {
+    pub codec: String,
+    pub file: String,
+    pub original_bytes: u64,
+    pub compressed_bytes: u64,
+    pub compress_secs: f64,
+    pub decompress_secs: f64,
+}
+
+/// A codec measurement abandoned by `--stage-timeout`, with how far it got.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TimedOut {
+    /// The step it ran in, `sweep` or `matrix`.
+    pub step: String,
+    pub codec: String,
+    pub limit_secs: f64,
+    /// `compress` or `decompress`.
+    pub phase: String,
+    pub files_done: usize,
+    pub files: usize,
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) timed out after {:?} in {}, {} of {} files done",
+            self.codec,
+            self.step,
+            Duration::from_secs_f64(self.limit_secs),
+            self.phase,
+            self.files_done,
+            self.files
+        )
+    }
+}
+
+/// A codec measurement whose `--isolate` child process died without reporting.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Failure {
+    /// The step it ran in, `sweep` or `matrix`.
+    pub step: String,
+    pub codec: String,
+    /// How the child ended, e.g. `killed by SIGSEGV`.
+    pub reason: String,
+    /// The last lines the child wrote to stderr.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}) failed: {}", self.codec, self.step, self.reason)
+    }
+}
+
+/// One codec configuration of `--matrix` or `--sweep`.
+#[derive(Serialize, Deserialize)]
+pub struct MatrixCell {
+    pub family: String,
+    pub codec: String,
+    pub compressed_bytes: u64,
+    pub ratio: f64,
+    pub compress_secs: f64,
+    pub decompress_secs: f64,
+    pub compress_memory: Option<u64>,
+    pub decompress_memory: Option<u64>,
+}
+
+impl MatrixCell {
+    pub fn new(family: &str, codec: String, measurement: &Measurement, json_size: u64) -> Self {
+        MatrixCell {
+            family: family.to_string(),
+            codec,
+            compressed_bytes: measurement.compressed_size,
+            ratio: measurement.compressed_size as f64 / json_size as f64,
+            compress_secs: measurement.compress_time.as_secs_f64(),
+            decompress_secs: measurement.decompress_time.as_secs_f64(),
+            compress_memory: measurement.memory.map(|m| m.compress),
+            decompress_memory: measurement.memory.map(|m| m.decompress),
+        }
+    }
+}
+
+impl Results {
+    pub fn new(corpus: CorpusInfo) -> Self {
+        Results {
+            schema_version: SCHEMA_VERSION,
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            corpus,
+            stages: Vec::new(),
+            matrix: Vec::new(),
+            sweep: Vec::new(),
+            timeouts: Vec::new(),
+            failures: Vec::new(),
+            dedup: None,
+            scatter: Vec::new(),
+            files: Vec::new(),
+            partial: false,
+            object_overhead: None,
+        }
+    }
+
+    /// Reads results of any supported schema version, migrating older ones forward.
+    /// Gzip and zstd compressed files are decompressed first.
+    pub fn read(path: &Path) -> Result<Self> {
+        let data = artifact::read(path)?;
+        let value: Value = serde_json::from_slice(&data).with_context(|| format!("failed to parse {}", path.display()))?;
+        Self::from_value(value).with_context(|| format!("invalid results in {}", path.display()))
+    }
+
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        Self::from_value(serde_json::from_slice(bytes)?)
+    }
+
+    fn from_value(mut value: Value) -> Result<Self> {
+        migrate(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Writes the results as JSON, compressed if `path` ends in `.gz` or `.zst`.
+    pub fn write(&self, path: &Path) -> Result<artifact::Written> {
+        artifact::write(path, &serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// What deduplication saved, before compression.
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Dedup {
+    /// Entries written as references instead of content.
+    pub references: usize,
+    /// Content bytes those references did not store again.
+    pub saved_bytes: u64,
+    /// Time spent hashing the corpus to find the duplicates, kept out of the compression time.
+    #[serde(default)]
+    pub hash_secs: f64,
+}
+
+/// Upgrades results JSON of an older schema version to [`SCHEMA_VERSION`] in
+/// place and returns the version it had.
+pub fn migrate(value: &mut Value) -> Result<u32> {
+    let object = value.as_object_mut().context("results must be a JSON object")?;
+    let version = match object.get("schema_version") {
+        None => 1,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|&version| version >= 1)
+            .context("schema_version must be a positive integer")?,
+    };
+    ensure!(
+        version <= SCHEMA_VERSION,
+        "schema version {} is newer than this build reads (up to {}); update gz-vs-zstd",
+        version,
+        SCHEMA_VERSION
+    );
+    if version < 2 {
+        if let Some(Value::Object(dedup)) = object.get_mut("dedup") {
+            dedup.entry("hash_secs").or_insert(Value::from(0.0));
+        }
+    }
+    object.insert("schema_version".to_string(), Value::from(SCHEMA_VERSION));
+    Ok(version)
+}
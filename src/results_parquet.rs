@@ -0,0 +1,139 @@
+//! `--output-parquet`: the full measurement data of a run as one tidy Parquet table,
+//! for loading into DuckDB, pandas or polars.
+//!
+//! Every row is one measurement: a whole stage, one file within a per-file stage,
+//! one direction of a `--matrix` codec, or one direction of a `--scatter` codec on
+//! one file. Columns are
+//! `generated_at, record, stage, codec, file, bytes_in, bytes_out, secs, sync_secs`,
+//! with `file` and `sync_secs` null where they do not apply.
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::Result;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::data_type::{ByteArray, ByteArrayType, DataType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedColumnWriter, SerializedFileWriter};
+use parquet::schema::parser::parse_message_type;
+use crate::results::Results;
+
+const SCHEMA: &str = "
+message results {
+    REQUIRED BYTE_ARRAY generated_at (UTF8);
+    REQUIRED BYTE_ARRAY record (UTF8);
+    REQUIRED BYTE_ARRAY stage (UTF8);
+    REQUIRED BYTE_ARRAY codec (UTF8);
+    OPTIONAL BYTE_ARRAY file (UTF8);
+    REQUIRED INT64 bytes_in;
+    REQUIRED INT64 bytes_out;
+    REQUIRED DOUBLE secs;
+    OPTIONAL DOUBLE sync_secs;
+}
+";
+
+struct Row<'a> {
+    record: &'static str,
+    stage: &'a str,
+    codec: &'a str,
+    file: Option<&'a str>,
+    bytes_in: u64,
+    bytes_out: u64,
+    secs: f64,
+    sync_secs: Option<f64>,
+}
+
+fn rows(results: &Results) -> Vec<Row<'_>> {
+    let mut rows =
+        Vec::with_capacity(results.stages.len() + results.files.len() + 2 * results.matrix.len() + 2 * results.scatter.len());
+    for stage in &results.stages {
+        rows.push(Row {
+            record: "stage",
+            stage: &stage.name,
+            codec: &stage.codec,
+            file: None,
+            bytes_in: stage.bytes_in,
+            bytes_out: stage.bytes_out,
+            secs: stage.secs,
+            sync_secs: stage.sync_secs,
+        });
+    }
+    for file in &results.files {
+        // Per-file rows belong to a stage row; borrow its codec so the two always agree.
+        let codec = results.stages.iter().find(|stage| stage.name == file.stage).map_or("", |stage| stage.codec.as_str());
+        rows.push(Row {
+            record: "file",
+            stage: file.stage,
+            codec,
+            file: Some(&file.name),
+            bytes_in: file.bytes_in,
+            bytes_out: file.bytes_out,
+            secs: file.time.as_secs_f64(),
+            sync_secs: None,
+        });
+    }
+    for cell in &results.matrix {
+        let uncompressed = results.corpus.bytes;
+        for (stage, bytes_in, bytes_out, secs) in [
+            ("matrix_compress", uncompressed, cell.compressed_bytes, cell.compress_secs),
+            ("matrix_decompress", cell.compressed_bytes, uncompressed, cell.decompress_secs),
+        ] {
+            rows.push(Row { record: "matrix", stage, codec: &cell.codec, file: None, bytes_in, bytes_out, secs, sync_secs: None });
+        }
+    }
+    for point in &results.scatter {
+        for (stage, bytes_in, bytes_out, secs) in [
+            ("scatter_compress", point.original_bytes, point.compressed_bytes, point.compress_secs),
+            ("scatter_decompress", point.compressed_bytes, point.original_bytes, point.decompress_secs),
+        ] {
+            rows.push(Row { record: "scatter", stage, codec: &point.codec, file: Some(&point.file), bytes_in, bytes_out, secs, sync_secs: None });
+        }
+    }
+    rows
+}
+
+/// Writes one column; definition levels are only passed for OPTIONAL columns.
+pub fn write_column<T: DataType>(column: &mut SerializedColumnWriter<'_>, values: Vec<Option<T::T>>) -> Result<()> {
+    let levels: Vec<i16> = values.iter().map(|value| value.is_some() as i16).collect();
+    let present: Vec<T::T> = values.into_iter().flatten().collect();
+    let writer = column.typed::<T>();
+    let levels = (writer.get_descriptor().max_def_level() > 0).then_some(levels);
+    writer.write_batch(&present, levels.as_deref(), None)?;
+    Ok(())
+}
+
+fn text(value: Option<&str>) -> Option<ByteArray> {
+    value.map(|value| ByteArray::from(value.as_bytes().to_vec()))
+}
+
+/// Writes every measurement in `results` to `path` as a single zstd-compressed row group.
+pub fn write(path: &Path, results: &Results) -> Result<usize> {
+    let rows = rows(results);
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .set_created_by(concat!("gz-vs-zstd ", env!("CARGO_PKG_VERSION")).to_string())
+        .build();
+    let mut writer = SerializedFileWriter::new(File::create(path)?, Arc::new(parse_message_type(SCHEMA)?), Arc::new(properties))?;
+
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        match index {
+            0 => write_column::<ByteArrayType>(&mut column, rows.iter().map(|_| text(Some(&results.generated_at))).collect())?,
+            1 => write_column::<ByteArrayType>(&mut column, rows.iter().map(|row| text(Some(row.record))).collect())?,
+            2 => write_column::<ByteArrayType>(&mut column, rows.iter().map(|row| text(Some(row.stage))).collect())?,
+            3 => write_column::<ByteArrayType>(&mut column, rows.iter().map(|row| text(Some(row.codec))).collect())?,
+            4 => write_column::<ByteArrayType>(&mut column, rows.iter().map(|row| text(row.file)).collect())?,
+            5 => write_column::<Int64Type>(&mut column, rows.iter().map(|row| Some(row.bytes_in as i64)).collect())?,
+            6 => write_column::<Int64Type>(&mut column, rows.iter().map(|row| Some(row.bytes_out as i64)).collect())?,
+            7 => write_column::<DoubleType>(&mut column, rows.iter().map(|row| Some(row.secs)).collect())?,
+            8 => write_column::<DoubleType>(&mut column, rows.iter().map(|row| row.sync_secs).collect())?,
+            _ => unreachable!("schema has nine columns"),
+        }
+        column.close()?;
+        index += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(rows.len())
+}
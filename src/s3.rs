@@ -0,0 +1,279 @@
+//! `--input s3://bucket/prefix`: lists and downloads objects from an S3-compatible
+//! endpoint into the workspace, signing requests with AWS Signature Version 4.
+//!
+//! Configuration follows the AWS CLI environment variables: `AWS_ACCESS_KEY_ID`,
+//! `AWS_SECRET_ACCESS_KEY`, optional `AWS_SESSION_TOKEN`, `AWS_REGION` (default
+//! `us-east-1`) and `AWS_ENDPOINT_URL` for non-AWS endpoints. Requests are
+//! anonymous when no credentials are set. Buckets are addressed path-style.
+//!
+//! Objects are not streamed into the stages: every stage reads the corpus from
+//! files in the workspace, so the objects are downloaded there first. The
+//! download is timed on its own, and the stages then measure local reads, not
+//! reads from object storage.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::time::Instant;
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::{Digest, Sha256};
+use crate::input::{SkippedFile, Snapshot};
+use crate::progress_bar;
+use crate::workspace::output_dir;
+
+/// SHA-256 of an empty body; every request here is a GET.
+const EMPTY_PAYLOAD_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+struct Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+struct Client {
+    agent: ureq::Agent,
+    endpoint: String,
+    host: String,
+    region: String,
+    credentials: Option<Credentials>,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes everything but the SigV4 unreserved characters (and `/` when `keep_slash`).
+fn uri_encode(s: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(byte as char),
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Text of every `<tag>…</tag>` element in `xml`, with the predefined entities decoded.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    xml.split(open.as_str()).skip(1).filter_map(|rest| {
+        let value = &rest[..rest.find(close.as_str())?];
+        Some(
+            value
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        )
+    }).collect()
+}
+
+impl Client {
+    fn from_env() -> Result<Self> {
+        let region = env::var("AWS_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+        let endpoint = env::var("AWS_ENDPOINT_URL")
+            .unwrap_or_else(|_| format!("https://s3.{}.amazonaws.com", region))
+            .trim_end_matches('/')
+            .to_string();
+        let host = endpoint
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .with_context(|| format!("AWS_ENDPOINT_URL must include a scheme, got {:?}", endpoint))?
+            .split('/')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let credentials = match (env::var("AWS_ACCESS_KEY_ID"), env::var("AWS_SECRET_ACCESS_KEY")) {
+            (Ok(access_key), Ok(secret_key)) => Some(Credentials {
+                access_key,
+                secret_key,
+                session_token: env::var("AWS_SESSION_TOKEN").ok(),
+            }),
+            _ => None,
+        };
+        Ok(Client { agent: ureq::AgentBuilder::new().build(), endpoint, host, region, credentials })
+    }
+
+    /// Sends a signed GET for `path` (already starting with `/bucket`) with `query` parameters.
+    fn get(&self, path: &str, query: &[(&str, &str)]) -> std::result::Result<ureq::Response, Box<ureq::Error>> {
+        let canonical_uri = uri_encode(path, true);
+        let mut query: Vec<(String, String)> = query.iter().map(|(k, v)| (uri_encode(k, false), uri_encode(v, false))).collect();
+        query.sort();
+        let canonical_query = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>().join("&");
+
+        let url = if canonical_query.is_empty() {
+            format!("{}{}", self.endpoint, canonical_uri)
+        } else {
+            format!("{}{}?{}", self.endpoint, canonical_uri, canonical_query)
+        };
+        let mut request = self.agent.get(&url);
+        let Some(credentials) = &self.credentials else {
+            return request.call().map_err(Box::new);
+        };
+
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let mut headers = vec![
+            ("host", self.host.clone()),
+            ("x-amz-content-sha256", EMPTY_PAYLOAD_SHA256.to_string()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value.trim())).collect();
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "GET\n{}\n{}\n{}\n{}\n{}",
+            canonical_uri, canonical_query, canonical_headers, signed_headers, EMPTY_PAYLOAD_SHA256
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = hmac(format!("AWS4{}", credentials.secret_key).as_bytes(), &date);
+        let key = hmac(&key, &self.region);
+        let key = hmac(&key, "s3");
+        let key = hmac(&key, "aws4_request");
+        let signature = hex(&hmac(&key, &string_to_sign));
+
+        // ureq sends `host` itself.
+        for (name, value) in &headers[1..] {
+            request = request.set(name, value);
+        }
+        request
+            .set(
+                "Authorization",
+                &format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    credentials.access_key, scope, signed_headers, signature
+                ),
+            )
+            .call()
+            .map_err(Box::new)
+    }
+
+    /// Every object key under `prefix`, following ListObjectsV2 continuation tokens.
+    fn list(&self, bucket: &str, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token));
+            }
+            let body = self
+                .get(&format!("/{}", bucket), &query)
+                .with_context(|| format!("failed to list s3://{}/{}", bucket, prefix))?
+                .into_string()?;
+            keys.extend(xml_values(&body, "Key"));
+            token = xml_values(&body, "NextContinuationToken").pop();
+            if xml_values(&body, "IsTruncated").first().map(String::as_str) != Some("true") || token.is_none() {
+                return Ok(keys);
+            }
+        }
+    }
+}
+
+/// Workspace name for `key`: the part after `prefix`, rejected if it could escape the workspace.
+fn local_name(key: &str, prefix: &str) -> Option<String> {
+    let name = key.strip_prefix(prefix).unwrap_or(key).trim_start_matches('/');
+    let safe = !name.is_empty() && name.split('/').all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    safe.then(|| name.to_string())
+}
+
+/// Downloads every object under `s3://bucket/prefix` into the workspace.
+///
+/// Objects deleted or made unreadable between listing and download are
+/// skipped and reported, like vanished files in a local directory.
+pub fn snapshot(bucket: &str, prefix: &str) -> Result<Snapshot> {
+    let client = Client::from_env()?;
+    let start = Instant::now();
+    let keys: Vec<String> = client.list(bucket, prefix)?.into_iter().filter(|key| !key.ends_with('/')).collect();
+    if keys.is_empty() {
+        bail!("no objects under s3://{}/{}", bucket, prefix);
+    }
+
+    let pb = progress_bar(keys.len());
+    let mut names = Vec::with_capacity(keys.len());
+    let mut skipped = Vec::new();
+    for key in keys {
+        pb.inc(1);
+        let Some(name) = local_name(&key, prefix) else {
+            skipped.push(SkippedFile { name: key, reason: "key cannot be used as a relative file name".to_string() });
+            continue;
+        };
+        let response = match client.get(&format!("/{}/{}", bucket, key), &[]) {
+            Ok(response) => response,
+            Err(err) if matches!(*err, ureq::Error::Status(403 | 404, _)) => {
+                let reason = err.to_string();
+                pb.println(format!("⚠️  Skipping {}: {}", key, reason));
+                skipped.push(SkippedFile { name: key, reason });
+                continue;
+            }
+            Err(err) => return Err(err).with_context(|| format!("failed to download s3://{}/{}", bucket, key)),
+        };
+
+        let path = output_dir().join(&name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Stream straight to disk so large objects never sit in memory.
+        let mut file = BufWriter::new(File::create(&path)?);
+        io::copy(&mut response.into_reader(), &mut file)?;
+        file.flush()?;
+        names.push(name);
+    }
+    pb.finish_with_message("Download complete!");
+    names.sort();
+
+    Ok(Snapshot { names, links: Vec::new(), skipped, elapsed: start.elapsed() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uri_encode_keeps_only_unreserved_characters() {
+        assert_eq!(uri_encode("logs/2025 07/a+b~c_d-e.json", true), "logs/2025%2007/a%2Bb~c_d-e.json");
+        assert_eq!(uri_encode("logs/a=b", false), "logs%2Fa%3Db");
+        assert_eq!(uri_encode("é", false), "%C3%A9");
+    }
+
+    #[test]
+    fn xml_values_returns_every_element_decoded() {
+        let xml = "<ListBucketResult><Contents><Key>a.json</Key></Contents>\
+                   <Contents><Key>b &amp; c&lt;1&gt;.json</Key></Contents><IsTruncated>false</IsTruncated></ListBucketResult>";
+        assert_eq!(xml_values(xml, "Key"), ["a.json", "b & c<1>.json"]);
+        assert_eq!(xml_values(xml, "IsTruncated"), ["false"]);
+        assert!(xml_values(xml, "NextContinuationToken").is_empty());
+        assert!(xml_values("<Key>unterminated", "Key").is_empty());
+    }
+
+    #[test]
+    fn local_name_strips_the_prefix_and_rejects_escapes() {
+        assert_eq!(local_name("logs/2025/a.json", "logs/").as_deref(), Some("2025/a.json"));
+        assert_eq!(local_name("logs/2025/a.json", "logs").as_deref(), Some("2025/a.json"));
+        assert_eq!(local_name("a.json", "").as_deref(), Some("a.json"));
+        for key in ["logs/", "logs/../a.json", "logs/./a.json", "logs/a//b.json"] {
+            assert_eq!(local_name(key, "logs/"), None, "{:?}", key);
+        }
+    }
+}
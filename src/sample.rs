@@ -0,0 +1,66 @@
+//! `--sample` / `--limit`: run the whole pipeline on a seeded random subset of
+//! the corpus and scale the core numbers back up to the full corpus.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::format_bytes;
+use crate::render::{self, Align, Table};
+use crate::results::Results;
+
+/// Parses `--sample` as a percentage of the corpus, e.g. `1%` or `0.5`.
+pub fn parse_percent(s: &str) -> std::result::Result<f64, String> {
+    let percent: f64 = s.trim().trim_end_matches('%').parse().map_err(|_| format!("invalid percentage `{}`", s))?;
+    if !(percent > 0.0 && percent <= 100.0) {
+        return Err(format!("percentage must be in (0, 100], got {}", percent));
+    }
+    Ok(percent / 100.0)
+}
+
+/// How much of the corpus to keep.
+#[derive(Clone, Copy)]
+pub enum Subset {
+    Fraction(f64),
+    Limit(usize),
+}
+
+impl Subset {
+    pub fn from_args(percent: Option<f64>, limit: Option<usize>) -> Option<Self> {
+        percent.map(Subset::Fraction).or(limit.map(Subset::Limit))
+    }
+}
+
+/// Picks the files to keep, in their original order; the same seed always picks the same files.
+pub fn choose(names: Vec<String>, subset: Subset, seed: u64) -> Vec<String> {
+    let keep = match subset {
+        Subset::Fraction(fraction) => ((names.len() as f64 * fraction).ceil() as usize).max(1),
+        Subset::Limit(limit) => limit,
+    }
+    .min(names.len());
+    let mut indices = rand::seq::index::sample(&mut StdRng::seed_from_u64(seed), names.len(), keep).into_vec();
+    indices.sort_unstable();
+    let mut names: Vec<Option<String>> = names.into_iter().map(Some).collect();
+    indices.into_iter().filter_map(|index| names[index].take()).collect()
+}
+
+/// Prints every stage of `results` scaled by `factor`, the full corpus over the sample.
+///
+/// Sizes and times are scaled linearly. Solid archives usually compress the
+/// full corpus slightly better than that, so their scaled size is an upper bound.
+pub fn print_extrapolated(results: &Results, factor: f64, full_files: usize) {
+    let mut table = Table::new(&[("Stage", Align::Left), ("Codec", Align::Left), ("Output", Align::Right), ("Time", Align::Right)]);
+    for stage in &results.stages {
+        table.row(vec![
+            stage.name.clone(),
+            stage.codec.clone(),
+            format_bytes((stage.bytes_out as f64 * factor) as u64),
+            render::seconds(stage.secs * factor),
+        ]);
+    }
+    print!("{}", table.terminal());
+    println!(
+        "  Scaled ×{:.1} to {} files of about {}.",
+        factor,
+        full_files,
+        format_bytes((results.corpus.bytes as f64 * factor) as u64)
+    );
+}
@@ -0,0 +1,88 @@
+//! `--scatter`: every file compressed on its own by each listed codec, keeping
+//! the per-file numbers so ratio can be plotted against file size.
+
+use std::io::Read;
+use std::time::Instant;
+use anyhow::{ensure, Result};
+use crate::codec::CodecSpec;
+use crate::interrupt;
+use crate::results::ScatterPoint;
+use crate::{progress_bar, render};
+
+/// Size buckets of the console summary: quartiles of the file sizes.
+const BUCKETS: usize = 4;
+
+/// Compresses and decompresses each file of `files` (named `names`) with every codec of `specs`.
+pub fn run(specs: &[CodecSpec], names: &[String], files: &[Vec<u8>]) -> Result<Vec<ScatterPoint>> {
+    let pb = progress_bar(specs.len() * files.len());
+    let mut points = Vec::with_capacity(specs.len() * files.len());
+    for spec in specs {
+        if interrupt::requested() {
+            break;
+        }
+        let codec = spec.build();
+        pb.set_message(codec.name());
+        for (name, file) in names.iter().zip(files) {
+            let start = Instant::now();
+            let frame = codec.compress_bytes(file)?;
+            let compress_time = start.elapsed();
+
+            let start = Instant::now();
+            let mut input = frame.as_slice();
+            let mut output = Vec::with_capacity(file.len());
+            codec.decoder(&mut input)?.read_to_end(&mut output)?;
+            let decompress_time = start.elapsed();
+            ensure!(output == *file, "{} round trip mismatch on {}", codec.name(), name);
+
+            points.push(ScatterPoint {
+                codec: codec.name(),
+                file: name.clone(),
+                original_bytes: file.len() as u64,
+                compressed_bytes: frame.len() as u64,
+                compress_secs: compress_time.as_secs_f64(),
+                decompress_secs: decompress_time.as_secs_f64(),
+            });
+            pb.inc(1);
+        }
+    }
+    interrupt::finish(&pb, "Per-file scatter complete!");
+    Ok(points)
+}
+
+/// Ratio of each codec within size quartiles of the corpus, the gist of the full scatter.
+pub fn print(points: &[ScatterPoint]) {
+    let mut codecs: Vec<&str> = Vec::new();
+    for point in points {
+        if !codecs.contains(&point.codec.as_str()) {
+            codecs.push(&point.codec);
+        }
+    }
+    // Every codec saw the same files, so the first codec's points give the size bounds.
+    let mut sizes: Vec<u64> = points.iter().filter(|point| point.codec == codecs[0]).map(|point| point.original_bytes).collect();
+    sizes.sort_unstable();
+    let mut bounds: Vec<u64> = (1..=BUCKETS).map(|i| sizes[(sizes.len() * i).div_ceil(BUCKETS) - 1]).collect();
+    // Files of equal size can fill several quartiles; those collapse into one bucket.
+    bounds.dedup();
+    let bucket = |size: u64| bounds.iter().position(|&bound| size <= bound).unwrap_or(bounds.len() - 1);
+
+    print!("  {:<16}", "Codec");
+    let mut lower = sizes[0];
+    for &bound in &bounds {
+        print!(" {:>20}", format!("{}–{} B", lower, bound));
+        lower = bound + 1;
+    }
+    println!();
+    for codec in codecs {
+        let mut totals = vec![(0u64, 0u64); bounds.len()];
+        for point in points.iter().filter(|point| point.codec == codec) {
+            let total = &mut totals[bucket(point.original_bytes)];
+            total.0 += point.original_bytes;
+            total.1 += point.compressed_bytes;
+        }
+        print!("  {:<16}", codec);
+        for (original, compressed) in totals {
+            print!(" {:>20}", render::percent(compressed as f64 / original.max(1) as f64));
+        }
+        println!();
+    }
+}
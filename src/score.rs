@@ -0,0 +1,100 @@
+//! `--weights`: declares the winner by a weighted score of size and speed
+//! instead of by size alone.
+//!
+//! Each component scores the best contender 1.0 and every other one the best
+//! value divided by its own, so a codec twice as slow as the fastest scores 0.5.
+//! The total is the weighted sum, with the weights scaled to add up to 1.
+
+use std::str::FromStr;
+use std::time::Duration;
+use anyhow::{bail, ensure, Result};
+use crate::format_bytes;
+use crate::render::{self, Align, Table};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Weights {
+    pub size: f64,
+    pub compress_time: f64,
+    pub decompress_time: f64,
+}
+
+impl FromStr for Weights {
+    type Err = anyhow::Error;
+
+    /// Parses `size=0.5,compress_time=0.2,decompress_time=0.3`; left out components weigh 0.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut weights = Weights::default();
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                bail!("expected COMPONENT=WEIGHT, got `{}`", part);
+            };
+            let value: f64 = value.trim().parse().map_err(|_| anyhow::anyhow!("invalid weight `{}`", value))?;
+            ensure!(value >= 0.0 && value.is_finite(), "weights must not be negative, got {} for {}", value, key);
+            match key.trim() {
+                "size" => weights.size = value,
+                "compress_time" => weights.compress_time = value,
+                "decompress_time" => weights.decompress_time = value,
+                other => bail!("unknown component `{}`; expected size, compress_time or decompress_time", other),
+            }
+        }
+        ensure!(weights.total() > 0.0, "at least one weight must be positive");
+        Ok(weights)
+    }
+}
+
+impl Weights {
+    fn total(&self) -> f64 {
+        self.size + self.compress_time + self.decompress_time
+    }
+}
+
+/// One side of the verdict, e.g. per-file gzip.
+pub struct Contender {
+    pub label: &'static str,
+    pub size: u64,
+    pub compress_time: Duration,
+    pub decompress_time: Duration,
+}
+
+/// One component's score for every contender, 1.0 for the best.
+fn component_scores(contenders: &[Contender], value: impl Fn(&Contender) -> f64) -> Vec<f64> {
+    let best = contenders.iter().map(&value).fold(f64::INFINITY, f64::min);
+    contenders.iter().map(|contender| if value(contender) > 0.0 { best / value(contender) } else { 1.0 }).collect()
+}
+
+/// Prints every contender's component and weighted scores and returns the index of the winner.
+pub fn print(contenders: &[Contender], weights: Weights) -> usize {
+    let components = [
+        ("size", weights.size, component_scores(contenders, |c| c.size as f64)),
+        ("compress", weights.compress_time, component_scores(contenders, |c| c.compress_time.as_secs_f64())),
+        ("decompress", weights.decompress_time, component_scores(contenders, |c| c.decompress_time.as_secs_f64())),
+    ];
+    let totals: Vec<f64> = (0..contenders.len())
+        .map(|i| components.iter().map(|(_, weight, scores)| weight / weights.total() * scores[i]).sum())
+        .collect();
+
+    println!(
+        "  Weights: size {:.2}, compress time {:.2}, decompress time {:.2}",
+        weights.size / weights.total(),
+        weights.compress_time / weights.total(),
+        weights.decompress_time / weights.total()
+    );
+    let mut table = Table::new(&[
+        ("", Align::Left),
+        ("Size", Align::Right),
+        ("Compress", Align::Right),
+        ("Decompress", Align::Right),
+        ("Score", Align::Right),
+    ]);
+    for (i, contender) in contenders.iter().enumerate() {
+        table.row(vec![
+            contender.label.to_string(),
+            format!("{} ({:.3})", format_bytes(contender.size), components[0].2[i]),
+            format!("{} ({:.3})", render::duration(contender.compress_time), components[1].2[i]),
+            format!("{} ({:.3})", render::duration(contender.decompress_time), components[2].2[i]),
+            format!("{:.3}", totals[i]),
+        ]);
+    }
+    print!("{}", table.terminal());
+    totals.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).map_or(0, |(i, _)| i)
+}
@@ -0,0 +1,77 @@
+//! `search`: zstdgrep-style regex search over the entries of a solid archive.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use anyhow::Result;
+use regex::bytes::Regex;
+use crate::archive;
+use crate::index::{self, Index, TimeRange};
+
+/// Prints every line of every entry matching `pattern` as `name:line:text`, or
+/// only the matching entry names with `files_only`. Returns the number of matches.
+///
+/// Nothing is written to disk and entries are tested line by line as they
+/// stream past. The solid archive is one zstd stream, so every entry is decoded;
+/// an indexed archive (`--index`, with its `.idx` sidecar next to it) is read
+/// frame by frame, skipping frames whose timestamps all fall outside `range`.
+pub fn run(pattern: &Regex, archive_path: &Path, dict: Option<&[u8]>, files_only: bool, range: &TimeRange) -> Result<u64> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut matches = 0;
+    let (mut line, mut buffered) = (Vec::new(), Vec::new());
+
+    let mut search_entry = |entry: &archive::Entry, content: &mut dyn Read| -> Result<()> {
+        if entry.is_symlink {
+            return Ok(());
+        }
+        let mut content: Box<dyn BufRead + '_> = if range.is_set() {
+            buffered.clear();
+            content.read_to_end(&mut buffered)?;
+            if !range.admits(&buffered) {
+                return Ok(());
+            }
+            Box::new(buffered.as_slice())
+        } else {
+            Box::new(BufReader::new(content))
+        };
+        let mut line_number = 0;
+        loop {
+            line.clear();
+            if content.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_number += 1;
+            if !pattern.is_match(&line) {
+                continue;
+            }
+            matches += 1;
+            if files_only {
+                writeln!(out, "{}", entry.name)?;
+                break;
+            }
+            write!(out, "{}:{}:", entry.name, line_number)?;
+            out.write_all(line.strip_suffix(b"\n").unwrap_or(&line))?;
+            writeln!(out)?;
+        }
+        Ok(())
+    };
+
+    let index_path = index::sidecar(archive_path);
+    let result = if index_path.exists() {
+        let index = Index::read(&index_path)?;
+        index
+            .frames
+            .iter()
+            .filter(|frame| range.overlaps(frame))
+            .try_for_each(|frame| index::read_frame(archive_path, frame, &mut search_entry))
+    } else {
+        archive::for_each_entry(archive_path, dict, true, search_entry)
+    };
+    match result {
+        // The reader went away, e.g. `search ... | head`; that is not an error.
+        Err(err) if err.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) => {}
+        other => other?,
+    }
+
+    Ok(matches)
+}
@@ -0,0 +1,245 @@
+//! `soak`: a long-running synthetic event stream, compressed in rolling chunks
+//! with each codec, to show what a short batch benchmark cannot: whether the
+//! codecs keep up with the stream, and whether ratio or memory drift over hours.
+//!
+//! Every chunk holds `rate × chunk` events as NDJSON and is compressed as one
+//! stream per codec, like a rotated log file, then decompressed and checked.
+//! The stream is paced in real time: a chunk that takes longer to generate and
+//! compress than it took to arrive puts the soak behind, which is reported as lag.
+
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use anyhow::{ensure, Result};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+use crate::artifact;
+use crate::bench::throughput;
+use crate::codec::{Codec, CodecSpec};
+use crate::corpus::generate_json_with;
+use crate::render::{Align, Table};
+use crate::{format_bytes, render};
+
+/// Parses durations such as `500ms`, `45s`, `30m` or `2h`; a bare number is seconds.
+pub fn parse_duration(s: &str) -> std::result::Result<Duration, String> {
+    let s = s.trim();
+    let digits = s.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let value: f64 = digits.parse().map_err(|_| format!("invalid duration `{}`", s))?;
+    let secs = match &s[digits.len()..] {
+        "ms" => value / 1000.0,
+        "" | "s" => value,
+        "m" => value * 60.0,
+        "h" => value * 3600.0,
+        unit => return Err(format!("unknown duration unit `{}`", unit)),
+    };
+    if !(secs > 0.0 && secs.is_finite()) {
+        return Err(format!("duration must be positive, got `{}`", s));
+    }
+    Ok(Duration::from_secs_f64(secs))
+}
+
+pub struct Options {
+    /// Events generated per second of the stream.
+    pub rate: u64,
+    pub duration: Duration,
+    /// Stream time covered by each compressed chunk.
+    pub chunk: Duration,
+    /// How often a window of chunks is summarized.
+    pub interval: Duration,
+    pub codecs: Vec<CodecSpec>,
+    pub seed: u64,
+    pub json: Option<PathBuf>,
+}
+
+/// One codec over one reporting window.
+#[derive(Serialize, Default, Clone)]
+struct CodecWindow {
+    codec: String,
+    input_bytes: u64,
+    compressed_bytes: u64,
+    compress_secs: f64,
+    decompress_secs: f64,
+}
+
+#[derive(Serialize)]
+struct Window {
+    /// Seconds since the soak started, at the end of the window.
+    elapsed_secs: f64,
+    chunks: usize,
+    events: u64,
+    generation_secs: f64,
+    /// How far the soak was behind the stream at the end of the window.
+    lag_secs: f64,
+    /// Resident memory at the end of the window, where the platform reports it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rss_bytes: Option<u64>,
+    codecs: Vec<CodecWindow>,
+}
+
+/// Resident set size of this process, from `/proc/self/status` on Linux.
+fn rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.trim_start_matches("VmRSS:").trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kb * 1024)
+}
+
+fn ratio(window: &CodecWindow) -> f64 {
+    window.compressed_bytes as f64 / window.input_bytes.max(1) as f64
+}
+
+fn compress_chunk(codec: &dyn Codec, chunk: &[u8], window: &mut CodecWindow) -> Result<()> {
+    let start = Instant::now();
+    let frame = codec.compress_bytes(chunk)?;
+    window.compress_secs += start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    let mut output = Vec::with_capacity(chunk.len());
+    codec.decoder(&mut frame.as_slice())?.read_to_end(&mut output)?;
+    window.decompress_secs += start.elapsed().as_secs_f64();
+    ensure!(output == chunk, "{} round trip mismatch during the soak", codec.name());
+
+    window.input_bytes += chunk.len() as u64;
+    window.compressed_bytes += frame.len() as u64;
+    Ok(())
+}
+
+fn print_window(window: &Window) {
+    let rss = window.rss_bytes.map_or("-".to_string(), format_bytes);
+    println!(
+        "[{:>10}] {} events, lag {}, RSS {}",
+        render::seconds(window.elapsed_secs),
+        window.events,
+        render::seconds(window.lag_secs),
+        rss
+    );
+    for codec in &window.codecs {
+        println!(
+            "  {:<16} {:>9} {:>14} {:>14}",
+            codec.codec,
+            render::percent(ratio(codec)),
+            render::mb_per_sec(throughput(codec.input_bytes, Duration::from_secs_f64(codec.compress_secs))),
+            render::mb_per_sec(throughput(codec.input_bytes, Duration::from_secs_f64(codec.decompress_secs)))
+        );
+    }
+}
+
+/// Sustained numbers over the whole soak, and how the last window compares to the first.
+fn print_summary(windows: &[Window], names: &[String]) {
+    let (Some(first), Some(last)) = (windows.first(), windows.last()) else {
+        return;
+    };
+    println!("\n📊 SOAK RESULTS ({} windows, {})", windows.len(), render::seconds(last.elapsed_secs));
+    println!("=====================================");
+    let mut table = Table::new(&[
+        ("Codec", Align::Left),
+        ("Ratio", Align::Right),
+        ("Compress", Align::Right),
+        ("Slowest window", Align::Right),
+        ("Decompress", Align::Right),
+        ("Drift", Align::Right),
+    ]);
+    for (i, name) in names.iter().enumerate() {
+        let mut total = CodecWindow::default();
+        let mut slowest = f64::INFINITY;
+        for window in windows {
+            let codec = &window.codecs[i];
+            total.input_bytes += codec.input_bytes;
+            total.compressed_bytes += codec.compressed_bytes;
+            total.compress_secs += codec.compress_secs;
+            total.decompress_secs += codec.decompress_secs;
+            slowest = slowest.min(throughput(codec.input_bytes, Duration::from_secs_f64(codec.compress_secs)));
+        }
+        table.row(vec![
+            name.clone(),
+            render::percent(ratio(&total)),
+            render::mb_per_sec(throughput(total.input_bytes, Duration::from_secs_f64(total.compress_secs))),
+            render::mb_per_sec(slowest),
+            render::mb_per_sec(throughput(total.input_bytes, Duration::from_secs_f64(total.decompress_secs))),
+            format!("{:+.2}pp", (ratio(&last.codecs[i]) - ratio(&first.codecs[i])) * 100.0),
+        ]);
+    }
+    print!("{}", table.terminal());
+    let max_lag = windows.iter().map(|window| window.lag_secs).fold(0.0, f64::max);
+    if max_lag > 0.0 {
+        println!("  ⚠️  Fell behind the stream by up to {}", render::seconds(max_lag));
+    } else {
+        println!("  Kept up with the stream throughout");
+    }
+    let rss: Vec<u64> = windows.iter().filter_map(|window| window.rss_bytes).collect();
+    if let (Some(start), Some(end), Some(peak)) = (rss.first(), rss.last(), rss.iter().max()) {
+        println!("  RSS: {} after the first window, {} at the end, {} peak", format_bytes(*start), format_bytes(*end), format_bytes(*peak));
+    }
+}
+
+/// Streams events at `options.rate` for `options.duration`, compressing each chunk with every codec.
+pub fn run(options: &Options) -> Result<()> {
+    ensure!(options.rate > 0, "--rate must be at least 1 event per second");
+    ensure!(options.chunk <= options.duration, "--chunk must not be longer than --duration");
+    let codecs: Vec<Box<dyn Codec>> = options.codecs.iter().map(CodecSpec::build).collect();
+    let names: Vec<String> = codecs.iter().map(|codec| codec.name()).collect();
+    let events_per_chunk = ((options.rate as f64 * options.chunk.as_secs_f64()).round() as u64).max(1);
+    println!(
+        "🌊 Soaking {} for {} at {} events/s, in chunks of {} events ({}, seed {})",
+        names.join(", "),
+        render::duration(options.duration),
+        options.rate,
+        events_per_chunk,
+        render::duration(options.chunk),
+        options.seed
+    );
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let start = Instant::now();
+    let mut windows = Vec::new();
+    let mut chunk = Vec::new();
+    let (mut stream_time, mut window_end) = (Duration::ZERO, options.interval);
+    let new_window = || names.iter().map(|name| CodecWindow { codec: name.clone(), ..CodecWindow::default() }).collect::<Vec<_>>();
+    let (mut window_codecs, mut chunks, mut events, mut generation) = (new_window(), 0, 0, Duration::ZERO);
+
+    while stream_time < options.duration {
+        let generation_start = Instant::now();
+        chunk.clear();
+        for _ in 0..events_per_chunk {
+            serde_json::to_writer(&mut chunk, &generate_json_with(&mut rng))?;
+            chunk.push(b'\n');
+        }
+        generation += generation_start.elapsed();
+        for (codec, window) in codecs.iter().zip(&mut window_codecs) {
+            compress_chunk(codec.as_ref(), &chunk, window)?;
+        }
+        chunks += 1;
+        events += events_per_chunk;
+        stream_time += options.chunk;
+
+        // Wait for the stream to catch up, unless the work has already fallen behind it.
+        let lag = start.elapsed().saturating_sub(stream_time);
+        if lag.is_zero() {
+            std::thread::sleep(stream_time - start.elapsed().min(stream_time));
+        }
+
+        if stream_time >= window_end || stream_time >= options.duration {
+            let window = Window {
+                elapsed_secs: stream_time.as_secs_f64(),
+                chunks,
+                events,
+                generation_secs: generation.as_secs_f64(),
+                lag_secs: lag.as_secs_f64(),
+                rss_bytes: rss_bytes(),
+                codecs: std::mem::replace(&mut window_codecs, new_window()),
+            };
+            print_window(&window);
+            windows.push(window);
+            (chunks, events, generation) = (0, 0, Duration::ZERO);
+            window_end += options.interval;
+        }
+    }
+
+    print_summary(&windows, &names);
+    if let Some(path) = &options.json {
+        let written = artifact::write(path, &serde_json::to_vec_pretty(&windows)?)?;
+        println!("\n💾 {} windows written to {} ({})", windows.len(), path.display(), written);
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! Characterization of the corpus, measured before any codec runs so every
+//! report starts with what was actually compressed.
+//!
+//! Every file is read once to tell JSON documents, NDJSON and anything else
+//! apart. Byte entropy is only taken over an evenly spaced sample of files;
+//! order-0 entropy ignores repetition, so it bounds what a coder without
+//! matches could do rather than what gzip or zstd will reach.
+
+use std::fs;
+use std::path::Path;
+use anyhow::Result;
+use serde::de::IgnoredAny;
+use crate::format_bytes;
+use crate::parse::is_blank;
+use crate::render;
+use crate::results::CorpusStats;
+
+/// Files whose bytes go into the entropy estimate, at most.
+const ENTROPY_SAMPLE_FILES: usize = 1000;
+
+enum Kind {
+    Json,
+    Ndjson,
+    Other,
+}
+
+fn kind(data: &[u8]) -> Kind {
+    if serde_json::from_slice::<IgnoredAny>(data).is_ok() {
+        return Kind::Json;
+    }
+    let mut lines = data.split(|&b| b == b'\n').filter(|line| !is_blank(line)).peekable();
+    if lines.peek().is_some() && lines.all(|line| serde_json::from_slice::<IgnoredAny>(line).is_ok()) {
+        Kind::Ndjson
+    } else {
+        Kind::Other
+    }
+}
+
+/// Shannon entropy of a byte histogram, in bits per byte.
+fn entropy(counts: &[u64; 256]) -> f64 {
+    let total: u64 = counts.iter().sum();
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Size at quantile `q` of `sorted`, by nearest rank.
+fn quantile(sorted: &[u64], q: f64) -> u64 {
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Measures the files `names` under `dir`.
+pub fn analyze(dir: &Path, names: &[String]) -> Result<CorpusStats> {
+    anyhow::ensure!(!names.is_empty(), "the corpus is empty");
+    let step = names.len().div_ceil(ENTROPY_SAMPLE_FILES);
+    let mut sizes = Vec::with_capacity(names.len());
+    let (mut json_files, mut ndjson_files, mut other_files) = (0, 0, 0);
+    let mut counts = [0u64; 256];
+    for (i, name) in names.iter().enumerate() {
+        let data = fs::read(dir.join(name))?;
+        sizes.push(data.len() as u64);
+        match kind(&data) {
+            Kind::Json => json_files += 1,
+            Kind::Ndjson => ndjson_files += 1,
+            Kind::Other => other_files += 1,
+        }
+        if i % step == 0 {
+            for &byte in &data {
+                counts[byte as usize] += 1;
+            }
+        }
+    }
+    sizes.sort_unstable();
+    Ok(CorpusStats {
+        mean_bytes: sizes.iter().sum::<u64>() as f64 / sizes.len() as f64,
+        median_bytes: quantile(&sizes, 0.5),
+        p99_bytes: quantile(&sizes, 0.99),
+        max_bytes: sizes[sizes.len() - 1],
+        json_files,
+        ndjson_files,
+        other_files,
+        entropy_bits: entropy(&counts),
+        entropy_sample_bytes: counts.iter().sum(),
+    })
+}
+
+pub fn print(stats: &CorpusStats, files: usize, bytes: u64) {
+    println!("  Files: {} ({})", files, format_bytes(bytes));
+    println!(
+        "  File size: mean {}, median {}, p99 {}, max {}",
+        format_bytes(stats.mean_bytes as u64),
+        format_bytes(stats.median_bytes),
+        format_bytes(stats.p99_bytes),
+        format_bytes(stats.max_bytes)
+    );
+    println!("  Content: {} JSON documents, {} NDJSON, {} other", stats.json_files, stats.ndjson_files, stats.other_files);
+    println!(
+        "  Entropy: {:.2} bits/byte over {} sampled (an order-0 coder could reach about {})",
+        stats.entropy_bits,
+        format_bytes(stats.entropy_sample_bytes),
+        render::percent(stats.entropy_bits / 8.0)
+    );
+}
@@ -0,0 +1,180 @@
+//! Archiving-strategy comparison: separates the effect of the codec (gzip vs zstd)
+//! from the effect of the container (one file per log vs one solid stream).
+//! With `--object-overhead` every stored object also costs that many bytes, so
+//! the container effect includes what thousands of small objects cost to store
+//! and transfer.
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use crate::codec::{Codec, Gzip, Zstd};
+use crate::overhead::{self, Stored};
+use crate::render::{self, Align, Table};
+use crate::{archive, corpus_size, format_bytes, generate_corpus, progress_bar};
+use crate::workspace::output_dir;
+
+const STRATEGY_DIR: &str = "strategies";
+
+struct StrategyResult {
+    label: String,
+    size: u64,
+    /// Files the strategy stores: one per log, or a single archive.
+    objects: u64,
+    time: Duration,
+}
+
+impl StrategyResult {
+    fn stored(&self) -> Stored {
+        Stored { label: self.label.clone(), objects: self.objects, payload: self.size }
+    }
+}
+
+/// Compresses every file on its own, returning the summed compressed size.
+fn per_file(codec: &dyn Codec, names: &[String], out_dir: &Path) -> Result<StrategyResult> {
+    fs::create_dir_all(out_dir)?;
+    let start = Instant::now();
+    let pb = progress_bar(names.len());
+    let mut size = 0;
+
+    for name in names {
+        let mut input = BufReader::new(File::open(output_dir().join(name))?);
+        let output_path = out_dir.join(format!("{}.{}", name, codec.extension()));
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut output = BufWriter::new(File::create(&output_path)?);
+        let mut encoder = codec.encoder(&mut output)?;
+        std::io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?;
+        output.flush()?;
+        size += fs::metadata(&output_path)?.len();
+        pb.inc(1);
+    }
+    pb.finish_with_message(format!("Per-file {} complete!", codec.name()));
+
+    Ok(StrategyResult { label: format!("per-file {}", codec.name()), size, objects: names.len() as u64, time: start.elapsed() })
+}
+
+/// Packs all files into a tarball and compresses it as one stream.
+fn tarball(codec: &dyn Codec, names: &[String], out_dir: &Path) -> Result<StrategyResult> {
+    let start = Instant::now();
+    let pb = progress_bar(names.len());
+    let tar_path = out_dir.join(format!("all_logs.tar.{}", codec.extension()));
+
+    let mut output = BufWriter::new(File::create(&tar_path)?);
+    let mut encoder = codec.encoder(&mut output)?;
+    {
+        let mut builder = tar::Builder::new(&mut encoder);
+        for name in names {
+            builder.append_path_with_name(output_dir().join(name), name)?;
+            pb.inc(1);
+        }
+        builder.finish()?;
+    }
+    encoder.finish()?;
+    output.flush()?;
+    pb.finish_with_message(format!("tar + {} complete!", codec.name()));
+
+    let size = fs::metadata(&tar_path)?.len();
+    Ok(StrategyResult { label: format!("tar + {}", codec.name()), size, objects: 1, time: start.elapsed() })
+}
+
+/// Writes the project's own solid zstd archive format.
+fn custom_archive(codec: &Zstd, names: &[String], out_dir: &Path) -> Result<StrategyResult> {
+    let start = Instant::now();
+    let pb = progress_bar(names.len());
+    let archive_path = out_dir.join("all_logs.zst");
+    archive::write_archive(output_dir(), names, &[], &archive_path, archive::Options::level(codec.level), &pb)?;
+    pb.finish_with_message("Custom archive complete!");
+
+    let size = fs::metadata(&archive_path)?.len();
+    Ok(StrategyResult { label: format!("solid {} (custom)", codec.name()), size, objects: 1, time: start.elapsed() })
+}
+
+fn signed_bytes(delta: i64) -> String {
+    let sign = if delta < 0 { "-" } else { "+" };
+    format!("{}{}", sign, format_bytes(delta.unsigned_abs()))
+}
+
+fn print_effect(label: &str, from: u64, to: u64) {
+    let delta = to as i64 - from as i64;
+    println!("  {:<44} {:>12} ({})", label, signed_bytes(delta), render::change(to as f64, from as f64));
+}
+
+/// Compares the strategies, counting `object_overhead` bytes per stored object when given.
+pub fn run(names: &[String], object_overhead: Option<u64>) -> Result<()> {
+    println!("🚀 Starting archiving strategy comparison");
+    println!("Generating {} fake JSON files...", names.len());
+
+    println!("\n📝 Step 1: Generating JSON files");
+    generate_corpus(names, rand::random())?;
+    let json_size = corpus_size(names)?;
+
+    let out_dir = output_dir().join(STRATEGY_DIR);
+    if out_dir.exists() {
+        fs::remove_dir_all(&out_dir)?;
+    }
+    fs::create_dir_all(&out_dir)?;
+
+    let gzip = Gzip { level: 6 };
+    let zstd = Zstd { level: 3 };
+
+    println!("\n🗜️  Step 2: Per-file gzip");
+    let per_file_gzip = per_file(&gzip, names, &out_dir.join("per_file_gz"))?;
+    println!("\n🗜️  Step 3: Per-file zstd");
+    let per_file_zstd = per_file(&zstd, names, &out_dir.join("per_file_zst"))?;
+    println!("\n🗜️  Step 4: tar.gz");
+    let tar_gzip = tarball(&gzip, names, &out_dir)?;
+    println!("\n🗜️  Step 5: tar.zst");
+    let tar_zstd = tarball(&zstd, names, &out_dir)?;
+    println!("\n🗜️  Step 6: Solid zstd archive");
+    let solid_zstd = custom_archive(&zstd, names, &out_dir)?;
+
+    println!("\n📊 ARCHIVING STRATEGY COMPARISON");
+    println!("=====================================");
+    println!("Original JSON files: {} in {} files", format_bytes(json_size), names.len());
+    println!();
+    let mut table = Table::new(&[("Strategy", Align::Left), ("Size", Align::Right), ("Ratio", Align::Right), ("Time", Align::Right)]);
+    for result in [&per_file_gzip, &per_file_zstd, &tar_gzip, &tar_zstd, &solid_zstd] {
+        table.row(vec![
+            result.label.clone(),
+            format_bytes(result.size),
+            render::percent(result.size as f64 / json_size as f64),
+            render::duration(result.time),
+        ]);
+    }
+    print!("{}", table.terminal());
+    println!();
+    let mut grid = Table::new(&[("", Align::Left), ("per-file", Align::Right), ("solid (tar)", Align::Right)]);
+    grid.row(vec!["gzip".to_string(), format_bytes(per_file_gzip.size), format_bytes(tar_gzip.size)]);
+    grid.row(vec!["zstd".to_string(), format_bytes(per_file_zstd.size), format_bytes(tar_zstd.size)]);
+    print!("{}", grid.terminal());
+    let overhead = object_overhead.unwrap_or(0);
+    if let Some(overhead) = object_overhead {
+        println!();
+        println!("📦 Effective size with {} per object (--object-overhead):", format_bytes(overhead));
+        let stored: Vec<Stored> = [&per_file_gzip, &per_file_zstd, &tar_gzip, &tar_zstd, &solid_zstd].iter().map(|result| result.stored()).collect();
+        overhead::print(&stored, overhead);
+    }
+    let [per_file_gzip, per_file_zstd, tar_gzip, tar_zstd] =
+        [&per_file_gzip, &per_file_zstd, &tar_gzip, &tar_zstd].map(|result| result.stored().effective(overhead));
+    println!();
+    println!("🔍 EFFECT ATTRIBUTION{}:", if object_overhead.is_some() { " (effective sizes)" } else { "" });
+    print_effect("Codec effect (zstd vs gzip, per-file)", per_file_gzip, per_file_zstd);
+    print_effect("Codec effect (zstd vs gzip, solid)", tar_gzip, tar_zstd);
+    print_effect("Container effect (solid vs per-file, gzip)", per_file_gzip, tar_gzip);
+    print_effect("Container effect (solid vs per-file, zstd)", per_file_zstd, tar_zstd);
+    let codec_effect = per_file_zstd as i64 - per_file_gzip as i64;
+    let container_effect = tar_gzip as i64 - per_file_gzip as i64;
+    let total = tar_zstd as i64 - per_file_gzip as i64;
+    let interaction = total - codec_effect - container_effect;
+    println!();
+    println!("  per-file gzip → tar + zstd: {}", signed_bytes(total));
+    println!("    = codec {} + container {} + interaction {}", signed_bytes(codec_effect), signed_bytes(container_effect), signed_bytes(interaction));
+
+    println!("\n✅ Archiving strategy comparison complete!");
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Compact Markdown summary written with `--summary`, meant to be posted as a PR
+//! comment or CI job summary. Deltas are relative to a previous `--json` run.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use anyhow::Result;
+use crate::bench::throughput_per_core;
+use crate::format_bytes;
+use crate::render::{self, Align, Table};
+use crate::results::Results;
+
+/// Relative change from `old` to `new`, or a dash when there is nothing to compare.
+fn delta(new: f64, old: Option<f64>) -> String {
+    render::change(new, old.unwrap_or(0.0))
+}
+
+fn render(results: &Results, baseline: Option<&Results>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "### gz-vs-zstd: {} files, {}",
+        results.corpus.files,
+        format_bytes(results.corpus.bytes)
+    );
+    out.push('\n');
+    if results.partial {
+        out.push_str("⚠️ Partial run: stopped by Ctrl-C, so later steps are missing.\n\n");
+    }
+    if let Some(stats) = &results.corpus.stats {
+        let _ = writeln!(out, "Corpus: {}.", stats);
+        out.push('\n');
+    }
+    match baseline {
+        Some(baseline) => {
+            let _ = writeln!(out, "Δ relative to the run from {}.", baseline.generated_at);
+        }
+        None => out.push_str("No baseline given, so no deltas.\n"),
+    }
+    out.push('\n');
+
+    let mut stages = Table::new(&[
+        ("Stage", Align::Left),
+        ("Codec", Align::Left),
+        ("Size", Align::Right),
+        ("Ratio", Align::Right),
+        ("Time", Align::Right),
+        ("Per core", Align::Right),
+        ("Δ size", Align::Right),
+        ("Δ time", Align::Right),
+    ]);
+    for stage in &results.stages {
+        // Sizes and ratios refer to the compressed side, whichever direction the stage ran in.
+        let compressed = stage.bytes_in.min(stage.bytes_out);
+        let uncompressed = stage.bytes_in.max(stage.bytes_out);
+        let old = baseline.and_then(|baseline| baseline.stages.iter().find(|old| old.name == stage.name));
+        stages.row(vec![
+            stage.name.clone(),
+            stage.codec.clone(),
+            format_bytes(compressed),
+            render::percent(compressed as f64 / uncompressed as f64),
+            render::seconds(stage.secs),
+            render::mb_per_sec(throughput_per_core(uncompressed, Duration::from_secs_f64(stage.secs), stage.threads())),
+            delta(compressed as f64, old.map(|old| old.bytes_in.min(old.bytes_out) as f64)),
+            delta(stage.secs, old.map(|old| old.secs)),
+        ]);
+    }
+    out.push_str(&stages.markdown());
+
+    if let Some(overhead) = results.object_overhead {
+        let mut effective = Table::new(&[
+            ("Stage", Align::Left),
+            ("Objects", Align::Right),
+            ("Payload", Align::Right),
+            ("Effective", Align::Right),
+        ]);
+        for stage in &results.stages {
+            if let (Some(objects), Some(bytes)) = (stage.objects, stage.effective_bytes(overhead)) {
+                effective.row(vec![stage.name.clone(), objects.to_string(), format_bytes(stage.bytes_out), format_bytes(bytes)]);
+            }
+        }
+        out.push('\n');
+        let _ = writeln!(out, "Effective sizes count {} per stored object.", format_bytes(overhead));
+        out.push('\n');
+        out.push_str(&effective.markdown());
+    }
+
+    if !results.matrix.is_empty() {
+        let mut matrix = Table::new(&[
+            ("Codec", Align::Left),
+            ("Size", Align::Right),
+            ("Ratio", Align::Right),
+            ("Compress", Align::Right),
+            ("Decompress", Align::Right),
+            ("Δ size", Align::Right),
+            ("Δ compress", Align::Right),
+        ]);
+        for cell in &results.matrix {
+            let old = baseline.and_then(|baseline| baseline.matrix.iter().find(|old| old.codec == cell.codec));
+            matrix.row(vec![
+                cell.codec.clone(),
+                format_bytes(cell.compressed_bytes),
+                render::percent(cell.ratio),
+                render::seconds(cell.compress_secs),
+                render::seconds(cell.decompress_secs),
+                delta(cell.compressed_bytes as f64, old.map(|old| old.compressed_bytes as f64)),
+                delta(cell.compress_secs, old.map(|old| old.compress_secs)),
+            ]);
+        }
+        out.push('\n');
+        out.push_str(&matrix.markdown());
+    }
+    if !results.timeouts.is_empty() {
+        out.push('\n');
+        for timed_out in &results.timeouts {
+            let _ = writeln!(out, "⏱️ {}", timed_out);
+        }
+    }
+    if !results.failures.is_empty() {
+        out.push('\n');
+        for failure in &results.failures {
+            let _ = writeln!(out, "💥 {}", failure);
+        }
+    }
+    out
+}
+
+pub fn write(path: &Path, results: &Results, baseline: Option<&Results>) -> Result<()> {
+    fs::write(path, render(results, baseline))?;
+    Ok(())
+}
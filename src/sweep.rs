@@ -0,0 +1,158 @@
+//! Level sweeps: every configuration of a codec family over the same corpus,
+//! so the report shows the whole speed/ratio curve rather than a single point.
+
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use crate::bench::{throughput, Measurement};
+use crate::codec::{Codec, Family};
+use crate::render::{Align, Table};
+use crate::results::MatrixCell;
+use crate::watchdog::Watchdog;
+use crate::{format_bytes, interrupt, progress_bar, render};
+
+/// Family-specific dimensions swept in addition to the level.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SweepOptions {
+    #[cfg(feature = "brotli")]
+    pub brotli_lgwin: Vec<u32>,
+    #[cfg(feature = "lz4")]
+    pub lz4_modes: Vec<crate::codec::Lz4Mode>,
+    #[cfg(feature = "lz4")]
+    pub lz4_acceleration: Vec<u32>,
+}
+
+/// Acceleration factors covered by an lz4 sweep unless overridden.
+#[cfg(feature = "lz4")]
+const DEFAULT_LZ4_ACCELERATION: [u32; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+/// Every codec configuration visited when sweeping `family`.
+#[cfg_attr(not(any(feature = "brotli", feature = "lz4")), allow(unused_variables))]
+pub fn codecs(family: Family, options: &SweepOptions) -> Vec<Box<dyn Codec>> {
+    match family {
+        #[cfg(feature = "brotli")]
+        Family::Brotli => {
+            use crate::codec::Brotli;
+            let windows = if options.brotli_lgwin.is_empty() { vec![Brotli::DEFAULT_LGWIN] } else { options.brotli_lgwin.clone() };
+            windows
+                .into_iter()
+                .flat_map(|lgwin| family.levels().map(move |quality| Box::new(Brotli { quality: quality as u32, lgwin }) as Box<dyn Codec>))
+                .collect()
+        }
+        #[cfg(feature = "lz4")]
+        Family::Lz4 => {
+            use crate::codec::{Lz4, Lz4Mode};
+            let modes = if options.lz4_modes.is_empty() { vec![Lz4Mode::Frame, Lz4Mode::Block] } else { options.lz4_modes.clone() };
+            let factors = if options.lz4_acceleration.is_empty() { DEFAULT_LZ4_ACCELERATION.to_vec() } else { options.lz4_acceleration.clone() };
+            modes
+                .into_iter()
+                .flat_map(|mode| factors.iter().map(move |&acceleration| Box::new(Lz4 { mode, acceleration }) as Box<dyn Codec>))
+                .collect()
+        }
+        #[cfg(feature = "xz")]
+        Family::Xz => {
+            use crate::codec::Xz;
+            [false, true]
+                .into_iter()
+                .flat_map(|extreme| family.levels().map(move |preset| Box::new(Xz { preset: preset as u32, extreme }) as Box<dyn Codec>))
+                .collect()
+        }
+        _ => family.levels().map(|level| family.build(level)).collect(),
+    }
+}
+
+pub struct SweepResult {
+    pub family: Family,
+    pub cells: Vec<(String, Measurement)>,
+}
+
+/// Measures every configuration of `families`, leaving out any that `watchdog` gives up on.
+pub fn run(families: &[Family], options: &SweepOptions, files: &Arc<Vec<Vec<u8>>>, watchdog: &mut Watchdog) -> Result<Vec<SweepResult>> {
+    let plan: Vec<(Family, Vec<Box<dyn Codec>>)> = families.iter().map(|&f| (f, codecs(f, options))).collect();
+    let pb = progress_bar(plan.iter().map(|(_, codecs)| codecs.len()).sum());
+
+    let mut results = Vec::with_capacity(plan.len());
+    for (family, codecs) in plan {
+        let mut cells = Vec::with_capacity(codecs.len());
+        for codec in codecs {
+            if interrupt::requested() {
+                break;
+            }
+            pb.set_message(codec.name());
+            let name = codec.name();
+            if let Some(measurement) = watchdog.measure("sweep", codec, files, &pb)? {
+                cells.push((name, measurement));
+            }
+            pb.inc(1);
+        }
+        results.push(SweepResult { family, cells });
+    }
+    interrupt::finish(&pb, "Level sweep complete!");
+
+    Ok(results)
+}
+
+/// Every measured configuration as results cells.
+pub fn cells(results: &[SweepResult], json_size: u64) -> Vec<MatrixCell> {
+    results
+        .iter()
+        .flat_map(|result| result.cells.iter().map(|(name, measurement)| MatrixCell::new(&result.family.to_string(), name.clone(), measurement, json_size)))
+        .collect()
+}
+
+/// A configuration's name and throughput in MB/s.
+type Rated<'a> = (&'a str, f64);
+
+/// Slowest and fastest configuration by the time `time` picks, with their throughput.
+fn extremes(cells: &[(String, Measurement)], json_size: u64, time: fn(&Measurement) -> Duration) -> Option<(Rated<'_>, Rated<'_>)> {
+    let rated = cells.iter().map(|(name, measurement)| (name.as_str(), throughput(json_size, time(measurement))));
+    let slowest = rated.clone().min_by(|a, b| a.1.total_cmp(&b.1))?;
+    let fastest = rated.max_by(|a, b| a.1.total_cmp(&b.1))?;
+    Some((slowest, fastest))
+}
+
+pub fn print(results: &[SweepResult], json_size: u64) {
+    for result in results {
+        println!();
+        println!("{} level sweep (per-file, in memory):", result.family);
+        let with_memory = result.cells.iter().any(|(_, m)| m.memory.is_some());
+        let mut columns = vec![
+            ("Codec", Align::Left),
+            ("Size", Align::Right),
+            ("Ratio", Align::Right),
+            ("Compress", Align::Right),
+            ("Decompress", Align::Right),
+        ];
+        if with_memory {
+            columns.extend([("Enc memory", Align::Right), ("Dec memory", Align::Right)]);
+        }
+        let mut table = Table::new(&columns);
+        for (name, measurement) in &result.cells {
+            let mut row = vec![
+                name.clone(),
+                format_bytes(measurement.compressed_size),
+                render::percent(measurement.compressed_size as f64 / json_size as f64),
+                render::mb_per_sec(throughput(json_size, measurement.compress_time)),
+                render::mb_per_sec(throughput(json_size, measurement.decompress_time)),
+            ];
+            if let Some(memory) = measurement.memory {
+                row.extend([format_bytes(memory.compress), format_bytes(memory.decompress)]);
+            }
+            table.row(row);
+        }
+        print!("{}", table.terminal());
+        // Decoding speed depends on how the data was encoded, but far less than encoding speed does.
+        for (label, time) in [
+            ("Compression", (|m| m.compress_time) as fn(&Measurement) -> Duration),
+            ("Decompression", |m| m.decompress_time),
+        ] {
+            if let Some(((slow_name, slow), (fast_name, fast))) = extremes(&result.cells, json_size, time) {
+                println!(
+                    "  {} spread: {} ({}) to {} ({}), {:.1}x",
+                    label, render::mb_per_sec(slow), slow_name, render::mb_per_sec(fast), fast_name, fast / slow
+                );
+            }
+        }
+    }
+}
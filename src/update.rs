@@ -0,0 +1,188 @@
+//! `archive update`: the nightly-backup pattern. Compares a directory against
+//! the manifest of an existing archive and compresses only the new and changed
+//! files into an incremental archive next to it.
+//!
+//! Every archive of a chain has a `<archive>.manifest.json` sidecar listing
+//! each file of the directory as of that archive, with its size, xxh3 checksum
+//! and the archive of the chain that holds its content. An increment's manifest
+//! is therefore the combined index of the base and every increment so far;
+//! files removed from the directory drop out of it. A base archive without a
+//! manifest, e.g. one kept with `--archive-path`, is read once to build one.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use twox_hash::XxHash3_64;
+use crate::{archive, format_bytes, plan, progress_bar, render};
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    /// File names of the archives in the chain, base first, all in the manifest's directory.
+    pub archives: Vec<String>,
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub size: u64,
+    pub xxh3: u64,
+    /// Position in `archives` of the archive holding this version of the file.
+    pub archive: usize,
+}
+
+pub struct Options<'a> {
+    pub level: i32,
+    pub dict: Option<&'a [u8]>,
+}
+
+/// Path of the manifest that goes with `archive_path`.
+pub fn manifest_path(archive_path: &Path) -> PathBuf {
+    let mut name = archive_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".manifest.json");
+    archive_path.with_file_name(name)
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .with_context(|| format!("{} has no UTF-8 file name", path.display()))
+}
+
+/// Lists the regular-file entries of an archive that has no manifest yet.
+fn scan(archive_path: &Path, dict: Option<&[u8]>) -> Result<Manifest> {
+    let mut files = BTreeMap::new();
+    let mut buf = Vec::new();
+    archive::for_each_entry(archive_path, dict, true, |entry, content| {
+        if !entry.is_symlink {
+            buf.clear();
+            content.read_to_end(&mut buf)?;
+            files.insert(entry.name.clone(), ManifestEntry { size: buf.len() as u64, xxh3: XxHash3_64::oneshot(&buf), archive: 0 });
+        }
+        Ok(())
+    })?;
+    Ok(Manifest { archives: vec![file_name(archive_path)?], files })
+}
+
+/// Loads the manifest of `archive_path`, building and saving it first if there is none.
+fn load(archive_path: &Path, dict: Option<&[u8]>) -> Result<Manifest> {
+    let path = manifest_path(archive_path);
+    if path.exists() {
+        let data = fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        let manifest: Manifest = serde_json::from_slice(&data).with_context(|| format!("invalid manifest {}", path.display()))?;
+        ensure!(
+            manifest.archives.last().map(String::as_str) == Some(file_name(archive_path)?.as_str()),
+            "{} does not describe {}; update from the latest archive of its chain",
+            path.display(),
+            archive_path.display()
+        );
+        return Ok(manifest);
+    }
+    println!("📋 {} has no manifest; reading it to build one", archive_path.display());
+    let manifest = scan(archive_path, dict)?;
+    fs::write(&path, serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+#[derive(Default)]
+struct Tally {
+    files: usize,
+    bytes: u64,
+}
+
+impl Tally {
+    fn add(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Compresses the files of `input` that are not in `base`'s manifest as they are now into `output`,
+/// and writes the combined manifest of the chain next to it.
+pub fn run(base: &Path, input: &Path, output: &Path, options: &Options) -> Result<()> {
+    let same_dir = |path: &Path| fs::canonicalize(path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new(".")));
+    ensure!(
+        same_dir(base)? == same_dir(output)?,
+        "the increment must be written next to {} so the manifest can name it",
+        base.display()
+    );
+    ensure!(!output.exists(), "{} already exists", output.display());
+    let previous = load(base, options.dict)?;
+
+    let start = Instant::now();
+    let files = plan::file_sizes(input)?;
+    let pb = progress_bar(files.len());
+    pb.set_message("Comparing");
+    let new_index = previous.archives.len();
+    let mut archives = previous.archives.clone();
+    archives.push(file_name(output)?);
+    let mut manifest = Manifest { archives, files: BTreeMap::new() };
+    let (mut unchanged, mut changed, mut added) = (Tally::default(), Tally::default(), Tally::default());
+    let mut names = Vec::new();
+    for (path, size) in &files {
+        let name = path.strip_prefix(input)?.to_string_lossy().replace('\\', "/");
+        let known = previous.files.get(&name);
+        let xxh3 = XxHash3_64::oneshot(&fs::read(path)?);
+        match known {
+            Some(entry) if entry.size == *size && entry.xxh3 == xxh3 => {
+                unchanged.add(*size);
+                manifest.files.insert(name, *entry);
+            }
+            _ => {
+                if known.is_some() { changed.add(*size) } else { added.add(*size) }
+                manifest.files.insert(name.clone(), ManifestEntry { size: *size, xxh3, archive: new_index });
+                names.push(name);
+            }
+        }
+        pb.inc(1);
+    }
+    pb.finish_with_message("Comparison complete!");
+    let removed = previous.files.keys().filter(|name| !manifest.files.contains_key(*name)).count();
+    let compare_time = start.elapsed();
+
+    let start = Instant::now();
+    let pb = progress_bar(names.len());
+    let archive_options = archive::Options { dict: options.dict, ..archive::Options::level(options.level) };
+    archive::write_archive(input, &names, &[], output, archive_options, &pb)?;
+    pb.finish_with_message("Incremental archive complete!");
+    let compress_time = start.elapsed();
+    let manifest_path = manifest_path(output);
+    fs::write(&manifest_path, serde_json::to_vec_pretty(&manifest)?)?;
+
+    let increment_size = fs::metadata(output)?.len();
+    let dir = output.parent().unwrap_or(Path::new(""));
+    let full_size = fs::metadata(dir.join(&manifest.archives[0]))?.len();
+    let chain_size = manifest.archives.iter().map(|name| Ok(fs::metadata(dir.join(name))?.len())).sum::<Result<u64>>()?;
+    let percent = |part: u64, whole: u64| render::percent(part as f64 / whole.max(1) as f64);
+
+    println!("\n📊 Incremental update of {}", base.display());
+    println!("  Unchanged: {:>7} files {:>12}", unchanged.files, format_bytes(unchanged.bytes));
+    println!("  Changed:   {:>7} files {:>12}", changed.files, format_bytes(changed.bytes));
+    println!("  New:       {:>7} files {:>12}", added.files, format_bytes(added.bytes));
+    println!("  Removed:   {:>7} files", removed);
+    println!("  Compared in {}, compressed in {}", render::duration(compare_time), render::duration(compress_time));
+    println!();
+    println!("Incremental archive: {}", output.display());
+    if names.is_empty() {
+        println!("  Size: {} (nothing changed)", format_bytes(increment_size));
+    } else {
+        println!(
+            "  Size: {} ({} of the {} that changed)",
+            format_bytes(increment_size),
+            percent(increment_size, changed.bytes + added.bytes),
+            format_bytes(changed.bytes + added.bytes)
+        );
+    }
+    println!("  {} of the {} full archive {}", percent(increment_size, full_size), format_bytes(full_size), manifest.archives[0]);
+    println!(
+        "Combined index: {} ({} files across {} archives, {} in total)",
+        manifest_path.display(),
+        manifest.files.len(),
+        manifest.archives.len(),
+        format_bytes(chain_size)
+    );
+    Ok(())
+}
@@ -0,0 +1,249 @@
+//! Batched file I/O over io_uring, compared against the standard blocking path.
+//!
+//! Each batch opens, reads or writes and closes up to `BATCH` files with one
+//! submission per phase instead of one syscall per file per phase.
+
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use io_uring::{opcode, squeue, types, IoUring};
+use crate::codec::{Codec, Gzip};
+use crate::render::{self, Align, Table};
+use crate::{format_bytes, progress_bar};
+
+const BATCH: usize = 64;
+const INITIAL_READ_BUFFER: usize = 16 * 1024;
+
+/// Submits every entry (user data = index) and waits for all of them to complete.
+///
+/// Entries may reference caller-owned buffers; they stay valid because this
+/// function does not return until the kernel has finished with every entry.
+fn run_batch(ring: &mut IoUring, entries: &[squeue::Entry]) -> Result<Vec<i32>> {
+    let mut results = vec![0; entries.len()];
+    let mut submitted = 0;
+    let mut completed = 0;
+    while completed < entries.len() {
+        {
+            let mut submission = ring.submission();
+            while submitted < entries.len() && !submission.is_full() {
+                // SAFETY: see the function docs for buffer lifetimes.
+                unsafe { submission.push(&entries[submitted]).expect("submission queue has room") };
+                submitted += 1;
+            }
+        }
+        ring.submit_and_wait(1)?;
+        for cqe in ring.completion() {
+            results[cqe.user_data() as usize] = cqe.result();
+            completed += 1;
+        }
+    }
+    Ok(results)
+}
+
+fn check(result: i32) -> io::Result<i32> {
+    if result < 0 { Err(io::Error::from_raw_os_error(-result)) } else { Ok(result) }
+}
+
+fn open_all(ring: &mut IoUring, paths: &[PathBuf], flags: i32) -> Result<Vec<i32>> {
+    let names = paths
+        .iter()
+        .map(|path| CString::new(path.as_os_str().as_bytes()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    let entries: Vec<_> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            opcode::OpenAt::new(types::Fd(libc::AT_FDCWD), name.as_ptr())
+                .flags(flags | libc::O_CLOEXEC)
+                .mode(0o644)
+                .build()
+                .user_data(i as u64)
+        })
+        .collect();
+    let fds = run_batch(ring, &entries)?;
+    fds.into_iter().map(|fd| Ok(check(fd)?)).collect()
+}
+
+fn close_all(ring: &mut IoUring, fds: &[i32]) -> Result<()> {
+    let entries: Vec<_> = fds
+        .iter()
+        .enumerate()
+        .map(|(i, &fd)| opcode::Close::new(types::Fd(fd)).build().user_data(i as u64))
+        .collect();
+    for result in run_batch(ring, &entries)? {
+        check(result)?;
+    }
+    Ok(())
+}
+
+/// Creates (or truncates) each path and writes its contents.
+pub fn write_files(ring: &mut IoUring, files: &[(PathBuf, &[u8])]) -> Result<()> {
+    let paths: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    let fds = open_all(ring, &paths, libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC)?;
+
+    // Resubmit the remainder of any short write until every file is complete.
+    let mut written = vec![0usize; files.len()];
+    loop {
+        let pending: Vec<usize> = (0..files.len()).filter(|&i| written[i] < files[i].1.len()).collect();
+        if pending.is_empty() {
+            break;
+        }
+        let entries: Vec<_> = pending
+            .iter()
+            .enumerate()
+            .map(|(slot, &i)| {
+                let rest = &files[i].1[written[i]..];
+                opcode::Write::new(types::Fd(fds[i]), rest.as_ptr(), rest.len() as u32)
+                    .offset(written[i] as u64)
+                    .build()
+                    .user_data(slot as u64)
+            })
+            .collect();
+        for (slot, result) in run_batch(ring, &entries)?.into_iter().enumerate() {
+            written[pending[slot]] += check(result)? as usize;
+        }
+    }
+
+    close_all(ring, &fds)
+}
+
+/// Reads every path to the end, growing each buffer until the kernel reports EOF.
+pub fn read_files(ring: &mut IoUring, paths: &[PathBuf]) -> Result<Vec<Vec<u8>>> {
+    let fds = open_all(ring, paths, libc::O_RDONLY)?;
+    let mut buffers: Vec<Vec<u8>> = paths.iter().map(|_| Vec::with_capacity(INITIAL_READ_BUFFER)).collect();
+    let mut done = vec![false; paths.len()];
+
+    loop {
+        let pending: Vec<usize> = (0..paths.len()).filter(|&i| !done[i]).collect();
+        if pending.is_empty() {
+            break;
+        }
+        let entries: Vec<_> = pending
+            .iter()
+            .enumerate()
+            .map(|(slot, &i)| {
+                let buffer = &mut buffers[i];
+                if buffer.len() == buffer.capacity() {
+                    buffer.reserve(buffer.capacity());
+                }
+                let spare = buffer.spare_capacity_mut();
+                opcode::Read::new(types::Fd(fds[i]), spare.as_mut_ptr().cast(), spare.len() as u32)
+                    .offset(buffer.len() as u64)
+                    .build()
+                    .user_data(slot as u64)
+            })
+            .collect();
+        for (slot, result) in run_batch(ring, &entries)?.into_iter().enumerate() {
+            let i = pending[slot];
+            let read = check(result)? as usize;
+            if read == 0 {
+                done[i] = true;
+            } else {
+                let buffer = &mut buffers[i];
+                let len = buffer.len() + read;
+                // SAFETY: the kernel initialised `read` bytes of spare capacity.
+                unsafe { buffer.set_len(len) };
+            }
+        }
+    }
+
+    close_all(ring, &fds)?;
+    Ok(buffers)
+}
+
+pub struct IoComparison {
+    pub bytes: u64,
+    pub blocking_write: Duration,
+    pub uring_write: Duration,
+    pub blocking_compress: Duration,
+    pub uring_compress: Duration,
+}
+
+/// Writes `docs` and then gzip-compresses them file by file, once with `std::fs`
+/// and once with batched io_uring, into separate directories under `dir`.
+pub fn run(dir: &Path, names: &[String], docs: &[Vec<u8>]) -> Result<IoComparison> {
+    let blocking_dir = dir.join("blocking");
+    let uring_dir = dir.join("uring");
+    for path in [&blocking_dir, &uring_dir] {
+        if path.exists() {
+            fs::remove_dir_all(path)?;
+        }
+        fs::create_dir_all(path)?;
+        // Directories for nested input names are created up front so they stay out of the timings.
+        for name in names {
+            if let Some(parent) = path.join(name).parent() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+    }
+    let codec = Gzip { level: 6 };
+    let mut ring = IoUring::new(BATCH as u32 * 2)?;
+    let pb = progress_bar(names.len() * 4);
+
+    let start = Instant::now();
+    for (name, doc) in names.iter().zip(docs) {
+        fs::write(blocking_dir.join(name), doc)?;
+        pb.inc(1);
+    }
+    let blocking_write = start.elapsed();
+
+    let start = Instant::now();
+    for (names, docs) in names.chunks(BATCH).zip(docs.chunks(BATCH)) {
+        let files: Vec<_> = names.iter().map(|name| uring_dir.join(name)).zip(docs.iter().map(Vec::as_slice)).collect();
+        write_files(&mut ring, &files)?;
+        pb.inc(names.len() as u64);
+    }
+    let uring_write = start.elapsed();
+
+    let start = Instant::now();
+    for name in names {
+        let data = fs::read(blocking_dir.join(name))?;
+        fs::write(blocking_dir.join(format!("{}.gz", name)), codec.compress_bytes(&data)?)?;
+        pb.inc(1);
+    }
+    let blocking_compress = start.elapsed();
+
+    let start = Instant::now();
+    for names in names.chunks(BATCH) {
+        let paths: Vec<PathBuf> = names.iter().map(|name| uring_dir.join(name)).collect();
+        let outputs = read_files(&mut ring, &paths)?
+            .iter()
+            .zip(names)
+            .map(|(data, name)| Ok((uring_dir.join(format!("{}.gz", name)), codec.compress_bytes(data)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let outputs: Vec<_> = outputs.iter().map(|(path, data)| (path.clone(), data.as_slice())).collect();
+        write_files(&mut ring, &outputs)?;
+        pb.inc(names.len() as u64);
+    }
+    let uring_compress = start.elapsed();
+    pb.finish_with_message("I/O backend comparison complete!");
+
+    Ok(IoComparison {
+        bytes: docs.iter().map(|doc| doc.len() as u64).sum(),
+        blocking_write,
+        uring_write,
+        blocking_compress,
+        uring_compress,
+    })
+}
+
+pub fn print(comparison: &IoComparison) {
+    println!("  Corpus: {}", format_bytes(comparison.bytes));
+    let mut table = Table::new(&[("Stage", Align::Left), ("Blocking", Align::Right), ("io_uring", Align::Right), ("Speedup", Align::Right)]);
+    for (stage, blocking, uring) in [
+        ("Write JSON files", comparison.blocking_write, comparison.uring_write),
+        ("Per-file gzip", comparison.blocking_compress, comparison.uring_compress),
+    ] {
+        table.row(vec![
+            stage.to_string(),
+            render::duration(blocking),
+            render::duration(uring),
+            format!("{:.2}x", blocking.as_secs_f64() / uring.as_secs_f64()),
+        ]);
+    }
+    print!("{}", table.terminal());
+}
@@ -0,0 +1,119 @@
+//! `validate`: checks a `--json` results file against the schema this build
+//! writes, and optionally rewrites an older one in the current version.
+//!
+//! A file conforms when it migrates to [`SCHEMA_VERSION`], every field has the
+//! type the results types expect, no field is unknown to them, `generated_at`
+//! is an RFC 3339 timestamp and no time is negative. Unknown fields are errors
+//! rather than warnings: they usually mean a script is reading a field that a
+//! later version renamed.
+
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use crate::artifact;
+use crate::results::{self, Results, SCHEMA_VERSION};
+
+/// Paths of fields in `original` that did not survive a round trip through the results types.
+fn unknown_fields(original: &Value, parsed: &Value, path: &str, found: &mut Vec<String>) {
+    match (original, parsed) {
+        (Value::Object(original), Value::Object(parsed)) => {
+            for (key, value) in original {
+                let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match parsed.get(key) {
+                    Some(parsed) => unknown_fields(value, parsed, &field, found),
+                    // Empty lists and nulls are left out when written, so they are not unknown.
+                    None if value.is_null() || value.as_array().is_some_and(Vec::is_empty) => {}
+                    None => found.push(field),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(parsed)) => {
+            for (i, (value, parsed)) in original.iter().zip(parsed).enumerate() {
+                unknown_fields(value, parsed, &format!("{}[{}]", path, i), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Paths of time fields (`secs` and `*_secs`) holding a negative number.
+fn negative_times(value: &Value, path: &str, found: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                let is_time = key == "secs" || key.ends_with("_secs");
+                if is_time && value.as_f64().is_some_and(|secs| secs < 0.0) {
+                    found.push(field);
+                } else {
+                    negative_times(value, &field, found);
+                }
+            }
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                negative_times(value, &format!("{}[{}]", path, i), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Validates `path`, rewriting it in the current schema version with `migrate` if it conforms.
+/// Returns whether it conforms.
+pub fn run(path: &Path, migrate: bool) -> Result<bool> {
+    let stored = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let compression = artifact::Compression::detect(&stored);
+    let text = artifact::decode(stored).with_context(|| format!("failed to decompress {}", path.display()))?;
+    let original: Value = serde_json::from_slice(&text).with_context(|| format!("{} is not JSON", path.display()))?;
+    let mut value = original.clone();
+    let version = match results::migrate(&mut value) {
+        Ok(version) => version,
+        Err(err) => {
+            println!("❌ {}: {:#}", path.display(), err);
+            return Ok(false);
+        }
+    };
+    let parsed: Results = match serde_json::from_value(value.clone()) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            println!("❌ {}: does not match schema version {}: {}", path.display(), SCHEMA_VERSION, err);
+            return Ok(false);
+        }
+    };
+
+    let mut problems = Vec::new();
+    let mut unknown = Vec::new();
+    unknown_fields(&value, &serde_json::to_value(&parsed)?, "", &mut unknown);
+    problems.extend(unknown.into_iter().map(|field| format!("unknown field `{}`", field)));
+    if chrono::DateTime::parse_from_rfc3339(&parsed.generated_at).is_err() {
+        problems.push(format!("generated_at `{}` is not an RFC 3339 timestamp", parsed.generated_at));
+    }
+    let mut negative = Vec::new();
+    negative_times(&original, "", &mut negative);
+    problems.extend(negative.into_iter().map(|field| format!("`{}` is a negative time", field)));
+
+    if problems.is_empty() {
+        if version == SCHEMA_VERSION {
+            println!("✅ {}: conforms to schema version {}", path.display(), SCHEMA_VERSION);
+        } else {
+            println!("✅ {}: schema version {}, conforms once migrated to version {}", path.display(), version, SCHEMA_VERSION);
+        }
+    } else {
+        println!("❌ {}: schema version {}, {} problem{}", path.display(), version, problems.len(), if problems.len() == 1 { "" } else { "s" });
+        for problem in &problems {
+            println!("  {}", problem);
+        }
+    }
+
+    // A file that does not conform is left as it is rather than half fixed.
+    if migrate && version < SCHEMA_VERSION && problems.is_empty() {
+        // Compressed the way the original was, whatever the temporary file is called.
+        let temp = path.with_extension("tmp");
+        artifact::write_with(&temp, &serde_json::to_vec_pretty(&value)?, compression)?;
+        fs::rename(&temp, path)?;
+        println!("💾 Migrated {} from schema version {} to {}", path.display(), version, SCHEMA_VERSION);
+    }
+    Ok(problems.is_empty())
+}
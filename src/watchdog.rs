@@ -0,0 +1,113 @@
+//! `--stage-timeout` and `--isolate`: a watchdog for the steps that measure many
+//! codecs in turn, `--sweep` and `--matrix`. A codec that overruns the limit is
+//! recorded as timed out with how far it got, and one whose `--isolate` child
+//! process crashes is recorded as failed; either way the step goes on with the
+//! next codec instead of hanging or taking down the whole run.
+//!
+//! Without `--isolate` each codec runs on a thread of its own. A thread cannot
+//! be stopped from outside, so an abandoned measurement keeps running in the
+//! background until it finishes or the process exits. It still takes a core
+//! meanwhile, which later timings on a busy machine will show. An isolated
+//! child that overruns is killed instead.
+//!
+//! Measurements found in the `--cache` are reused without running the codec at all.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use anyhow::{bail, Result};
+use indicatif::ProgressBar;
+use crate::bench::{self, Measurement};
+use crate::cache::Cache;
+use crate::codec::Codec;
+use crate::{interrupt, isolate};
+use crate::results::{Failure, TimedOut};
+
+/// How one measurement ended.
+pub enum Outcome {
+    Measured(Measurement),
+    /// Overran the limit after counting `progress` files, compressed and then decompressed.
+    TimedOut { progress: usize },
+    /// The isolated child died without reporting a measurement.
+    Failed { reason: String, stderr: String },
+}
+
+pub struct Watchdog {
+    pub limit: Option<Duration>,
+    /// The corpus `--isolate` children load; codecs run in this process when `None`.
+    pub isolate: Option<isolate::Corpus>,
+    /// Earlier measurements of the same corpus; `None` with `--no-cache`.
+    pub cache: Option<Cache>,
+    pub timeouts: Vec<TimedOut>,
+    pub failures: Vec<Failure>,
+}
+
+impl Watchdog {
+    pub fn new(limit: Option<Duration>, isolate: Option<isolate::Corpus>, cache: Option<Cache>) -> Self {
+        Watchdog { limit, isolate, cache, timeouts: Vec::new(), failures: Vec::new() }
+    }
+
+    /// Measures `codec` like `bench::measure`, or records why it could not and returns `None`.
+    pub fn measure(&mut self, step: &str, codec: Box<dyn Codec>, files: &Arc<Vec<Vec<u8>>>, pb: &ProgressBar) -> Result<Option<Measurement>> {
+        let name = codec.name();
+        if let Some(measurement) = self.cache.as_mut().and_then(|cache| cache.get(&name)) {
+            return Ok(Some(measurement));
+        }
+        let outcome = match &self.isolate {
+            Some(corpus) => isolate::measure(corpus, &name, self.limit)?,
+            None => in_thread(codec, files, self.limit)?,
+        };
+        match outcome {
+            Outcome::Measured(measurement) => {
+                if let Some(cache) = &mut self.cache {
+                    cache.insert(&name, &measurement)?;
+                }
+                Ok(Some(measurement))
+            }
+            Outcome::TimedOut { progress } => {
+                let (phase, files_done) = if progress < files.len() { ("compress", progress) } else { ("decompress", progress - files.len()) };
+                let timed_out = TimedOut {
+                    step: step.to_string(),
+                    codec: name,
+                    limit_secs: self.limit.unwrap_or_default().as_secs_f64(),
+                    phase: phase.to_string(),
+                    files_done,
+                    files: files.len(),
+                };
+                pb.println(format!("⏱️  {}; moving on", timed_out));
+                self.timeouts.push(timed_out);
+                Ok(None)
+            }
+            // Ctrl-C reaches the isolated child too; that is the run stopping, not the codec failing.
+            Outcome::Failed { .. } if interrupt::requested() => Ok(None),
+            Outcome::Failed { reason, stderr } => {
+                let failure = Failure { step: step.to_string(), codec: name, reason, stderr };
+                pb.println(format!("💥 {}; moving on", failure));
+                self.failures.push(failure);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Measures `codec` on a thread of its own, giving up on it after `limit`.
+fn in_thread(codec: Box<dyn Codec>, files: &Arc<Vec<Vec<u8>>>, limit: Option<Duration>) -> Result<Outcome> {
+    let Some(limit) = limit else {
+        return Ok(Outcome::Measured(bench::measure(codec.as_ref(), files)?));
+    };
+    let name = codec.name();
+    let progress = Arc::new(AtomicUsize::new(0));
+    let (sender, receiver) = mpsc::channel();
+    let (worker_files, worker_progress) = (Arc::clone(files), Arc::clone(&progress));
+    thread::Builder::new().name(format!("measure {}", name)).spawn(move || {
+        // The receiver is gone once the watchdog has given up, so the result has nowhere to go.
+        let _ = sender.send(bench::measure_tracked(codec.as_ref(), &worker_files, &worker_progress));
+    })?;
+    match receiver.recv_timeout(limit) {
+        Ok(measurement) => Ok(Outcome::Measured(measurement?)),
+        Err(RecvTimeoutError::Timeout) => Ok(Outcome::TimedOut { progress: progress.load(Ordering::Relaxed) }),
+        Err(RecvTimeoutError::Disconnected) => bail!("measuring {} panicked", name),
+    }
+}
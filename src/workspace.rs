@@ -0,0 +1,82 @@
+//! Scratch directory holding every intermediate artifact of a run.
+//!
+//! By default the workspace is a temporary directory that is removed when the
+//! guard is dropped (including while unwinding from a panic) or when Ctrl-C ends
+//! the process; `interrupt` covers the press a comparison run handles itself.
+//! `--keep-workspace` uses the persistent `mock_logs/` instead, and
+//! `--output-dir` any other persistent directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use tempfile::TempDir;
+use crate::interrupt;
+
+const PERSISTENT_DIR: &str = "mock_logs";
+
+static OUTPUT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Directory all stages read from and write to.
+pub fn output_dir() -> &'static Path {
+    OUTPUT_DIR.get().map(PathBuf::as_path).unwrap_or(Path::new(PERSISTENT_DIR))
+}
+
+/// Drop guard for the run's workspace.
+pub struct Workspace {
+    temp: Option<TempDir>,
+}
+
+impl Workspace {
+    pub fn create(keep: bool, dir: Option<&Path>) -> Result<Self> {
+        let persistent = dir.or(keep.then_some(Path::new(PERSISTENT_DIR)));
+        let (path, temp) = if let Some(dir) = persistent {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create output directory {}", dir.display()))?;
+            (dir.to_path_buf(), None)
+        } else {
+            // Stay on the current filesystem: a tmpfs /tmp would skew every I/O timing.
+            let temp = tempfile::Builder::new()
+                .prefix("mock_logs-")
+                .tempdir_in(".")
+                .context("failed to create the scratch workspace")?;
+            (temp.path().to_path_buf(), Some(temp))
+        };
+
+        let cleanup = temp.is_some().then(|| path.clone());
+        ctrlc::set_handler(move || {
+            if interrupt::intercept() {
+                return;
+            }
+            if let Some(cleanup) = &cleanup {
+                eprintln!("\n🛑 Interrupted, removing workspace {}", cleanup.display());
+                remove_with_retries(cleanup);
+            }
+            std::process::exit(130);
+        })?;
+        OUTPUT_DIR.set(path).expect("workspace created twice");
+
+        Ok(Workspace { temp })
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        if let Some(temp) = self.temp.take() {
+            remove_with_retries(temp.path());
+        }
+    }
+}
+
+/// The main thread may still be creating files while we delete, so retry a few times.
+fn remove_with_retries(path: &Path) {
+    for _ in 0..5 {
+        match fs::remove_dir_all(path) {
+            Ok(()) => return,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(_) => thread::sleep(Duration::from_millis(50)),
+        }
+    }
+    eprintln!("⚠️  Could not remove workspace {}", path.display());
+}